@@ -3,7 +3,12 @@ use std::fs::create_dir_all;
 
 use cosmwasm_schema::{export_schema, remove_schemas, schema_for};
 
-use umee_cosmwasm::msg::{ExecuteMsg, InstantiateMsg, OwnerResponse, QueryMsg};
+use umee_cosmwasm::msg::{
+  AssignedIdsResponse, BorrowLimitUsedResponse, CachedTvlResponse, CollateralBreakdownResponse,
+  DenomMetadataResponse, ExecuteMsg, FullPositionResponse, InstantiateMsg, IsLiquidatableResponse,
+  MaxWithdrawReplyResponse, MigrateMsg, OwnerResponse, PositionPnlResponse, QueryMsg,
+  RatesResponse, ResolveAliasResponse, SelfPositionResponse, TvlResponse,
+};
 use umee_cosmwasm::state::State;
 
 fn main() {
@@ -13,8 +18,22 @@ fn main() {
   remove_schemas(&out_dir).unwrap();
 
   export_schema(&schema_for!(InstantiateMsg), &out_dir);
+  export_schema(&schema_for!(MigrateMsg), &out_dir);
   export_schema(&schema_for!(ExecuteMsg), &out_dir);
   export_schema(&schema_for!(QueryMsg), &out_dir);
   export_schema(&schema_for!(State), &out_dir);
   export_schema(&schema_for!(OwnerResponse), &out_dir);
+  export_schema(&schema_for!(BorrowLimitUsedResponse), &out_dir);
+  export_schema(&schema_for!(DenomMetadataResponse), &out_dir);
+  export_schema(&schema_for!(IsLiquidatableResponse), &out_dir);
+  export_schema(&schema_for!(MaxWithdrawReplyResponse), &out_dir);
+  export_schema(&schema_for!(TvlResponse), &out_dir);
+  export_schema(&schema_for!(CollateralBreakdownResponse), &out_dir);
+  export_schema(&schema_for!(CachedTvlResponse), &out_dir);
+  export_schema(&schema_for!(RatesResponse), &out_dir);
+  export_schema(&schema_for!(SelfPositionResponse), &out_dir);
+  export_schema(&schema_for!(PositionPnlResponse), &out_dir);
+  export_schema(&schema_for!(AssignedIdsResponse), &out_dir);
+  export_schema(&schema_for!(FullPositionResponse), &out_dir);
+  export_schema(&schema_for!(ResolveAliasResponse), &out_dir);
 }
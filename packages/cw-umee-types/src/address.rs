@@ -0,0 +1,96 @@
+// UMEE_BECH32_PREFIX is the human readable part of the umee account addresses.
+pub const UMEE_BECH32_PREFIX: &str = "umee";
+
+const BECH32_CHARSET: &str = "qpzry9x8gf2tvdw0s3jn54khce6mua7l";
+// bech32 strings are at most 90 characters and end with a 6 characters checksum
+const BECH32_MAX_LEN: usize = 90;
+const BECH32_CHECKSUM_LEN: usize = 6;
+
+// is_umee_address returns true if addr is a bech32 string with the umee
+// prefix and a valid checksum. It doesn't call the api, so it can't tell
+// if the address has the length of a chain account.
+pub fn is_umee_address(addr: &str) -> bool {
+  match bech32_hrp(addr) {
+    Some(hrp) => hrp == UMEE_BECH32_PREFIX,
+    None => false,
+  }
+}
+
+// bech32_hrp returns the human readable part of a bech32 string with a
+// valid checksum, mixed case strings are invalid.
+fn bech32_hrp(addr: &str) -> Option<String> {
+  if addr.len() > BECH32_MAX_LEN || !addr.is_ascii() {
+    return None;
+  }
+  let lower = addr.to_ascii_lowercase();
+  if lower != addr && addr.to_ascii_uppercase() != addr {
+    return None;
+  }
+
+  let (hrp, data) = lower.rsplit_once('1')?;
+  if hrp.is_empty() || data.len() < BECH32_CHECKSUM_LEN {
+    return None;
+  }
+  let mut values: Vec<u8> = hrp.bytes().map(|b| b >> 5).collect();
+  values.push(0);
+  values.extend(hrp.bytes().map(|b| b & 31));
+  for c in data.chars() {
+    values.push(BECH32_CHARSET.find(c)? as u8);
+  }
+  if bech32_polymod(&values) != 1 {
+    return None;
+  }
+  Some(hrp.to_string())
+}
+
+fn bech32_polymod(values: &[u8]) -> u32 {
+  const GENERATORS: [u32; 5] = [0x3b6a57b2, 0x26508e6d, 0x1ea119fa, 0x3d4233dd, 0x2a1462b3];
+  let mut chk: u32 = 1;
+  for value in values {
+    let top = chk >> 25;
+    chk = ((chk & 0x1ffffff) << 5) ^ u32::from(*value);
+    for (i, generator) in GENERATORS.iter().enumerate() {
+      if (top >> i) & 1 == 1 {
+        chk ^= generator;
+      }
+    }
+  }
+  chk
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn umee_addresses() {
+    assert!(is_umee_address(
+      "umee1qyqszqgpqyqszqgpqyqszqgpqyqszqgpq9upfw"
+    ));
+    assert!(is_umee_address(
+      "UMEE1QYQSZQGPQYQSZQGPQYQSZQGPQYQSZQGPQ9UPFW"
+    ));
+
+    // other chains
+    assert!(!is_umee_address(
+      "cosmos1qyqszqgpqyqszqgpqyqszqgpqyqszqgpjnp7du"
+    ));
+    assert!(!is_umee_address(
+      "umeevaloper1qyqszqgpqyqszqgpqyqszqgpqyqszqgpq9upfw"
+    ));
+    // malformed
+    assert!(!is_umee_address(""));
+    assert!(!is_umee_address("umee1"));
+    assert!(!is_umee_address("borrower"));
+    assert!(!is_umee_address(
+      "umee1qyqszqgpqyqszqgpqyqszqgpqyqszqgpq9upfx"
+    ));
+    assert!(!is_umee_address(
+      "umee1qyqszqgpqyqszqgpqyqszqgpqyqszqgpq9upfW"
+    ));
+    assert!(!is_umee_address(
+      "umee1qyqszqgpqyqszqgpqyqszqgpqyqszqgpq9upbw"
+    ));
+    assert!(!is_umee_address("umee1ümee"));
+  }
+}
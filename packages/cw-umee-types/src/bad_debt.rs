@@ -6,3 +6,15 @@ pub struct BadDebt {
   address: String,
   denom: String,
 }
+
+impl BadDebt {
+  // address returns the borrower of the bad debt.
+  pub fn address(&self) -> &str {
+    &self.address
+  }
+
+  // denom returns the base denom of the bad debt.
+  pub fn denom(&self) -> &str {
+    &self.denom
+  }
+}
@@ -9,6 +9,21 @@ pub enum ContractError {
   #[error("Unauthorized")]
   Unauthorized {},
 
+  #[error("Contract already instantiated")]
+  AlreadyInstantiated {},
+
+  #[error("Umee native modules unavailable: {reason}")]
+  UmeeUnavailable { reason: String },
+
+  #[error("Invalid denom {denom}: {reason}")]
+  InvalidDenom { denom: String, reason: String },
+
+  #[error("Invalid address {address}: {reason}")]
+  InvalidAddress { address: String, reason: String },
+
+  #[error("Zero amount of {denom}")]
+  ZeroAmount { denom: String },
+
   #[error("Custom Error val: {val:?}")]
   CustomError { val: String },
   // Add any other custom errors you like here.
@@ -1,53 +1,112 @@
+// cw-umee-types holds the types to query and message the umee native
+// modules from a contract, one module per native module and kind:
+// query_leverage, query_oracle, query_incentive and query_metoken for the
+// queries, msg_leverage, msg_incentive and msg_oracle for the messages, query and msg
+// for the enums wrapping them, rates for the interest rate conversions and
+// math for the coin list arithmetic and address for the address checks.
+// Every params and response type is also re-exported from the crate root,
+// and the prelude bundles the common ones.
+// The native modules encode their responses with the proto JSON of the
+// chain, which omits the zero values and empty lists of a newly listed or
+// zero-state market, so the response fields that can be zero or empty are
+// #[serde(default)] while the identifying fields, like a symbol denom or
+// an address, stay required.
+pub mod address;
 pub mod aggregate_exchange_rate_prevote;
 pub mod aggregate_exchange_rate_vote;
 pub mod bad_debt;
 pub mod error;
 pub mod leverage_parameters;
+pub mod math;
 pub mod msg;
+pub mod msg_incentive;
 pub mod msg_leverage;
+pub mod msg_oracle;
 pub mod oracle_parameters;
+pub mod prelude;
+pub mod proto;
 pub mod query;
 pub mod query_incentive;
 pub mod query_leverage;
 pub mod query_metoken;
 pub mod query_oracle;
+pub mod rates;
 pub mod token;
 
+pub use address::{is_umee_address, UMEE_BECH32_PREFIX};
 pub use aggregate_exchange_rate_prevote::AggregateExchangeRatePrevote;
 pub use aggregate_exchange_rate_vote::{AggregateExchangeRateVote, ExchangeRateTuple};
 pub use bad_debt::BadDebt;
 pub use leverage_parameters::LeverageParameters;
+pub use math::{format_usd, merge_coins, round_decimal, subtract_coins};
 pub use oracle_parameters::{Denom, OracleParameters};
-pub use token::Token;
+pub use rates::{apy_to_apr, apy_to_per_block, compute_exchange_rate};
+pub use token::{
+  looks_like_base_denom, looks_like_symbol_denom, utoken_denom, Token, UTOKEN_PREFIX,
+};
 
-pub use query::{StructUmeeQuery, UmeeQuery};
+pub use query::{StructUmeeQuery, UmeeQuery, ASSIGNED_QUERIES};
 
 pub use query_leverage::{
-  AccountBalancesParams, AccountBalancesResponse, AccountSummaryParams, AccountSummaryResponse,
-  BadDebtsParams, BadDebtsResponse, LeverageParametersParams, LeverageParametersResponse,
-  LiquidationTargetsParams, LiquidationTargetsResponse, MarketSummaryParams, MarketSummaryResponse,
-  MaxBorrowParams, MaxWithdrawParams, MaxWithdrawResponse, RegisteredTokensParams,
-  RegisteredTokensResponse, UmeeQueryLeverage,
+  query_for, AccountBalancesParams, AccountBalancesResponse, AccountSummaryParams,
+  AccountSummaryResponse, AllReservesParams, AllReservesResponse, AvailableWithdrawParams,
+  AvailableWithdrawResponse, BadDebtTotalParams, BadDebtTotalResponse, BadDebtsParams,
+  BadDebtsResponse, BatchAccountSummaryParams, BatchAccountSummaryResponse, BorrowFactorsParams,
+  BorrowFactorsResponse, CapsUsageParams, CapsUsageResponse, CollateralValueAtPriceParams,
+  CollateralValueAtPriceResponse, DenomStatusParams, DenomStatusResponse, InterestRateModelParams,
+  InterestRateModelResponse, LeverageParametersParams, LeverageParametersResponse,
+  LiquidatableBorrowersParams, LiquidatableBorrowersResponse, LiquidationPriceParams,
+  LiquidationPriceResponse, LiquidationTargetsParams, LiquidationTargetsResponse,
+  MarketEnabledParams, MarketEnabledResponse, MarketRowParams, MarketRowResponse,
+  MarketSummaryParams, MarketSummaryResponse, MaxBorrowParams, MaxBorrowResponse,
+  MaxCollateralizeParams, MaxCollateralizeResponse, MaxWithdrawParams, MaxWithdrawResponse,
+  RegisteredTokensParams, RegisteredTokensResponse, ReserveInfoParams, ReserveInfoResponse,
+  SimulateLiquidationParams, SimulateLiquidationResponse, SimulateSupplyParams,
+  SimulateSupplyResponse, SpecialBorrowLimitParams, SpecialBorrowLimitResponse,
+  TimeToLiquidationParams, TimeToLiquidationResponse, TopSupplyMarketsParams,
+  TopSupplyMarketsResponse, UTokenSupplyParams, UTokenSupplyResponse, UmeeQueryLeverage,
 };
 
 pub use query_oracle::{
-  ActiveExchangeRatesParams, ActiveExchangeRatesResponse, AggregatePrevoteParams,
-  AggregatePrevoteResponse, AggregatePrevotesParams, AggregatePrevotesResponse,
-  AggregateVoteParams, AggregateVoteResponse, AggregateVotesParams, AggregateVotesResponse,
-  ExchangeRatesParams, ExchangeRatesResponse, FeederDelegationParams, FeederDelegationResponse,
-  MedianDeviationsParams, MedianDeviationsParamsResponse, MediansParams, MediansParamsResponse,
-  MissCounterParams, MissCounterResponse, OracleParametersParams, OracleParametersResponse,
-  SlashWindowParams, SlashWindowResponse, UmeeQueryOracle,
+  AcceptListParams, AcceptListResponse, ActiveExchangeRatesParams, ActiveExchangeRatesResponse,
+  AggregatePrevoteParams, AggregatePrevoteResponse, AggregatePrevotesParams,
+  AggregatePrevotesResponse, AggregateVoteParams, AggregateVoteResponse, AggregateVotesParams,
+  AggregateVotesResponse, DecCoin, DenomKind, DenomSymbol, ExchangeRatesBatchParams,
+  ExchangeRatesBatchResponse, ExchangeRatesParams, ExchangeRatesResponse, FeederDelegationParams,
+  FeederDelegationResponse, MedianDeviationsParams, MedianDeviationsParamsResponse, MediansParams,
+  MediansParamsResponse, MissCounterParams, MissCounterResponse, OracleParametersParams,
+  OracleParametersResponse, SlashWindowParams, SlashWindowResponse, UmeeQueryOracle,
 };
 
+pub use msg_incentive::BondParams;
 pub use msg_leverage::{
-  BorrowParams, CollateralizeParams, DecollateralizeParams, LiquidateParams, MsgMaxBorrowParams,
-  MsgMaxWithdrawParams, MsgTypes, RepayParams, SupplyCollateralParams, SupplyParams,
-  UmeeMsgLeverage, WithdrawParams,
+  BorrowMsgResponse, BorrowParams, CollateralizeParams, DecollateralizeParams,
+  LiquidateMsgResponse, LiquidateParams, MaxWithdrawMsgResponse, MsgMaxBorrowParams,
+  MsgMaxWithdrawParams, MsgTypes, RepayParams, SupplyCollateralParams, SupplyMsgResponse,
+  SupplyParams, UmeeMsgLeverage, WithdrawMsgResponse, WithdrawParams,
+};
+pub use msg_oracle::DelegateFeedConsentParams;
+pub use query_incentive::{
+  AccountBondsParams, AccountBondsResponse, ActualRatesParams, ActualRatesResponse,
+  CompletedIncentiveProgramsParams, CompletedIncentiveProgramsResponse, CurrentRatesParams,
+  CurrentRatesResponse, IncentiveParameters, IncentiveParametersParams,
+  IncentiveParametersResponse, IncentiveProgram, IncentiveProgramParams, IncentiveProgramResponse,
+  LastRewardTimeParams, LastRewardTimeResponse, OngoingIncentiveProgramsParams,
+  OngoingIncentiveProgramsResponse, PendingRewardsParams, PendingRewardsResponse,
+  TotalBondedParams, TotalBondedResponse, TotalUnbondingParams, TotalUnbondingResponse,
+  UmeeQueryIncentive, Unbonding, UpcomingIncentiveProgramsParams,
+  UpcomingIncentiveProgramsResponse,
+};
+pub use query_metoken::{
+  AcceptedAsset, AssetBalance, AssetPrice, Fee, Index, IndexBalances, IndexPrices,
+  MetokenIndexPricesParams, MetokenIndexPricesResponse, MetokenIndexbalancesParams,
+  MetokenIndexbalancesResponse, MetokenIndexesParams, MetokenIndexesResponse, MetokenParameters,
+  MetokenParametersParams, MetokenParametersResponse, MetokenRedeemfeeParams,
+  MetokenRedeemfeeResponse, MetokenSwapfeeParams, MetokenSwapfeeResponse, UmeeQueryMeToken,
 };
-pub use query_incentive::UmeeQueryIncentive;
 
-pub use msg::{StructUmeeMsg, UmeeMsg};
+pub use error::ContractError;
+pub use msg::{umee_response, umee_submsg_response, StructUmeeMsg, UmeeMsg};
 
 // This is a signal, such that any contract that imports these helpers will only run on the
 // umee blockchain, it makes mandatory that the blockchain have the "umee" inside
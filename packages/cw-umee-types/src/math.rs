@@ -0,0 +1,182 @@
+use cosmwasm_std::{Coin, Decimal256, StdError, StdResult, Uint128, Uint256};
+use std::collections::BTreeMap;
+
+// merge_coins sums the coins of both lists by denom, the result is sorted
+// by denom and has no zero amounts. It fails if a denom total overflows.
+pub fn merge_coins(a: &[Coin], b: &[Coin]) -> StdResult<Vec<Coin>> {
+  let mut totals = group_by_denom(a)?;
+  for coin in b {
+    let total = totals.entry(coin.denom.as_str()).or_default();
+    *total = total.checked_add(coin.amount)?;
+  }
+  Ok(into_coins(totals))
+}
+
+// subtract_coins removes the coins of b from a by denom, the result is
+// sorted by denom and has no zero amounts. It fails if b holds more of a
+// denom than a, including denoms missing from a.
+pub fn subtract_coins(a: &[Coin], b: &[Coin]) -> StdResult<Vec<Coin>> {
+  let mut totals = group_by_denom(a)?;
+  for coin in b {
+    let total = totals.entry(coin.denom.as_str()).or_default();
+    *total = total.checked_sub(coin.amount)?;
+  }
+  Ok(into_coins(totals))
+}
+
+// group_by_denom sums a coin list by denom, a list may repeat a denom.
+fn group_by_denom(coins: &[Coin]) -> StdResult<BTreeMap<&str, Uint128>> {
+  let mut totals = BTreeMap::new();
+  for coin in coins {
+    let total: &mut Uint128 = totals.entry(coin.denom.as_str()).or_default();
+    *total = total.checked_add(coin.amount)?;
+  }
+  Ok(totals)
+}
+
+// round_decimal rounds a decimal to the given number of decimal places with
+// the banker's rounding, a tie is rounded to the even neighbour. Above the
+// 18 places of a Decimal256 the value is returned as is. It fails if the
+// value rounds up above the Decimal256 max.
+pub fn round_decimal(value: Decimal256, places: u32) -> StdResult<Decimal256> {
+  if places >= Decimal256::DECIMAL_PLACES {
+    return Ok(value);
+  }
+  let step = Uint256::from(10u8).pow(Decimal256::DECIMAL_PLACES - places);
+  let atomics = value.atomics();
+  let mut units = atomics / step;
+  let rest = atomics % step;
+  let half = step / Uint256::from(2u8);
+  if rest > half || (rest == half && units % Uint256::from(2u8) == Uint256::one()) {
+    units += Uint256::one();
+  }
+  let rounded = units
+    .checked_mul(step)
+    .map_err(|_| StdError::generic_err(format!("{} rounds above the decimal max", value)))?;
+  Ok(Decimal256::new(rounded))
+}
+
+// format_usd renders a USD value with exactly the given number of decimal
+// places, rounded with round_decimal. Places above the 18 of a Decimal256
+// are padded with zeros, and a value too close to the Decimal256 max to be
+// rounded up is truncated instead.
+pub fn format_usd(value: Decimal256, places: u32) -> String {
+  let kept = places.min(Decimal256::DECIMAL_PLACES);
+  let step = Uint256::from(10u8).pow(Decimal256::DECIMAL_PLACES - kept);
+  let value = round_decimal(value, kept)
+    .unwrap_or_else(|_| Decimal256::new(value.atomics() - value.atomics() % step));
+  let one = Uint256::from(10u8).pow(Decimal256::DECIMAL_PLACES);
+  let whole = value.atomics() / one;
+  if places == 0 {
+    return whole.to_string();
+  }
+  let fraction = format!(
+    "{:0>width$}",
+    (value.atomics() % one).to_string(),
+    width = Decimal256::DECIMAL_PLACES as usize
+  );
+  format!(
+    "{}.{:0<width$}",
+    whole,
+    &fraction[..kept as usize],
+    width = places as usize
+  )
+}
+
+fn into_coins(totals: BTreeMap<&str, Uint128>) -> Vec<Coin> {
+  totals
+    .into_iter()
+    .filter(|(_, amount)| !amount.is_zero())
+    .map(|(denom, amount)| Coin::new(amount.u128(), denom))
+    .collect()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use cosmwasm_std::coins;
+  use std::str::FromStr;
+
+  #[test]
+  fn merge() {
+    let a = vec![
+      Coin::new(10, "uumee"),
+      Coin::new(5, "uatom"),
+      Coin::new(1, "uumee"),
+    ];
+    let b = vec![Coin::new(7, "uatom"), Coin::new(3, "ujuno")];
+    assert_eq!(
+      vec![
+        Coin::new(12, "uatom"),
+        Coin::new(3, "ujuno"),
+        Coin::new(11, "uumee")
+      ],
+      merge_coins(&a, &b).unwrap()
+    );
+    assert_eq!(merge_coins(&a, &b).unwrap(), merge_coins(&b, &a).unwrap());
+    assert_eq!(
+      Vec::<Coin>::new(),
+      merge_coins(&[], &coins(0, "uumee")).unwrap()
+    );
+
+    let max = coins(u128::MAX, "uumee");
+    merge_coins(&max, &coins(1, "uumee")).unwrap_err();
+    merge_coins(&[Coin::new(u128::MAX, "uumee"), Coin::new(1, "uumee")], &[]).unwrap_err();
+  }
+
+  #[test]
+  fn subtract() {
+    let a = vec![Coin::new(10, "uumee"), Coin::new(5, "uatom")];
+    assert_eq!(
+      coins(4, "uumee"),
+      subtract_coins(&a, &[Coin::new(6, "uumee"), Coin::new(5, "uatom")]).unwrap()
+    );
+    // underflow of a held denom and of a denom missing from a
+    subtract_coins(&a, &coins(11, "uumee")).unwrap_err();
+    subtract_coins(&a, &coins(1, "ujuno")).unwrap_err();
+    assert_eq!(
+      a.len(),
+      subtract_coins(&a, &coins(0, "ujuno")).unwrap().len()
+    );
+  }
+
+  #[test]
+  fn round() {
+    let round = |value: &str, places: u32| {
+      round_decimal(Decimal256::from_str(value).unwrap(), places)
+        .unwrap()
+        .to_string()
+    };
+    assert_eq!("1.23", round("1.234", 2));
+    assert_eq!("1.24", round("1.236", 2));
+    // ties go to the even neighbour
+    assert_eq!("1.24", round("1.235", 2));
+    assert_eq!("1.22", round("1.225", 2));
+    assert_eq!("2", round("2.5", 0));
+    assert_eq!("4", round("3.5", 0));
+    assert_eq!("0", round("0.0004", 3));
+    assert_eq!("0.000000000000000001", round("0.000000000000000001", 18));
+    assert_eq!("1.234", round("1.234", 30));
+
+    round_decimal(Decimal256::MAX, 0).unwrap_err();
+  }
+
+  #[test]
+  fn format_usd_places() {
+    let usd = |v: &str, places: u32| format_usd(Decimal256::from_str(v).unwrap(), places);
+    assert_eq!("0.000001", usd("0.000001", 6));
+    assert_eq!("0.00", usd("0.000001", 2));
+    assert_eq!("0.000000", usd("0.0000005", 6));
+    assert_eq!("0.000002", usd("0.0000015", 6));
+    assert_eq!("1234.57", usd("1234.5678", 2));
+    assert_eq!("1.00", usd("1.005", 2));
+    assert_eq!("1000000000000.00", usd("1000000000000", 2));
+    assert_eq!("999999999999.999999", usd("999999999999.999999", 6));
+    assert_eq!("1000000000000", usd("999999999999.5", 0));
+    assert_eq!("1.00000000000000000000", usd("1", 20));
+    assert_eq!(
+      Decimal256::MAX.to_string().split('.').next().unwrap(),
+      format_usd(Decimal256::MAX, 0)
+    );
+  }
+}
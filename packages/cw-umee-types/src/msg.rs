@@ -6,7 +6,7 @@ use crate::{
     UmeeMsgLeverage, WithdrawParams,
   },
 };
-use cosmwasm_std::{CosmosMsg, CustomMsg, Response};
+use cosmwasm_std::{CosmosMsg, CustomMsg, Response, SubMsg};
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
@@ -55,6 +55,61 @@ fn default_struct_umee_msg(m: MsgTypes) -> StructUmeeMsg {
   }
 }
 
+// From<UmeeMsgLeverage> builds the struct with the assigned msg matching
+// the leverage msg, it allows emitting several messages in one response
+impl From<UmeeMsgLeverage> for StructUmeeMsg {
+  fn from(msg: UmeeMsgLeverage) -> Self {
+    match msg {
+      UmeeMsgLeverage::Supply(params) => StructUmeeMsg {
+        supply: Some(SupplyParams {
+          check_caps: false,
+          ..params
+        }),
+        ..default_struct_umee_msg(MsgTypes::AssignedMsgSupply)
+      },
+      UmeeMsgLeverage::Withdraw(params) => StructUmeeMsg {
+        withdraw: Some(params),
+        ..default_struct_umee_msg(MsgTypes::AssignedMsgWithdraw)
+      },
+      UmeeMsgLeverage::MaxWithdraw(params) => StructUmeeMsg {
+        max_withdraw: Some(params),
+        ..default_struct_umee_msg(MsgTypes::AssignedMsgMaxWithdraw)
+      },
+      UmeeMsgLeverage::Collateralize(params) => StructUmeeMsg {
+        collateralize: Some(params),
+        ..default_struct_umee_msg(MsgTypes::AssignedMsgCollateralize)
+      },
+      UmeeMsgLeverage::Decollateralize(params) => StructUmeeMsg {
+        decollateralize: Some(params),
+        ..default_struct_umee_msg(MsgTypes::AssignedMsgDecollateralize)
+      },
+      UmeeMsgLeverage::Borrow(params) => StructUmeeMsg {
+        borrow: Some(BorrowParams {
+          max_available: false,
+          ..params
+        }),
+        ..default_struct_umee_msg(MsgTypes::AssignedMsgBorrow)
+      },
+      UmeeMsgLeverage::MaxBorrow(params) => StructUmeeMsg {
+        max_borrow: Some(params),
+        ..default_struct_umee_msg(MsgTypes::AssignedMsgMaxBorrow)
+      },
+      UmeeMsgLeverage::Repay(params) => StructUmeeMsg {
+        repay: Some(params),
+        ..default_struct_umee_msg(MsgTypes::AssignedMsgRepay)
+      },
+      UmeeMsgLeverage::Liquidate(params) => StructUmeeMsg {
+        liquidate: Some(params),
+        ..default_struct_umee_msg(MsgTypes::AssignedMsgLiquidate)
+      },
+      UmeeMsgLeverage::SupplyCollateral(params) => StructUmeeMsg {
+        supply_collateral: Some(params),
+        ..default_struct_umee_msg(MsgTypes::AssignedMsgSupplyCollateralize)
+      },
+    }
+  }
+}
+
 // msg_chain sends any message in the chain native modules
 pub fn msg_chain(umee_msg: StructUmeeMsg) -> Result<Response<StructUmeeMsg>, ContractError> {
   if !umee_msg.valid() {
@@ -63,11 +118,30 @@ pub fn msg_chain(umee_msg: StructUmeeMsg) -> Result<Response<StructUmeeMsg>, Con
     });
   }
 
-  let res = Response::new()
-    .add_attribute("method", umee_msg.assigned_str())
-    .add_message(umee_msg);
+  Ok(umee_response(&umee_msg.assigned_str(), umee_msg))
+}
 
-  Ok(res)
+// umee_response builds the response sending a single umee msg, with the
+// action attribute set to the action and the assigned_msg attribute to the
+// kind of the msg, so every path sending a msg emits the same attributes.
+pub fn umee_response(action: &str, umee_msg: StructUmeeMsg) -> Response<StructUmeeMsg> {
+  umee_attributes(action, &umee_msg).add_message(umee_msg)
+}
+
+// umee_submsg_response builds the same response as umee_response, sending
+// the umee msg as a submessage replied to on success under the reply id.
+pub fn umee_submsg_response(
+  action: &str,
+  umee_msg: StructUmeeMsg,
+  reply_id: u64,
+) -> Response<StructUmeeMsg> {
+  umee_attributes(action, &umee_msg).add_submessage(SubMsg::reply_on_success(umee_msg, reply_id))
+}
+
+fn umee_attributes(action: &str, umee_msg: &StructUmeeMsg) -> Response<StructUmeeMsg> {
+  Response::new()
+    .add_attribute("action", action)
+    .add_attribute("assigned_msg", umee_msg.assigned_str())
 }
 
 // Defines all the implementation related to the StructUmeeMsg
@@ -81,23 +155,15 @@ impl StructUmeeMsg {
   }
 
   pub fn assigned_str(&self) -> String {
-    match self.assigned_msg {
-      MsgTypes::AssignedMsgSupply => String::from("supply"),
-      MsgTypes::AssignedMsgWithdraw => String::from("withdraw"),
-      MsgTypes::AssignedMsgMaxWithdraw => String::from("max_withdraw"),
-      MsgTypes::AssignedMsgCollateralize => String::from("collateralize"),
-      MsgTypes::AssignedMsgDecollateralize => String::from("decollateralize"),
-      MsgTypes::AssignedMsgBorrow => String::from("borrow"),
-      MsgTypes::AssignedMsgMaxBorrow => String::from("max_borrow"),
-      MsgTypes::AssignedMsgRepay => String::from("repay"),
-      MsgTypes::AssignedMsgLiquidate => String::from("liquidate"),
-      MsgTypes::AssignedMsgSupplyCollateralize => String::from("supply_collateral"),
-    }
+    String::from(self.assigned_msg.name())
   }
   // creates a new lend message.
   pub fn supply(supply_params: SupplyParams) -> Result<Response<StructUmeeMsg>, ContractError> {
     let mut m = default_struct_umee_msg(MsgTypes::AssignedMsgSupply);
-    m.supply = Some(supply_params);
+    m.supply = Some(SupplyParams {
+      check_caps: false,
+      ..supply_params
+    });
     return msg_chain(m);
   }
 
@@ -136,7 +202,10 @@ impl StructUmeeMsg {
   // creates a new borrow message.
   pub fn borrow(borrow_params: BorrowParams) -> Result<Response<StructUmeeMsg>, ContractError> {
     let mut m = default_struct_umee_msg(MsgTypes::AssignedMsgBorrow);
-    m.borrow = Some(borrow_params);
+    m.borrow = Some(BorrowParams {
+      max_available: false,
+      ..borrow_params
+    });
     return msg_chain(m);
   }
   // creates a new max borrow message.
@@ -183,3 +252,96 @@ impl From<UmeeMsg> for CosmosMsg<UmeeMsg> {
     CosmosMsg::Custom(msg)
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use cosmwasm_std::{to_json_string, Addr, Coin};
+
+  #[test]
+  fn constructors_cover_every_leverage_msg() {
+    let coin = Coin::new(10, "uumee");
+    let utoken = Coin::new(10, "u/uumee");
+    let cases = vec![
+      (
+        StructUmeeMsg::supply(SupplyParams::new(coin.clone())),
+        UmeeMsgLeverage::Supply(SupplyParams::new(coin.clone())),
+      ),
+      (
+        StructUmeeMsg::withdraw(WithdrawParams::new(utoken.clone())),
+        UmeeMsgLeverage::Withdraw(WithdrawParams::new(utoken.clone())),
+      ),
+      (
+        StructUmeeMsg::max_withdraw(MsgMaxWithdrawParams::new("uumee")),
+        UmeeMsgLeverage::MaxWithdraw(MsgMaxWithdrawParams::new("uumee")),
+      ),
+      (
+        StructUmeeMsg::collateralize(CollateralizeParams::new(utoken.clone())),
+        UmeeMsgLeverage::Collateralize(CollateralizeParams::new(utoken.clone())),
+      ),
+      (
+        StructUmeeMsg::decollateralize(DecollateralizeParams::new(utoken.clone())),
+        UmeeMsgLeverage::Decollateralize(DecollateralizeParams::new(utoken)),
+      ),
+      (
+        StructUmeeMsg::borrow(BorrowParams::new(coin.clone())),
+        UmeeMsgLeverage::Borrow(BorrowParams::new(coin.clone())),
+      ),
+      (
+        StructUmeeMsg::max_borrow(MsgMaxBorrowParams::new(coin.clone())),
+        UmeeMsgLeverage::MaxBorrow(MsgMaxBorrowParams::new(coin.clone())),
+      ),
+      (
+        StructUmeeMsg::repay(RepayParams::new(coin.clone())),
+        UmeeMsgLeverage::Repay(RepayParams::new(coin.clone())),
+      ),
+      (
+        StructUmeeMsg::liquidate(LiquidateParams::new(
+          Addr::unchecked("borrower"),
+          coin.clone(),
+          Coin::new(11, "uatom"),
+        )),
+        UmeeMsgLeverage::Liquidate(LiquidateParams::new(
+          Addr::unchecked("borrower"),
+          coin.clone(),
+          Coin::new(11, "uatom"),
+        )),
+      ),
+      (
+        StructUmeeMsg::supply_collateral(SupplyCollateralParams::new(coin.clone())),
+        UmeeMsgLeverage::SupplyCollateral(SupplyCollateralParams::new(coin)),
+      ),
+    ];
+
+    for (res, leverage_msg) in cases {
+      let res = res.unwrap();
+      assert_eq!(1, res.messages.len());
+      let expected = StructUmeeMsg::from(leverage_msg);
+      assert!(expected.valid());
+      assert_eq!(CosmosMsg::Custom(expected.clone()), res.messages[0].msg);
+      // the payload is set under the field named as the assigned msg
+      let json = to_json_string(&expected).unwrap();
+      assert!(json.contains(&format!(r#""{}":{{"#, expected.assigned_str())));
+    }
+  }
+
+  #[test]
+  fn umee_response_attributes() {
+    let msg = StructUmeeMsg::from(UmeeMsgLeverage::Supply(SupplyParams::new(Coin::new(
+      10, "uumee",
+    ))));
+    let res = umee_response("lend", msg.clone());
+    assert_eq!(res.attributes[0].key, "action");
+    assert_eq!(res.attributes[0].value, "lend");
+    assert_eq!(res.attributes[1].key, "assigned_msg");
+    assert_eq!(res.attributes[1].value, "supply");
+    assert_eq!(res.messages[0], SubMsg::new(msg.clone()));
+
+    let res = umee_submsg_response("lend", msg.clone(), 3);
+    assert_eq!(
+      res.attributes,
+      umee_response("lend", msg.clone()).attributes
+    );
+    assert_eq!(res.messages[0], SubMsg::reply_on_success(msg, 3));
+  }
+}
@@ -0,0 +1,36 @@
+use crate::proto::{encode_coin, encode_message, encode_string};
+use cosmwasm_std::{Addr, Binary, Coin, CosmosMsg};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+// BondParams params to bond collateral uTokens in the incentive module.
+// The incentive module is not part of the umee custom msgs, so the bond
+// is sent to the chain as a stargate message.
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct BondParams {
+  // Account is the address bonding the uTokens and the signer of the message.
+  pub account: Addr,
+  // UToken is the collateral uToken amount to bond.
+  pub u_token: Coin,
+}
+
+impl BondParams {
+  pub const TYPE_URL: &'static str = "/umee.incentive.v1.MsgBond";
+
+  // encode returns the protobuf encoded MsgBond.
+  pub fn encode(&self) -> Binary {
+    let mut buf = Vec::new();
+    encode_string(1, self.account.as_str(), &mut buf);
+    encode_message(2, &encode_coin(&self.u_token), &mut buf);
+    Binary::from(buf)
+  }
+}
+
+impl<T> From<BondParams> for CosmosMsg<T> {
+  fn from(params: BondParams) -> Self {
+    CosmosMsg::Stargate {
+      type_url: BondParams::TYPE_URL.to_string(),
+      value: params.encode(),
+    }
+  }
+}
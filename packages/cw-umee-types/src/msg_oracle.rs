@@ -0,0 +1,53 @@
+use crate::proto::encode_string;
+use cosmwasm_std::{Addr, Binary, CosmosMsg};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+// DelegateFeedConsentParams params to delegate the price feeding of a
+// validator to a feeder account. The oracle module is not part of the umee
+// custom msgs, so the consent is sent to the chain as a stargate message.
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct DelegateFeedConsentParams {
+  // Operator is the validator operator address and the signer of the message.
+  pub operator: Addr,
+  // Delegate is the account allowed to submit the prices of the operator.
+  pub delegate: Addr,
+}
+
+impl DelegateFeedConsentParams {
+  pub const TYPE_URL: &'static str = "/umee.oracle.v1.MsgDelegateFeedConsent";
+
+  // encode returns the protobuf encoded MsgDelegateFeedConsent.
+  pub fn encode(&self) -> Binary {
+    let mut buf = Vec::new();
+    encode_string(1, self.operator.as_str(), &mut buf);
+    encode_string(2, self.delegate.as_str(), &mut buf);
+    Binary::from(buf)
+  }
+}
+
+impl<T> From<DelegateFeedConsentParams> for CosmosMsg<T> {
+  fn from(params: DelegateFeedConsentParams) -> Self {
+    CosmosMsg::Stargate {
+      type_url: DelegateFeedConsentParams::TYPE_URL.to_string(),
+      value: params.encode(),
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn encode_delegate_feed_consent() {
+    let params = DelegateFeedConsentParams {
+      operator: Addr::unchecked("op"),
+      delegate: Addr::unchecked("feeder"),
+    };
+    assert_eq!(
+      Binary::from(b"\x0a\x02op\x12\x06feeder".as_slice()),
+      params.encode()
+    );
+  }
+}
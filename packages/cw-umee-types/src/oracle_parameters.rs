@@ -1,3 +1,4 @@
+use crate::query_oracle::DenomSymbol;
 use cosmwasm_std::Decimal256;
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
@@ -19,6 +20,45 @@ pub struct OracleParameters {
   historic_accept_list: Vec<Denom>,
 }
 
+impl OracleParameters {
+  // vote_period returns the number of blocks of a vote period.
+  pub fn vote_period(&self) -> u64 {
+    self.vote_period
+  }
+
+  // slash_window returns the number of blocks of a slash window.
+  pub fn slash_window(&self) -> u64 {
+    self.slash_window
+  }
+
+  // accept_list returns the denoms the oracle currently accepts prices for.
+  pub fn accept_list(&self) -> Vec<DenomSymbol> {
+    self.accept_list.iter().map(DenomSymbol::from).collect()
+  }
+
+  // blocks_until_next_vote returns the number of blocks until the next
+  // vote period starts, it is the vote period itself on its first block
+  // and one on its last block.
+  pub fn blocks_until_next_vote(&self, current_height: u64) -> u64 {
+    blocks_until_next_period(current_height, self.vote_period)
+  }
+
+  // slash_window_remaining returns the number of blocks until the current
+  // slash window ends and the miss counters are evaluated.
+  pub fn slash_window_remaining(&self, current_height: u64) -> u64 {
+    blocks_until_next_period(current_height, self.slash_window)
+  }
+}
+
+// blocks_until_next_period returns the blocks left until the next multiple
+// of period, a period of zero blocks never ends and returns zero.
+fn blocks_until_next_period(current_height: u64, period: u64) -> u64 {
+  match current_height.checked_rem(period) {
+    Some(elapsed) => period - elapsed,
+    None => 0,
+  }
+}
+
 // Denom object to hold configurations of each denom.
 #[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
 pub struct Denom {
@@ -26,3 +66,48 @@ pub struct Denom {
   symbol_denom: String,
   exponent: u32,
 }
+
+impl From<&Denom> for DenomSymbol {
+  fn from(denom: &Denom) -> Self {
+    DenomSymbol {
+      base_denom: denom.base_denom.clone(),
+      symbol_denom: denom.symbol_denom.clone(),
+      exponent: denom.exponent,
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use std::str::FromStr;
+
+  fn params(vote_period: u64, slash_window: u64) -> OracleParameters {
+    OracleParameters {
+      vote_period,
+      vote_threshold: Decimal256::from_str("0.5").unwrap(),
+      reward_band: Decimal256::from_str("0.02").unwrap(),
+      reward_distribution_window: 5256000,
+      accept_list: vec![],
+      slash_fraction: Decimal256::from_str("0.0001").unwrap(),
+      slash_window,
+      min_valid_per_window: Decimal256::from_str("0.05").unwrap(),
+      stamp_period: 10,
+      prune_period: 100,
+      median_period: 50,
+      historic_accept_list: vec![],
+    }
+  }
+
+  #[test]
+  fn vote_window_boundaries() {
+    let p = params(5, 100);
+    // first block of a vote period
+    assert_eq!(5, p.blocks_until_next_vote(10));
+    // last block of a vote period
+    assert_eq!(1, p.blocks_until_next_vote(14));
+    assert_eq!(100, p.slash_window_remaining(200));
+    assert_eq!(1, p.slash_window_remaining(299));
+    assert_eq!(0, params(0, 0).blocks_until_next_vote(14));
+  }
+}
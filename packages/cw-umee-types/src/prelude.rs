@@ -0,0 +1,13 @@
+// prelude bundles the types most contracts need to talk with the umee
+// native modules, it is meant to be glob imported:
+// use cw_umee_types::prelude::*;
+pub use crate::error::ContractError;
+pub use crate::msg::{StructUmeeMsg, UmeeMsg};
+pub use crate::msg_incentive::BondParams;
+pub use crate::msg_leverage::UmeeMsgLeverage;
+pub use crate::query::{StructUmeeQuery, UmeeQuery};
+pub use crate::query_incentive::UmeeQueryIncentive;
+pub use crate::query_leverage::UmeeQueryLeverage;
+pub use crate::query_metoken::UmeeQueryMeToken;
+pub use crate::query_oracle::{DecCoin, UmeeQueryOracle};
+pub use crate::token::{utoken_denom, Token, UTOKEN_PREFIX};
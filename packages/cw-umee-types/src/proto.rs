@@ -0,0 +1,65 @@
+use cosmwasm_std::Coin;
+
+// Minimal protobuf encoding, enough to build the umee native messages
+// that are not exposed through the custom StructUmeeMsg and must be
+// sent as stargate messages. Only length-delimited fields are supported.
+
+// WIRE_TYPE_LEN is the protobuf wire type of strings and embedded messages.
+const WIRE_TYPE_LEN: u64 = 2;
+
+fn encode_varint(mut value: u64, buf: &mut Vec<u8>) {
+  while value >= 0x80 {
+    buf.push((value as u8) | 0x80);
+    value >>= 7;
+  }
+  buf.push(value as u8);
+}
+
+fn encode_len_delimited(field_number: u32, bytes: &[u8], buf: &mut Vec<u8>) {
+  encode_varint((u64::from(field_number) << 3) | WIRE_TYPE_LEN, buf);
+  encode_varint(bytes.len() as u64, buf);
+  buf.extend_from_slice(bytes);
+}
+
+// encode_string appends a string field, empty strings are the proto3
+// default value and are omitted.
+pub fn encode_string(field_number: u32, value: &str, buf: &mut Vec<u8>) {
+  if !value.is_empty() {
+    encode_len_delimited(field_number, value.as_bytes(), buf);
+  }
+}
+
+// encode_message appends an already encoded embedded message.
+pub fn encode_message(field_number: u32, message: &[u8], buf: &mut Vec<u8>) {
+  encode_len_delimited(field_number, message, buf);
+}
+
+// encode_coin encodes a cosmos.base.v1beta1.Coin.
+pub fn encode_coin(coin: &Coin) -> Vec<u8> {
+  let mut buf = Vec::new();
+  encode_string(1, &coin.denom, &mut buf);
+  encode_string(2, &coin.amount.to_string(), &mut buf);
+  buf
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn varint() {
+    let mut buf = Vec::new();
+    encode_varint(300, &mut buf);
+    assert_eq!(vec![0xac, 0x02], buf);
+  }
+
+  #[test]
+  fn coin() {
+    let coin = Coin::new(1000, "uumee");
+    let mut expected = vec![0x0a, 5];
+    expected.extend_from_slice(b"uumee");
+    expected.extend_from_slice(&[0x12, 4]);
+    expected.extend_from_slice(b"1000");
+    assert_eq!(expected, encode_coin(&coin));
+  }
+}
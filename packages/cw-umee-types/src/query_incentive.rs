@@ -19,6 +19,28 @@ pub enum UmeeQueryIncentive {
   LastRewardTime(LastRewardTimeParams),
 }
 
+impl UmeeQueryIncentive {
+  // utoken_denoms_mut returns every denom the query names, all of them
+  // uToken denoms since the incentive module bonds uTokens, for a caller
+  // to rewrite them before the query is sent.
+  pub fn utoken_denoms_mut(&mut self) -> Vec<&mut String> {
+    match self {
+      UmeeQueryIncentive::IncentiveParameters(_)
+      | UmeeQueryIncentive::AccountBonds(_)
+      | UmeeQueryIncentive::PendingRewards(_)
+      | UmeeQueryIncentive::CompletedIncentivePrograms(_)
+      | UmeeQueryIncentive::OngoingIncentivePrograms(_)
+      | UmeeQueryIncentive::UpcomingIncentivePrograms(_)
+      | UmeeQueryIncentive::IncentiveProgram(_)
+      | UmeeQueryIncentive::LastRewardTime(_) => vec![],
+      UmeeQueryIncentive::TotalBonded(params) => vec![&mut params.denom],
+      UmeeQueryIncentive::TotalUnbonding(params) => vec![&mut params.denom],
+      UmeeQueryIncentive::CurrentRates(params) => vec![&mut params.u_token],
+      UmeeQueryIncentive::ActualRates(params) => vec![&mut params.u_token],
+    }
+  }
+}
+
 #[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
 pub struct IncentiveParametersParams {}
 
@@ -41,6 +63,7 @@ pub struct TotalBondedParams {
 
 #[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
 pub struct TotalBondedResponse {
+  #[serde(default)]
   pub bonded: Vec<Coin>,
 }
 
@@ -51,6 +74,7 @@ pub struct TotalUnbondingParams {
 
 #[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
 pub struct TotalUnbondingResponse {
+  #[serde(default)]
   pub unbonding: Vec<Coin>,
 }
 
@@ -59,10 +83,14 @@ pub struct AccountBondsParams {
   pub address: String,
 }
 
-#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+// the module omits the empty lists of an account without bonds
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug, Default)]
 pub struct AccountBondsResponse {
+  #[serde(default)]
   pub bonded: Vec<Coin>,
+  #[serde(default)]
   pub unbonding: Vec<Coin>,
+  #[serde(default)]
   pub unbondings: Vec<Unbonding>,
 }
 
@@ -80,6 +108,7 @@ pub struct PendingRewardsParams {
 
 #[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
 pub struct PendingRewardsResponse {
+  #[serde(default)]
   pub rewards: Vec<Coin>,
 }
 
@@ -88,6 +117,7 @@ pub struct CompletedIncentiveProgramsParams {}
 
 #[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
 pub struct CompletedIncentiveProgramsResponse {
+  #[serde(default)]
   pub programs: Vec<IncentiveProgram>,
 }
 
@@ -96,6 +126,7 @@ pub struct OngoingIncentiveProgramsParams {}
 
 #[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
 pub struct OngoingIncentiveProgramsResponse {
+  #[serde(default)]
   pub programs: Vec<IncentiveProgram>,
 }
 
@@ -104,6 +135,7 @@ pub struct UpcomingIncentiveProgramsParams {}
 
 #[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
 pub struct UpcomingIncentiveProgramsResponse {
+  #[serde(default)]
   pub programs: Vec<IncentiveProgram>,
 }
 
@@ -125,17 +157,24 @@ pub struct CurrentRatesParams {
 #[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
 pub struct CurrentRatesResponse {
   pub reference_bond: Coin,
+  #[serde(default)]
   pub rewards: Vec<Coin>,
 }
 
+// round_to rounds the APY to that many decimal places with round_decimal,
+// it isn't sent to the incentive module
 #[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
 pub struct ActualRatesParams {
   pub u_token: String,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub round_to: Option<u32>,
 }
 
 #[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
 pub struct ActualRatesResponse {
-  pub APY: Decimal,
+  // the incentive module names the field after the proto field "APY"
+  #[serde(rename = "APY")]
+  pub apy: Decimal,
 }
 
 #[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
@@ -148,7 +187,9 @@ pub struct LastRewardTimeResponse {
 
 #[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
 pub struct IncentiveProgram {
-  pub ID: u32,
+  // the incentive module names the field after the proto field "ID"
+  #[serde(rename = "ID")]
+  pub id: u32,
   pub start_time: i64,
   pub duration: i64,
   pub u_token: String,
@@ -156,3 +197,49 @@ pub struct IncentiveProgram {
   pub total_rewards: Coin,
   pub remaining_rewards: Coin,
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use cosmwasm_std::{from_json, to_json_string, Uint128};
+  use std::str::FromStr;
+
+  #[test]
+  fn actual_rates_response_json() {
+    let resp: ActualRatesResponse = from_json(br#"{"APY":"0.052"}"#).unwrap();
+    assert_eq!(Decimal::from_str("0.052").unwrap(), resp.apy);
+    assert_eq!(r#"{"APY":"0.052"}"#, to_json_string(&resp).unwrap());
+  }
+
+  #[test]
+  fn incentive_program_response_json() {
+    let payload = br#"{"program":{"ID":3,"start_time":1690000000,"duration":86400,"u_token":"u/uumee","funded":true,"total_rewards":{"denom":"uumee","amount":"1000"},"remaining_rewards":{"denom":"uumee","amount":"400"}}}"#;
+    let resp: IncentiveProgramResponse = from_json(payload).unwrap();
+    assert_eq!(3, resp.program.id);
+    assert_eq!("u/uumee", resp.program.u_token);
+    assert_eq!(Uint128::new(400), resp.program.remaining_rewards.amount);
+  }
+
+  #[test]
+  fn sparse_responses() {
+    let bonded: TotalBondedResponse = from_json(b"{}").unwrap();
+    assert!(bonded.bonded.is_empty());
+    let unbonding: TotalUnbondingResponse = from_json(b"{}").unwrap();
+    assert!(unbonding.unbonding.is_empty());
+    let rewards: PendingRewardsResponse = from_json(b"{}").unwrap();
+    assert!(rewards.rewards.is_empty());
+    let completed: CompletedIncentiveProgramsResponse = from_json(b"{}").unwrap();
+    assert!(completed.programs.is_empty());
+    let ongoing: OngoingIncentiveProgramsResponse = from_json(b"{}").unwrap();
+    assert!(ongoing.programs.is_empty());
+    let upcoming: UpcomingIncentiveProgramsResponse = from_json(b"{}").unwrap();
+    assert!(upcoming.programs.is_empty());
+    let bonds: AccountBondsResponse = from_json(b"{}").unwrap();
+    assert_eq!(AccountBondsResponse::default(), bonds);
+
+    let current: CurrentRatesResponse =
+      from_json(br#"{"reference_bond":{"denom":"u/uumee","amount":"1"}}"#).unwrap();
+    assert!(current.rewards.is_empty());
+    assert!(from_json::<CurrentRatesResponse>(b"{}").is_err());
+  }
+}
@@ -1,12 +1,15 @@
 use crate::bad_debt::BadDebt;
 use crate::leverage_parameters::LeverageParameters;
-use crate::token::Token;
-use cosmwasm_std::{Addr, Coin, Decimal256};
+use crate::query_oracle::DecCoin;
+use crate::token::{looks_like_symbol_denom, Token};
+use cosmwasm_std::{Addr, Api, Coin, Decimal, Decimal256, StdError, StdResult, Uint128};
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 
 // UmeeQueryLeverage defines all the available queries
-// for the umee leverage native module.
+// for the umee leverage native module, the aliases accept
+// the names of the native query fields too.
 #[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
 #[serde(rename_all = "snake_case")]
 pub enum UmeeQueryLeverage {
@@ -30,11 +33,320 @@ pub enum UmeeQueryLeverage {
   // Expect to returns LiquidationTargetsResponse.
   LiquidationTargets(LiquidationTargetsParams),
   // BadDebts returns a list of borrow positions that have been marked for bad debt repayment.
+  #[serde(alias = "bad_debts_params")]
   BadDebts(BadDebtsParams),
   // MaxWithdraw returns the maximum amount of a given token an address can withdraw.
+  #[serde(alias = "max_withdraw_params")]
   MaxWithdraw(MaxWithdrawParams),
   // MaxBorrow queries the maximum amount of a given token an address can borrow.
+  #[serde(alias = "max_borrow_params")]
   MaxBorrow(MaxBorrowParams),
+  // AvailableWithdraw returns the market liquidity a supplier could withdraw,
+  // it is composed from the MarketSummary query.
+  // Expect to returns AvailableWithdrawResponse.
+  AvailableWithdraw(AvailableWithdrawParams),
+  // LiquidatableBorrowers returns a page of the liquidatable borrowers of a
+  // denom with their borrowed coin. It is not every borrower of the market:
+  // the leverage module only enumerates borrowers through its Inspect query,
+  // which the wasm bindings don't expose, so this is composed from the
+  // addresses listed by LiquidationTargets and their AccountBalances. It is
+  // empty for a healthy market, and only nodes running with the liquidator
+  // query enabled serve LiquidationTargets. The limit bounds the targets
+  // inspected per call, not only the borrowers returned.
+  // Expect to returns LiquidatableBorrowersResponse.
+  LiquidatableBorrowers(LiquidatableBorrowersParams),
+  // SpecialBorrowLimit returns the borrow limit of an address including the
+  // collateral weight bonus of the special asset pairs. The module already
+  // applies the special pairs when computing the AccountSummary borrow limit,
+  // so it is composed from that query.
+  // Expect to returns SpecialBorrowLimitResponse.
+  SpecialBorrowLimit(SpecialBorrowLimitParams),
+  // InterestRateModel returns the points of the borrow interest rate curve
+  // of a denom, it is composed from the RegisteredTokens query.
+  // Expect to returns InterestRateModelResponse.
+  InterestRateModel(InterestRateModelParams),
+  // SimulateLiquidation returns the reward a liquidator would receive by
+  // repaying part of a borrower debt, it is composed from the AccountBalances,
+  // MarketSummary and RegisteredTokens queries. It doesn't check that the
+  // borrower is eligible for liquidation.
+  // Expect to returns SimulateLiquidationResponse.
+  SimulateLiquidation(SimulateLiquidationParams),
+  // CapsUsage returns how much of the supply and borrow caps of a denom is
+  // used, it is composed from the MarketSummary and RegisteredTokens queries.
+  // Expect to returns CapsUsageResponse.
+  CapsUsage(CapsUsageParams),
+  // BatchAccountSummary returns the account summary of a list of addresses,
+  // it is composed from one AccountSummary query per address.
+  // Expect to returns BatchAccountSummaryResponse.
+  BatchAccountSummary(BatchAccountSummaryParams),
+  // AllReserves returns the reserves of every registered token, it is
+  // composed from the RegisteredTokens and MarketSummary queries.
+  // Expect to returns AllReservesResponse.
+  AllReserves(AllReservesParams),
+  // MaxCollateralize returns the uTokens of an address that can still be
+  // collateralized, composed from the bank balance and AccountBalances.
+  // Expect to returns MaxCollateralizeResponse.
+  MaxCollateralize(MaxCollateralizeParams),
+  // SimulateSupply returns the uTokens minted by supplying an amount at the
+  // current uToken exchange rate, composed from the MarketSummary query.
+  // Expect to returns SimulateSupplyResponse.
+  SimulateSupply(SimulateSupplyParams),
+  // BadDebtTotal returns the debt of every bad debt position summed by denom
+  // along with its USD value, composed from the BadDebts, AccountBalances
+  // and MarketSummary queries.
+  // Expect to returns BadDebtTotalResponse.
+  BadDebtTotal(BadDebtTotalParams),
+  // TopSupplyMarkets returns the registered base denoms with the highest
+  // supply APY, composed from the RegisteredTokens and MarketSummary queries.
+  // Expect to returns TopSupplyMarketsResponse.
+  TopSupplyMarkets(TopSupplyMarketsParams),
+  // UTokenSupply returns the uTokens in circulation of a base denom,
+  // composed from the bank module supply query.
+  // Expect to returns UTokenSupplyResponse.
+  UTokenSupply(UTokenSupplyParams),
+  // DenomStatus returns if a base denom is registered and which of its
+  // uses are enabled, composed from the RegisteredTokens query.
+  // Expect to returns DenomStatusResponse.
+  DenomStatus(DenomStatusParams),
+  // MarketEnabled returns which actions the governance left enabled on the
+  // market of a registered base denom, composed from the RegisteredTokens
+  // query.
+  // Expect to returns MarketEnabledResponse.
+  MarketEnabled(MarketEnabledParams),
+  // BorrowFactors returns the collateral weight and the borrow factor the
+  // module uses in the borrow limit of a registered base denom, composed
+  // from the RegisteredTokens query.
+  // Expect to returns BorrowFactorsResponse.
+  BorrowFactors(BorrowFactorsParams),
+  // LiquidationPrice returns the oracle price of a collateral denom at
+  // which the position of an address becomes liquidatable, composed from
+  // the RegisteredTokens, AccountSummary, AccountBalances and MarketSummary
+  // queries.
+  // Expect to returns LiquidationPriceResponse.
+  LiquidationPrice(LiquidationPriceParams),
+  // CollateralValueAtPrice returns the collateral value of an address with
+  // some prices replaced, composed from the AccountBalances and
+  // MarketSummary queries.
+  // Expect to returns CollateralValueAtPriceResponse.
+  CollateralValueAtPrice(CollateralValueAtPriceParams),
+  // MarketRow returns the figures a frontend shows for a market, composed
+  // from the RegisteredTokens and MarketSummary queries.
+  // Expect to returns MarketRowResponse.
+  MarketRow(MarketRowParams),
+  // ReserveInfo returns the reserve factor of a registered base denom with
+  // the reserves the market has collected and their USD value, composed
+  // from the RegisteredTokens and MarketSummary queries.
+  // Expect to returns ReserveInfoResponse.
+  ReserveInfo(ReserveInfoParams),
+  // TimeToLiquidation estimates the blocks until the interest accrued on
+  // the borrows of an address makes its position liquidatable, composed
+  // from the AccountSummary, AccountBalances and MarketSummary queries.
+  // Expect to returns TimeToLiquidationResponse.
+  TimeToLiquidation(TimeToLiquidationParams),
+}
+
+impl UmeeQueryLeverage {
+  // validate checks the inputs of the query before it reaches the chain:
+  // the addresses have to be valid for the api, the denoms non empty and
+  // the amounts above zero.
+  pub fn validate(&self, api: &dyn Api) -> StdResult<()> {
+    match self {
+      UmeeQueryLeverage::LeverageParameters(_)
+      | UmeeQueryLeverage::RegisteredTokens(_)
+      | UmeeQueryLeverage::LiquidationTargets(_)
+      | UmeeQueryLeverage::BadDebts(_)
+      | UmeeQueryLeverage::AllReserves(_)
+      | UmeeQueryLeverage::BadDebtTotal(_)
+      | UmeeQueryLeverage::TopSupplyMarkets(_) => Ok(()),
+      UmeeQueryLeverage::MarketSummary(params) => check_denom("market_summary", &params.denom),
+      UmeeQueryLeverage::AccountBalances(params) => {
+        check_address(api, "account_balances", &params.address)
+      }
+      UmeeQueryLeverage::AccountSummary(params) => {
+        check_address(api, "account_summary", &params.address)
+      }
+      UmeeQueryLeverage::MaxWithdraw(params) => {
+        check_address(api, "max_withdraw", &params.address)?;
+        check_denom("max_withdraw", &params.denom)
+      }
+      UmeeQueryLeverage::MaxBorrow(params) => {
+        check_address(api, "max_borrow", &params.address)?;
+        check_denom("max_borrow", &params.denom)
+      }
+      UmeeQueryLeverage::AvailableWithdraw(params) => {
+        check_denom("available_withdraw", &params.denom)
+      }
+      UmeeQueryLeverage::LiquidatableBorrowers(params) => {
+        if let Some(start_after) = &params.start_after {
+          check_address(api, "liquidatable_borrowers", start_after)?;
+        }
+        check_denom("liquidatable_borrowers", &params.denom)
+      }
+      UmeeQueryLeverage::SpecialBorrowLimit(params) => {
+        check_address(api, "special_borrow_limit", &params.address)
+      }
+      UmeeQueryLeverage::InterestRateModel(params) => {
+        check_denom("interest_rate_model", &params.denom)
+      }
+      UmeeQueryLeverage::SimulateLiquidation(params) => {
+        check_address(api, "simulate_liquidation", &params.borrower)?;
+        check_denom("simulate_liquidation", &params.repay.denom)?;
+        check_amount("simulate_liquidation", params.repay.amount)?;
+        check_denom("simulate_liquidation", &params.reward_denom)
+      }
+      UmeeQueryLeverage::CapsUsage(params) => check_denom("caps_usage", &params.denom),
+      UmeeQueryLeverage::BatchAccountSummary(params) => params
+        .addresses
+        .iter()
+        .try_for_each(|address| check_address(api, "batch_account_summary", address)),
+      UmeeQueryLeverage::MaxCollateralize(params) => {
+        check_address(api, "max_collateralize", &params.address)?;
+        check_denom("max_collateralize", &params.denom)
+      }
+      UmeeQueryLeverage::SimulateSupply(params) => {
+        check_denom("simulate_supply", &params.denom)?;
+        check_amount("simulate_supply", params.amount)
+      }
+      UmeeQueryLeverage::UTokenSupply(params) => check_denom("u_token_supply", &params.denom),
+      UmeeQueryLeverage::DenomStatus(params) => check_denom("denom_status", &params.denom),
+      UmeeQueryLeverage::MarketEnabled(params) => check_denom("market_enabled", &params.denom),
+      UmeeQueryLeverage::BorrowFactors(params) => check_denom("borrow_factors", &params.denom),
+      UmeeQueryLeverage::MarketRow(params) => check_denom("market_row", &params.denom),
+      UmeeQueryLeverage::ReserveInfo(params) => check_denom("reserve_info", &params.denom),
+      UmeeQueryLeverage::CollateralValueAtPrice(params) => {
+        check_address(api, "collateral_value_at_price", &params.address)?;
+        params.price_overrides.iter().try_for_each(|price| {
+          if price.denom.trim().is_empty() {
+            return Err(StdError::generic_err(
+              "collateral_value_at_price: empty denom",
+            ));
+          }
+          Ok(())
+        })
+      }
+      UmeeQueryLeverage::LiquidationPrice(params) => {
+        check_address(api, "liquidation_price", &params.address)?;
+        check_denom("liquidation_price", &params.collateral_denom)
+      }
+      UmeeQueryLeverage::TimeToLiquidation(params) => {
+        check_address(api, "time_to_liquidation", &params.address)?;
+        if params.blocks_per_year == 0 {
+          return Err(StdError::generic_err(
+            "time_to_liquidation: zero blocks per year",
+          ));
+        }
+        Ok(())
+      }
+    }
+  }
+
+  // denoms_mut returns every denom the query names, for a caller to
+  // rewrite them before the query is validated and sent.
+  pub fn denoms_mut(&mut self) -> Vec<&mut String> {
+    match self {
+      UmeeQueryLeverage::LeverageParameters(_)
+      | UmeeQueryLeverage::RegisteredTokens(_)
+      | UmeeQueryLeverage::AccountBalances(_)
+      | UmeeQueryLeverage::AccountSummary(_)
+      | UmeeQueryLeverage::LiquidationTargets(_)
+      | UmeeQueryLeverage::BadDebts(_)
+      | UmeeQueryLeverage::SpecialBorrowLimit(_)
+      | UmeeQueryLeverage::BatchAccountSummary(_)
+      | UmeeQueryLeverage::AllReserves(_)
+      | UmeeQueryLeverage::BadDebtTotal(_)
+      | UmeeQueryLeverage::TopSupplyMarkets(_)
+      | UmeeQueryLeverage::TimeToLiquidation(_) => vec![],
+      UmeeQueryLeverage::MarketSummary(params) => vec![&mut params.denom],
+      UmeeQueryLeverage::MaxWithdraw(params) => vec![&mut params.denom],
+      UmeeQueryLeverage::MaxBorrow(params) => vec![&mut params.denom],
+      UmeeQueryLeverage::AvailableWithdraw(params) => vec![&mut params.denom],
+      UmeeQueryLeverage::LiquidatableBorrowers(params) => vec![&mut params.denom],
+      UmeeQueryLeverage::InterestRateModel(params) => vec![&mut params.denom],
+      UmeeQueryLeverage::SimulateLiquidation(params) => {
+        vec![&mut params.repay.denom, &mut params.reward_denom]
+      }
+      UmeeQueryLeverage::CapsUsage(params) => vec![&mut params.denom],
+      UmeeQueryLeverage::MaxCollateralize(params) => vec![&mut params.denom],
+      UmeeQueryLeverage::SimulateSupply(params) => vec![&mut params.denom],
+      UmeeQueryLeverage::UTokenSupply(params) => vec![&mut params.denom],
+      UmeeQueryLeverage::DenomStatus(params) => vec![&mut params.denom],
+      UmeeQueryLeverage::MarketEnabled(params) => vec![&mut params.denom],
+      UmeeQueryLeverage::BorrowFactors(params) => vec![&mut params.denom],
+      UmeeQueryLeverage::MarketRow(params) => vec![&mut params.denom],
+      UmeeQueryLeverage::ReserveInfo(params) => vec![&mut params.denom],
+      UmeeQueryLeverage::CollateralValueAtPrice(params) => params
+        .price_overrides
+        .iter_mut()
+        .map(|price| &mut price.denom)
+        .collect(),
+      UmeeQueryLeverage::LiquidationPrice(params) => vec![&mut params.collateral_denom],
+    }
+  }
+}
+
+fn check_address(api: &dyn Api, query: &str, address: &Addr) -> StdResult<()> {
+  api
+    .addr_validate(address.as_str())
+    .map(|_| ())
+    .map_err(|err| {
+      StdError::generic_err(format!("{}: invalid address {}: {}", query, address, err))
+    })
+}
+
+fn check_denom(query: &str, denom: &str) -> StdResult<()> {
+  if denom.trim().is_empty() {
+    return Err(StdError::generic_err(format!("{}: empty denom", query)));
+  }
+  if looks_like_symbol_denom(denom) {
+    return Err(StdError::generic_err(format!(
+      "{}: {} looks like an oracle symbol denom, the leverage module takes \
+       the base denom of the token (e.g. uumee or an ibc/ denom)",
+      query, denom
+    )));
+  }
+  Ok(())
+}
+
+fn check_amount(query: &str, amount: Uint128) -> StdResult<()> {
+  if amount.is_zero() {
+    return Err(StdError::generic_err(format!("{}: zero amount", query)));
+  }
+  Ok(())
+}
+
+// query_for builds the leverage query answering an action on a denom for
+// generic tooling. The account actions (supplied, collateral, borrowed and
+// account_summary) are per address and ignore the denom, the caller picks
+// the denom out of the response.
+pub fn query_for(action: &str, denom: &str, address: Option<&str>) -> StdResult<UmeeQueryLeverage> {
+  let address = || {
+    address
+      .map(Addr::unchecked)
+      .ok_or_else(|| StdError::generic_err(format!("action {} requires an address", action)))
+  };
+  let query = match action {
+    "market_size" | "market_summary" => {
+      UmeeQueryLeverage::MarketSummary(MarketSummaryParams::new(denom))
+    }
+    "supplied" | "collateral" | "borrowed" => {
+      UmeeQueryLeverage::AccountBalances(AccountBalancesParams::new(address()?))
+    }
+    "account_summary" => UmeeQueryLeverage::AccountSummary(AccountSummaryParams::new(address()?)),
+    "max_withdraw" => UmeeQueryLeverage::MaxWithdraw(MaxWithdrawParams::new(address()?, denom)),
+    "max_borrow" => UmeeQueryLeverage::MaxBorrow(MaxBorrowParams::new(address()?, denom)),
+    "available_withdraw" => {
+      UmeeQueryLeverage::AvailableWithdraw(AvailableWithdrawParams::new(denom))
+    }
+    "interest_rate_model" => {
+      UmeeQueryLeverage::InterestRateModel(InterestRateModelParams::new(denom))
+    }
+    "caps_usage" => UmeeQueryLeverage::CapsUsage(CapsUsageParams::new(denom)),
+    "utoken_supply" => UmeeQueryLeverage::UTokenSupply(UTokenSupplyParams {
+      denom: denom.to_string(),
+    }),
+    _ => return Err(StdError::generic_err(format!("unknown action {}", action))),
+  };
+  Ok(query)
 }
 
 // LeverageParametersParams params to query LeverageParameters.
@@ -52,38 +364,123 @@ pub struct LeverageParametersResponse {
 pub struct RegisteredTokensParams {}
 
 // RegisteredTokensResponse response struct of RegisteredTokens query.
-#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug, Default)]
 pub struct RegisteredTokensResponse {
+  #[serde(default)]
   pub registry: Vec<Token>,
 }
 
+impl RegisteredTokensResponse {
+  // get returns the registered token of a base denom.
+  pub fn get(&self, denom: &str) -> Option<&Token> {
+    self
+      .registry
+      .iter()
+      .find(|token| token.base_denom() == denom)
+  }
+
+  // into_map returns the registered tokens keyed by their base denom.
+  pub fn into_map(self) -> BTreeMap<String, Token> {
+    self
+      .registry
+      .into_iter()
+      .map(|token| (token.base_denom().to_string(), token))
+      .collect()
+  }
+}
+
 // MarketSummaryParams params to query MarketSummary.
 #[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
 pub struct MarketSummaryParams {
   pub denom: String,
 }
 
-// MarketSummary base asset's current borrowing and supplying conditions.
-#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+impl MarketSummaryParams {
+  pub fn new(denom: impl Into<String>) -> Self {
+    MarketSummaryParams {
+      denom: denom.into(),
+    }
+  }
+}
+
+// MarketSummary base asset's current borrowing and supplying conditions,
+// the amounts omitted for a market without supply default to zero.
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug, Default)]
 pub struct MarketSummaryResponse {
-  symbol_denom: String,
-  exponent: u32,
-  oracle_price: Decimal256,
-  utoken_exchange_rate: Decimal256,
-  supply_apy: Decimal256,
-  borrow_apy: Decimal256,
-  supplied: Decimal256,
-  reserved: Decimal256,
-  collateral: Decimal256,
-  borrowed: Decimal256,
-  liquidity: Decimal256,
-  maximum_borrow: Decimal256,
-  maximum_collateral: Decimal256,
-  minimum_liquidity: Decimal256,
-  utoken_supply: Decimal256,
-  available_borrow: Decimal256,
-  available_withdraw: Decimal256,
-  available_collateralize: Decimal256,
+  pub symbol_denom: String,
+  #[serde(default)]
+  pub exponent: u32,
+  #[serde(default)]
+  pub oracle_price: Decimal256,
+  #[serde(default)]
+  pub utoken_exchange_rate: Decimal256,
+  #[serde(default)]
+  pub supply_apy: Decimal256,
+  #[serde(default)]
+  pub borrow_apy: Decimal256,
+  #[serde(default)]
+  pub supplied: Decimal256,
+  #[serde(default)]
+  pub reserved: Decimal256,
+  #[serde(default)]
+  pub collateral: Decimal256,
+  #[serde(default)]
+  pub borrowed: Decimal256,
+  #[serde(default)]
+  pub liquidity: Decimal256,
+  #[serde(default)]
+  pub maximum_borrow: Decimal256,
+  #[serde(default)]
+  pub maximum_collateral: Decimal256,
+  #[serde(default)]
+  pub minimum_liquidity: Decimal256,
+  #[serde(default)]
+  pub utoken_supply: Decimal256,
+  #[serde(default)]
+  pub available_borrow: Decimal256,
+  #[serde(default)]
+  pub available_withdraw: Decimal256,
+  #[serde(default)]
+  pub available_collateralize: Decimal256,
+}
+
+impl MarketSummaryResponse {
+  // utilization returns the borrowed portion of the supplied tokens, zero
+  // for a market without any supply.
+  pub fn utilization(&self) -> Decimal256 {
+    if self.supplied.is_zero() {
+      return Decimal256::zero();
+    }
+    self.borrowed / self.supplied
+  }
+}
+
+// AllReservesParams params to query AllReserves.
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct AllReservesParams {}
+
+// AllReservesResponse response struct of AllReserves query, the markets
+// without reserves are left out.
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct AllReservesResponse {
+  pub reserves: Vec<Coin>,
+}
+
+// MaxCollateralizeParams params to query MaxCollateralize, the denom
+// can be given as the base or the uToken denom.
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct MaxCollateralizeParams {
+  pub address: Addr,
+  pub denom: String,
+}
+
+// MaxCollateralizeResponse response struct of MaxCollateralize query, the
+// collateralized uTokens are escrowed by the module and not part of the
+// available amount.
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct MaxCollateralizeResponse {
+  pub available: Coin,
+  pub collateralized: Coin,
 }
 
 // AccountBalancesParams params to query AccountBalances.
@@ -92,11 +489,22 @@ pub struct AccountBalancesParams {
   pub address: Addr,
 }
 
+impl AccountBalancesParams {
+  pub fn new(address: Addr) -> Self {
+    AccountBalancesParams { address }
+  }
+}
+
 // AccountBalancesResponse response struct of AccountBalances query.
-#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+// The module omits the empty lists of an account that never
+// supplied or borrowed, so they default to empty.
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug, Default)]
 pub struct AccountBalancesResponse {
+  #[serde(default)]
   pub supplied: Vec<Coin>,
+  #[serde(default)]
   pub collateral: Vec<Coin>,
+  #[serde(default)]
   pub borrowed: Vec<Coin>,
 }
 
@@ -106,16 +514,74 @@ pub struct AccountSummaryParams {
   pub address: Addr,
 }
 
+impl AccountSummaryParams {
+  pub fn new(address: Addr) -> Self {
+    AccountSummaryParams { address }
+  }
+}
+
 // AccountSummaryResponse response struct of AccountSummary query.
-#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+// The values omitted for a fresh account default to zero.
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug, Default)]
 pub struct AccountSummaryResponse {
+  #[serde(default)]
   pub supplied_value: Decimal256,
+  #[serde(default)]
   pub collateral_value: Decimal256,
+  #[serde(default)]
   pub borrowed_value: Decimal256,
+  #[serde(default)]
   pub borrow_limit: Decimal256,
+  #[serde(default)]
   pub liquidation_threshold: Decimal256,
 }
 
+impl AccountSummaryResponse {
+  // health_factor returns the liquidation threshold over the borrowed value,
+  // None for an account without debt whose health factor is infinite. A
+  // ratio above the Decimal256 range is capped at its max.
+  pub fn health_factor(&self) -> Option<Decimal256> {
+    if self.borrowed_value.is_zero() {
+      return None;
+    }
+    Some(
+      self
+        .liquidation_threshold
+        .checked_div(self.borrowed_value)
+        .unwrap_or(Decimal256::MAX),
+    )
+  }
+
+  // borrow_limit_used returns the borrowed value over the borrow limit, zero
+  // without debt and one for a debt without any borrow limit left.
+  pub fn borrow_limit_used(&self) -> Decimal256 {
+    if self.borrowed_value.is_zero() {
+      return Decimal256::zero();
+    }
+    if self.borrow_limit.is_zero() {
+      return Decimal256::one();
+    }
+    self
+      .borrowed_value
+      .checked_div(self.borrow_limit)
+      .unwrap_or(Decimal256::MAX)
+  }
+}
+
+// BatchAccountSummaryParams params to query BatchAccountSummary.
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct BatchAccountSummaryParams {
+  pub addresses: Vec<Addr>,
+}
+
+// BatchAccountSummaryResponse response struct of BatchAccountSummary query,
+// the addresses without any position are listed in skipped.
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct BatchAccountSummaryResponse {
+  pub summaries: Vec<(Addr, AccountSummaryResponse)>,
+  pub skipped: Vec<Addr>,
+}
+
 // LiquidationTargetsParams params to query LiquidationTargets.
 #[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
 pub struct LiquidationTargetsParams {}
@@ -123,6 +589,7 @@ pub struct LiquidationTargetsParams {}
 // LiquidationTargetsResponse response struct of LiquidationTargets.
 #[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
 pub struct LiquidationTargetsResponse {
+  #[serde(default)]
   pub targets: Vec<String>,
 }
 
@@ -131,15 +598,37 @@ pub struct BadDebtsParams {}
 
 #[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
 pub struct BadDebtsResponse {
+  #[serde(default)]
   pub targets: Vec<BadDebt>,
 }
 
+// BadDebtTotalParams params to query BadDebtTotal.
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct BadDebtTotalParams {}
+
+// BadDebtTotalResponse response struct of BadDebtTotal query, total_value
+// is the USD value of the by_denom amounts.
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct BadDebtTotalResponse {
+  pub total_value: Decimal256,
+  pub by_denom: Vec<Coin>,
+}
+
 #[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
 pub struct MaxWithdrawParams {
   pub address: Addr,
   pub denom: String,
 }
 
+impl MaxWithdrawParams {
+  pub fn new(address: Addr, denom: impl Into<String>) -> Self {
+    MaxWithdrawParams {
+      address,
+      denom: denom.into(),
+    }
+  }
+}
+
 #[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
 pub struct MaxWithdrawResponse {
   pub u_tokens: Coin,
@@ -152,7 +641,502 @@ pub struct MaxBorrowParams {
   pub denom: String,
 }
 
+impl MaxBorrowParams {
+  pub fn new(address: Addr, denom: impl Into<String>) -> Self {
+    MaxBorrowParams {
+      address,
+      denom: denom.into(),
+    }
+  }
+}
+
 #[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
 pub struct MaxBorrowResponse {
+  #[serde(default)]
   pub tokens: Vec<Coin>,
 }
+
+// AvailableWithdrawParams params to query AvailableWithdraw.
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct AvailableWithdrawParams {
+  pub denom: String,
+}
+
+impl AvailableWithdrawParams {
+  pub fn new(denom: impl Into<String>) -> Self {
+    AvailableWithdrawParams {
+      denom: denom.into(),
+    }
+  }
+}
+
+// AvailableWithdrawResponse response struct of AvailableWithdraw query.
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct AvailableWithdrawResponse {
+  pub amount: Coin,
+}
+
+// LiquidatableBorrowersParams params to query LiquidatableBorrowers.
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct LiquidatableBorrowersParams {
+  pub denom: String,
+  pub limit: Option<u32>,
+  pub start_after: Option<Addr>,
+}
+
+// LiquidatableBorrowersResponse response struct of LiquidatableBorrowers
+// query, borrowers are sorted by address. start_after is the last inspected
+// target to continue from, None once every target was inspected.
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct LiquidatableBorrowersResponse {
+  pub borrowers: Vec<(Addr, Coin)>,
+  pub start_after: Option<Addr>,
+}
+
+// TopSupplyMarketsParams params to query TopSupplyMarkets, the limit is
+// capped by the contract.
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct TopSupplyMarketsParams {
+  pub limit: u32,
+}
+
+// TopSupplyMarketsResponse response struct of TopSupplyMarkets query,
+// markets are sorted by descending supply APY then by denom.
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct TopSupplyMarketsResponse {
+  pub markets: Vec<(String, Decimal256)>,
+}
+
+// UTokenSupplyParams params to query UTokenSupply, the denom is the base
+// denom of the market.
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct UTokenSupplyParams {
+  pub denom: String,
+}
+
+// UTokenSupplyResponse response struct of UTokenSupply query.
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct UTokenSupplyResponse {
+  pub utoken_supply: Coin,
+}
+
+// DenomStatusParams params to query DenomStatus.
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct DenomStatusParams {
+  pub denom: String,
+}
+
+// DenomStatusResponse response struct of DenomStatus query, the flags of
+// a denom that isn't registered are all false. A blacklisted token can't
+// be used as collateral, as its price is treated as zero.
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug, Default)]
+pub struct DenomStatusResponse {
+  pub registered: bool,
+  pub collateral_enabled: bool,
+  pub borrow_enabled: bool,
+  pub blacklisted: bool,
+}
+
+// MarketEnabledParams params to query MarketEnabled.
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct MarketEnabledParams {
+  pub denom: String,
+}
+
+// MarketEnabledResponse response struct of MarketEnabled query, every
+// action of a blacklisted token is disabled.
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct MarketEnabledResponse {
+  pub supply_enabled: bool,
+  pub borrow_enabled: bool,
+  pub collateral_enabled: bool,
+}
+
+// BorrowFactorsParams params to query BorrowFactors.
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct BorrowFactorsParams {
+  pub denom: String,
+}
+
+// BorrowFactorsResponse response struct of BorrowFactors query, the
+// collateral value of the token counts towards the borrow limit weighted
+// by collateral_weight and its borrowed value by borrow_factor.
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct BorrowFactorsResponse {
+  pub collateral_weight: Decimal,
+  pub borrow_factor: Decimal,
+}
+
+// LiquidationPriceParams params to query LiquidationPrice.
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct LiquidationPriceParams {
+  pub address: Addr,
+  pub collateral_denom: String,
+}
+
+// LiquidationPriceResponse response struct of LiquidationPrice query, the
+// USD price of one display unit of the collateral, all other prices being
+// unchanged. Zero means no price of the collateral liquidates the position.
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct LiquidationPriceResponse {
+  pub price: Decimal256,
+}
+
+// TimeToLiquidationParams params to query TimeToLiquidation, the blocks
+// per year convert the yearly borrow apy of the markets to blocks.
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct TimeToLiquidationParams {
+  pub address: Addr,
+  pub blocks_per_year: u64,
+}
+
+// TimeToLiquidationResponse response struct of TimeToLiquidation query,
+// None for a position that is already liquidatable or that interest never
+// makes liquidatable, such as one without debt.
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct TimeToLiquidationResponse {
+  pub blocks: Option<u64>,
+}
+
+// CollateralValueAtPriceParams params to query CollateralValueAtPrice, a
+// price override is the USD price of one display unit of a token, keyed by
+// its base or symbol denom. The other tokens keep their oracle price.
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct CollateralValueAtPriceParams {
+  pub address: Addr,
+  pub price_overrides: Vec<DecCoin>,
+}
+
+// CollateralValueAtPriceResponse response struct of CollateralValueAtPrice
+// query, the collateral valued like the collateral_value of the account
+// summary but at the overridden prices.
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct CollateralValueAtPriceResponse {
+  pub collateral_value: Decimal256,
+}
+
+// MarketRowParams params to query MarketRow.
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct MarketRowParams {
+  pub denom: String,
+}
+
+// MarketRowResponse response struct of MarketRow query, the totals are in
+// base tokens and the oracle price is the USD price of one display unit.
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct MarketRowResponse {
+  pub denom: String,
+  pub symbol_denom: String,
+  pub supply_apy: Decimal256,
+  pub borrow_apy: Decimal256,
+  pub total_supplied: Decimal256,
+  pub total_borrowed: Decimal256,
+  pub utilization: Decimal256,
+  pub oracle_price: Decimal256,
+}
+
+// ReserveInfoParams params to query ReserveInfo.
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct ReserveInfoParams {
+  pub denom: String,
+}
+
+// ReserveInfoResponse response struct of ReserveInfo query, the reserves
+// are rounded down to whole base tokens and valued at the oracle price.
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct ReserveInfoResponse {
+  pub reserve_factor: Decimal,
+  pub reserves: Coin,
+  pub reserves_value: Decimal256,
+}
+
+// SpecialBorrowLimitParams params to query SpecialBorrowLimit.
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct SpecialBorrowLimitParams {
+  pub address: Addr,
+}
+
+impl SpecialBorrowLimitParams {
+  pub fn new(address: Addr) -> Self {
+    SpecialBorrowLimitParams { address }
+  }
+}
+
+// SpecialBorrowLimitResponse response struct of SpecialBorrowLimit query.
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct SpecialBorrowLimitResponse {
+  pub borrow_limit: Decimal256,
+}
+
+// InterestRateModelParams params to query InterestRateModel.
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct InterestRateModelParams {
+  pub denom: String,
+}
+
+impl InterestRateModelParams {
+  pub fn new(denom: impl Into<String>) -> Self {
+    InterestRateModelParams {
+      denom: denom.into(),
+    }
+  }
+}
+
+// InterestRateModelResponse response struct of InterestRateModel query,
+// the borrow rate goes linearly from base_rate at zero utilization to
+// kink_rate at kink_utilization and then to max_rate at full utilization.
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct InterestRateModelResponse {
+  pub base_rate: Decimal,
+  pub kink_utilization: Decimal,
+  pub kink_rate: Decimal,
+  pub max_rate: Decimal,
+}
+
+// SimulateLiquidationParams params to query SimulateLiquidation, the
+// reward_denom can be a base denom or an uToken denom.
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct SimulateLiquidationParams {
+  pub borrower: Addr,
+  pub repay: Coin,
+  pub reward_denom: String,
+}
+
+// SimulateLiquidationResponse response struct of SimulateLiquidation query,
+// the repay is reduced when the borrower debt or collateral can't cover it.
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct SimulateLiquidationResponse {
+  pub repay: Coin,
+  pub reward: Coin,
+}
+
+// SimulateSupplyParams params to query SimulateSupply, the amount is in
+// base tokens of the denom.
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct SimulateSupplyParams {
+  pub denom: String,
+  pub amount: Uint128,
+}
+
+// SimulateSupplyResponse response struct of SimulateSupply query, the
+// uTokens are rounded down like the module mints them.
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct SimulateSupplyResponse {
+  pub utokens: Coin,
+}
+
+// CapsUsageParams params to query CapsUsage.
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct CapsUsageParams {
+  pub denom: String,
+}
+
+impl CapsUsageParams {
+  pub fn new(denom: impl Into<String>) -> Self {
+    CapsUsageParams {
+      denom: denom.into(),
+    }
+  }
+}
+
+// CapsUsageResponse response struct of CapsUsage query. The supply cap is
+// the token max supply, zero without limit, and the borrow cap is the
+// supplied amount times the token max supply utilization. The used values
+// are the ratios of the current amounts to the caps, zero without cap.
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct CapsUsageResponse {
+  pub supply_used: Decimal,
+  pub supply_cap: Uint128,
+  pub borrow_used: Decimal,
+  pub borrow_cap: Uint128,
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use cosmwasm_std::from_json;
+  use cosmwasm_std::testing::MockApi;
+
+  const TOKEN_JSON: &str = r#"{"base_denom":"uumee","reserve_factor":"0.1","collateral_weight":"0.35","liquidation_threshold":"0.5","base_borrow_rate":"0.02","kink_borrow_rate":"0.15","max_borrow_rate":"1.5","kink_utilization":"0.7","liquidation_incentive":"0.1","symbol_denom":"UMEE","exponent":6,"enable_msg_supply":true,"enable_msg_borrow":true,"blacklist":false,"max_collateral_share":"1","max_supply_utilization":"0.9","min_collateral_liquidity":"0.3","max_supply":"100000000000","historic_medians":24}"#;
+
+  #[test]
+  fn zero_debt_account_summary() {
+    let supplier = AccountSummaryResponse {
+      supplied_value: Decimal256::percent(100_000),
+      collateral_value: Decimal256::percent(80_000),
+      borrow_limit: Decimal256::percent(40_000),
+      liquidation_threshold: Decimal256::percent(50_000),
+      ..AccountSummaryResponse::default()
+    };
+    assert_eq!(None, supplier.health_factor());
+    assert_eq!(Decimal256::zero(), supplier.borrow_limit_used());
+
+    let fresh = AccountSummaryResponse::default();
+    assert_eq!(None, fresh.health_factor());
+    assert_eq!(Decimal256::zero(), fresh.borrow_limit_used());
+
+    let borrower = AccountSummaryResponse {
+      borrowed_value: Decimal256::percent(20_000),
+      ..supplier
+    };
+    assert_eq!(Some(Decimal256::percent(250)), borrower.health_factor());
+    assert_eq!(Decimal256::percent(50), borrower.borrow_limit_used());
+    let no_limit = AccountSummaryResponse {
+      borrow_limit: Decimal256::zero(),
+      ..borrower
+    };
+    assert_eq!(Decimal256::one(), no_limit.borrow_limit_used());
+
+    let dust_debt = AccountSummaryResponse {
+      borrowed_value: Decimal256::raw(1),
+      borrow_limit: Decimal256::raw(1),
+      liquidation_threshold: Decimal256::MAX,
+      ..AccountSummaryResponse::default()
+    };
+    assert_eq!(Some(Decimal256::MAX), dust_debt.health_factor());
+  }
+
+  #[test]
+  fn registered_tokens_lookup() {
+    let atom = TOKEN_JSON
+      .replace("uumee", "uatom")
+      .replace("\"0.1\"", "\"0.05\"");
+    let json = format!(r#"{{"registry":[{},{}]}}"#, TOKEN_JSON, atom);
+    let registry: RegisteredTokensResponse = from_json(json.as_bytes()).unwrap();
+    assert_eq!(
+      Decimal::percent(5),
+      registry.get("uatom").unwrap().liquidation_incentive()
+    );
+    assert!(registry.get("ujuno").is_none());
+
+    let map = registry.into_map();
+    assert_eq!(vec!["uatom", "uumee"], map.keys().collect::<Vec<_>>());
+    assert_eq!("uumee", map["uumee"].base_denom());
+  }
+
+  #[test]
+  fn sparse_responses() {
+    let market: MarketSummaryResponse = from_json(br#"{"symbol_denom":"UMEE"}"#).unwrap();
+    assert_eq!("UMEE", market.symbol_denom);
+    assert_eq!(0, market.exponent);
+    assert_eq!(Decimal256::zero(), market.reserved);
+    assert_eq!(Decimal256::zero(), market.available_withdraw);
+    assert!(from_json::<MarketSummaryResponse>(b"{}").is_err());
+
+    let registry: RegisteredTokensResponse = from_json(b"{}").unwrap();
+    assert!(registry.registry.is_empty());
+    let targets: LiquidationTargetsResponse = from_json(b"{}").unwrap();
+    assert!(targets.targets.is_empty());
+    let bad_debts: BadDebtsResponse = from_json(b"{}").unwrap();
+    assert!(bad_debts.targets.is_empty());
+    let max_borrow: MaxBorrowResponse = from_json(b"{}").unwrap();
+    assert!(max_borrow.tokens.is_empty());
+    let balances: AccountBalancesResponse = from_json(b"{}").unwrap();
+    assert_eq!(AccountBalancesResponse::default(), balances);
+    let summary: AccountSummaryResponse = from_json(b"{}").unwrap();
+    assert_eq!(AccountSummaryResponse::default(), summary);
+  }
+
+  #[test]
+  fn query_for_actions() {
+    let addr = Some("umee1addr");
+    let market = UmeeQueryLeverage::MarketSummary(MarketSummaryParams::new("uumee"));
+    assert_eq!(market, query_for("market_size", "uumee", None).unwrap());
+    assert_eq!(market, query_for("market_summary", "uumee", addr).unwrap());
+
+    let balances =
+      UmeeQueryLeverage::AccountBalances(AccountBalancesParams::new(Addr::unchecked("umee1addr")));
+    for action in ["supplied", "collateral", "borrowed"] {
+      assert_eq!(balances, query_for(action, "uumee", addr).unwrap());
+    }
+    assert_eq!(
+      UmeeQueryLeverage::AccountSummary(AccountSummaryParams::new(Addr::unchecked("umee1addr"))),
+      query_for("account_summary", "uumee", addr).unwrap()
+    );
+    assert_eq!(
+      UmeeQueryLeverage::MaxWithdraw(MaxWithdrawParams::new(
+        Addr::unchecked("umee1addr"),
+        "uumee"
+      )),
+      query_for("max_withdraw", "uumee", addr).unwrap()
+    );
+    assert_eq!(
+      UmeeQueryLeverage::MaxBorrow(MaxBorrowParams::new(Addr::unchecked("umee1addr"), "uumee")),
+      query_for("max_borrow", "uumee", addr).unwrap()
+    );
+    assert_eq!(
+      UmeeQueryLeverage::AvailableWithdraw(AvailableWithdrawParams::new("uumee")),
+      query_for("available_withdraw", "uumee", None).unwrap()
+    );
+    assert_eq!(
+      UmeeQueryLeverage::InterestRateModel(InterestRateModelParams::new("uumee")),
+      query_for("interest_rate_model", "uumee", None).unwrap()
+    );
+    assert_eq!(
+      UmeeQueryLeverage::CapsUsage(CapsUsageParams::new("uumee")),
+      query_for("caps_usage", "uumee", None).unwrap()
+    );
+    assert_eq!(
+      UmeeQueryLeverage::UTokenSupply(UTokenSupplyParams {
+        denom: "uumee".to_string()
+      }),
+      query_for("utoken_supply", "uumee", None).unwrap()
+    );
+
+    let err = query_for("borrowed", "uumee", None).unwrap_err();
+    assert!(err
+      .to_string()
+      .contains("action borrowed requires an address"));
+    let err = query_for("lend", "uumee", addr).unwrap_err();
+    assert!(err.to_string().contains("unknown action lend"));
+  }
+
+  #[test]
+  fn validate_inputs() {
+    let api = MockApi::default();
+    let valid = [
+      UmeeQueryLeverage::RegisteredTokens(RegisteredTokensParams {}),
+      UmeeQueryLeverage::MarketSummary(MarketSummaryParams::new("uumee")),
+      UmeeQueryLeverage::MaxWithdraw(MaxWithdrawParams::new(Addr::unchecked("supplier"), "uumee")),
+      UmeeQueryLeverage::SimulateSupply(SimulateSupplyParams {
+        denom: "uumee".to_string(),
+        amount: Uint128::new(10),
+      }),
+    ];
+    for query in valid {
+      query.validate(&api).unwrap();
+    }
+
+    let invalid = [
+      (
+        UmeeQueryLeverage::MarketSummary(MarketSummaryParams::new(" ")),
+        "market_summary: empty denom",
+      ),
+      (
+        UmeeQueryLeverage::AccountBalances(AccountBalancesParams::new(Addr::unchecked("Supplier"))),
+        "account_balances: invalid address Supplier",
+      ),
+      (
+        UmeeQueryLeverage::MaxBorrow(MaxBorrowParams::new(Addr::unchecked("borrower"), "")),
+        "max_borrow: empty denom",
+      ),
+      (
+        UmeeQueryLeverage::BatchAccountSummary(BatchAccountSummaryParams {
+          addresses: vec![Addr::unchecked("borrower"), Addr::unchecked("")],
+        }),
+        "batch_account_summary: invalid address",
+      ),
+      (
+        UmeeQueryLeverage::SimulateSupply(SimulateSupplyParams {
+          denom: "uumee".to_string(),
+          amount: Uint128::zero(),
+        }),
+        "simulate_supply: zero amount",
+      ),
+    ];
+    for (query, expected) in invalid {
+      let err = query.validate(&api).unwrap_err();
+      assert!(err.to_string().contains(expected), "{}", err);
+    }
+  }
+}
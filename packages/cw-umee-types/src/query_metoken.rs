@@ -13,6 +13,22 @@ pub enum UmeeQueryMeToken {
   MetokenIndexPrices(MetokenIndexPricesParams),
 }
 
+impl UmeeQueryMeToken {
+  // asset_denoms_mut returns the base denoms of the index assets the query
+  // names, for a caller to rewrite them before the query is sent. The
+  // meToken denoms are not the denom of a leverage token and are left out.
+  pub fn asset_denoms_mut(&mut self) -> Vec<&mut String> {
+    match self {
+      UmeeQueryMeToken::MetokenParameters(_)
+      | UmeeQueryMeToken::MetokenIndexes(_)
+      | UmeeQueryMeToken::MetokenIndexbalances(_)
+      | UmeeQueryMeToken::MetokenIndexPrices(_) => vec![],
+      UmeeQueryMeToken::MetokenSwapfee(params) => vec![&mut params.asset],
+      UmeeQueryMeToken::MetokenRedeemfee(params) => vec![&mut params.asset_denom],
+    }
+  }
+}
+
 #[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
 pub struct MetokenParametersParams {}
 
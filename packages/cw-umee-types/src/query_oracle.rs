@@ -1,17 +1,20 @@
 use crate::aggregate_exchange_rate_prevote::AggregateExchangeRatePrevote;
 use crate::aggregate_exchange_rate_vote::AggregateExchangeRateVote;
 use crate::oracle_parameters::OracleParameters;
-use cosmwasm_std::{Addr, Decimal256};
+use crate::token::looks_like_base_denom;
+use cosmwasm_std::{Addr, Decimal256, StdError, StdResult};
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
 // UmeeQueryOracle defines  all the available queries
-// for the umee Oracle native module
+// for the umee Oracle native module, the aliases accept
+// the names of the native query fields too
 #[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
 #[serde(rename_all = "snake_case")]
 pub enum UmeeQueryOracle {
   // ExchangeRates returns an sdk.Dec representing the exchange rate
   // of an denom. Expect to returns ExchangeRatesResponse.
+  #[serde(alias = "get_exchange_rate_base")]
   ExchangeRates(ExchangeRatesParams),
   // ActiveExchangeRates returns all active denoms.
   // Expect to returns ActiveExchangeRatesResponse.
@@ -39,27 +42,195 @@ pub enum UmeeQueryOracle {
   AggregateVotes(AggregateVotesParams),
   // OracleParameters returns all oracle module parameters.
   // Expect to returns OracleParametersParams.
+  #[serde(alias = "oracle_params")]
   OracleParameters(OracleParametersParams),
   // Medians returns medians of all denoms,
   // or, if specified, returns a single median
+  #[serde(alias = "medians_params")]
   Medians(MediansParams),
   // MedianDeviations returns median deviations of all denoms,
   // or, if specified, returns a single median deviation
+  #[serde(alias = "median_deviations_params")]
   MedianDeviations(MedianDeviationsParams),
+  // ExchangeRatesBatch returns the exchange rates of a set of denoms,
+  // it is composed from one ExchangeRates query per denom.
+  // Expect to returns ExchangeRatesBatchResponse.
+  ExchangeRatesBatch(ExchangeRatesBatchParams),
+  // AcceptList returns the denoms accepted by the oracle,
+  // it is composed from the OracleParameters query.
+  // Expect to returns AcceptListResponse.
+  AcceptList(AcceptListParams),
 }
 
-// ExchangeRatesParams params to query ExchangeRates
+impl UmeeQueryOracle {
+  // base_denoms_mut returns the base denoms the query names, for a caller
+  // to rewrite them before the query is validated and sent. The symbol
+  // denoms priced by the oracle are left out.
+  pub fn base_denoms_mut(&mut self) -> Vec<&mut String> {
+    match self {
+      UmeeQueryOracle::ExchangeRates(params) => params.base_denom_mut().into_iter().collect(),
+      UmeeQueryOracle::ActiveExchangeRates(_)
+      | UmeeQueryOracle::FeederDelegation(_)
+      | UmeeQueryOracle::MissCounter(_)
+      | UmeeQueryOracle::SlashWindow(_)
+      | UmeeQueryOracle::AggregatePrevote(_)
+      | UmeeQueryOracle::AggregatePrevotes(_)
+      | UmeeQueryOracle::AggregateVote(_)
+      | UmeeQueryOracle::AggregateVotes(_)
+      | UmeeQueryOracle::OracleParameters(_)
+      | UmeeQueryOracle::Medians(_)
+      | UmeeQueryOracle::MedianDeviations(_)
+      | UmeeQueryOracle::ExchangeRatesBatch(_)
+      | UmeeQueryOracle::AcceptList(_) => vec![],
+    }
+  }
+
+  // validate rejects the leverage base denoms given to the queries of the
+  // oracle module, which prices the tokens under their symbol denom.
+  pub fn validate(&self) -> StdResult<()> {
+    match self {
+      UmeeQueryOracle::ActiveExchangeRates(_)
+      | UmeeQueryOracle::FeederDelegation(_)
+      | UmeeQueryOracle::MissCounter(_)
+      | UmeeQueryOracle::SlashWindow(_)
+      | UmeeQueryOracle::AggregatePrevote(_)
+      | UmeeQueryOracle::AggregatePrevotes(_)
+      | UmeeQueryOracle::AggregateVote(_)
+      | UmeeQueryOracle::AggregateVotes(_)
+      | UmeeQueryOracle::OracleParameters(_)
+      | UmeeQueryOracle::AcceptList(_) => Ok(()),
+      UmeeQueryOracle::ExchangeRates(params) => params.validate(),
+      UmeeQueryOracle::ExchangeRatesBatch(params) => params
+        .denoms
+        .iter()
+        .try_for_each(|denom| check_symbol_denom("exchange_rates_batch", denom)),
+      UmeeQueryOracle::Medians(params) => check_symbol_denom("medians", &params.denom),
+      UmeeQueryOracle::MedianDeviations(params) => {
+        check_symbol_denom("median_deviations", &params.denom)
+      }
+    }
+  }
+}
+
+fn check_symbol_denom(query: &str, denom: &str) -> StdResult<()> {
+  if looks_like_base_denom(denom) {
+    return Err(StdError::generic_err(format!(
+      "{}: {} looks like a leverage base denom, the oracle module takes the \
+       symbol denom of the token (e.g. UMEE)",
+      query, denom
+    )));
+  }
+  Ok(())
+}
+
+// ExchangeRatesParams params to query ExchangeRates, round_to rounds the
+// rates to that many decimal places with round_decimal. Neither round_to
+// nor denom_kind is sent to the oracle module.
 #[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
 pub struct ExchangeRatesParams {
   pub denom: String,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub round_to: Option<u32>,
+  #[serde(default, skip_serializing_if = "DenomKind::is_symbol")]
+  pub denom_kind: DenomKind,
+}
+
+impl ExchangeRatesParams {
+  pub fn new(denom: impl Into<String>) -> Self {
+    ExchangeRatesParams {
+      denom: denom.into(),
+      round_to: None,
+      denom_kind: DenomKind::Symbol,
+    }
+  }
+
+  // validate rejects a base denom given as a symbol denom, the base kind
+  // resolves it to its symbol instead.
+  pub fn validate(&self) -> StdResult<()> {
+    if self.denom_kind.is_symbol() {
+      return check_symbol_denom("exchange_rates", &self.denom)
+        .map_err(|err| StdError::generic_err(format!("{}, or set denom_kind to base", err)));
+    }
+    Ok(())
+  }
+
+  // base_denom_mut returns the denom if it is given as a base denom.
+  pub fn base_denom_mut(&mut self) -> Option<&mut String> {
+    match self.denom_kind {
+      DenomKind::Base => Some(&mut self.denom),
+      DenomKind::Symbol => None,
+    }
+  }
+}
+
+// DenomKind tells which denom of a token a query receives. The oracle
+// prices a token under its symbol denom (e.g. ATOM) while the leverage
+// module keys it by its base denom (e.g. uatom or an ibc/ hash), the
+// registered token of the base denom gives its symbol denom.
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum DenomKind {
+  Base,
+  #[default]
+  Symbol,
+}
+
+impl DenomKind {
+  pub fn is_symbol(&self) -> bool {
+    *self == DenomKind::Symbol
+  }
 }
 
 // ExchangeRatesResponse response struct of ExchangeRates query
 #[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
 pub struct ExchangeRatesResponse {
+  #[serde(default)]
   pub exchange_rates: Vec<DecCoin>,
 }
 
+// ExchangeRatesBatchParams params to query ExchangeRatesBatch.
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct ExchangeRatesBatchParams {
+  pub denoms: Vec<String>,
+}
+
+// ExchangeRatesBatchResponse response struct of ExchangeRatesBatch query,
+// the denoms without a price are listed in missing.
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct ExchangeRatesBatchResponse {
+  pub rates: Vec<DecCoin>,
+  pub missing: Vec<String>,
+}
+
+// AcceptListParams params to query AcceptList.
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct AcceptListParams {}
+
+// AcceptListResponse response struct of AcceptList query.
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct AcceptListResponse {
+  pub denoms: Vec<DenomSymbol>,
+}
+
+impl AcceptListResponse {
+  // find returns the accepted denom matching the base or symbol denom,
+  // the symbol denom is compared ignoring case as the oracle prices do.
+  pub fn find(&self, denom: &str) -> Option<&DenomSymbol> {
+    self
+      .denoms
+      .iter()
+      .find(|d| d.base_denom == denom || d.symbol_denom.eq_ignore_ascii_case(denom))
+  }
+}
+
+// DenomSymbol is a denom accepted by the oracle with its price symbol.
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct DenomSymbol {
+  pub base_denom: String,
+  pub symbol_denom: String,
+  pub exponent: u32,
+}
+
 // DecCoin defines a token with a denomination and a decimal amount.
 //
 // NOTE: The amount field is an Dec which implements the custom method
@@ -77,6 +248,7 @@ pub struct ActiveExchangeRatesParams {}
 // ActiveExchangeRatesResponse response struct of ActiveExchangeRates.
 #[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
 pub struct ActiveExchangeRatesResponse {
+  #[serde(default)]
   pub active_rates: Vec<String>,
 }
 
@@ -101,6 +273,7 @@ pub struct MissCounterParams {
 // MissCounterResponse response struct of MissCounter.
 #[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
 pub struct MissCounterResponse {
+  #[serde(default)]
   pub miss_counter: u64,
 }
 
@@ -111,6 +284,7 @@ pub struct SlashWindowParams {}
 // SlashWindowResponse response struct of SlashWindow.
 #[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
 pub struct SlashWindowResponse {
+  #[serde(default)]
   pub window_progress: u64,
 }
 
@@ -133,6 +307,7 @@ pub struct AggregatePrevotesParams {}
 // AggregatePrevotesResponse response struct of AggregatePrevotes.
 #[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
 pub struct AggregatePrevotesResponse {
+  #[serde(default)]
   pub aggregate_prevotes: Vec<AggregateExchangeRatePrevote>,
 }
 
@@ -155,6 +330,7 @@ pub struct AggregateVotesParams {}
 // AggregateVotesResponse response struct of AggregateVotes.
 #[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
 pub struct AggregateVotesResponse {
+  #[serde(default)]
   pub aggregate_votes: Vec<AggregateExchangeRateVote>,
 }
 
@@ -168,13 +344,34 @@ pub struct OracleParametersResponse {
   pub params: OracleParameters,
 }
 
+impl OracleParametersResponse {
+  // blocks_until_next_vote returns the number of blocks until the next vote period.
+  pub fn blocks_until_next_vote(&self, current_height: u64) -> u64 {
+    self.params.blocks_until_next_vote(current_height)
+  }
+
+  // slash_window_remaining returns the number of blocks until the slash window ends.
+  pub fn slash_window_remaining(&self, current_height: u64) -> u64 {
+    self.params.slash_window_remaining(current_height)
+  }
+}
+
 #[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
 pub struct MediansParams {
   pub denom: String,
 }
 
+impl MediansParams {
+  pub fn new(denom: impl Into<String>) -> Self {
+    MediansParams {
+      denom: denom.into(),
+    }
+  }
+}
+
 #[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
 pub struct MediansParamsResponse {
+  #[serde(default)]
   pub medians: Vec<DecCoin>,
 }
 #[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
@@ -182,7 +379,61 @@ pub struct MedianDeviationsParams {
   pub denom: String,
 }
 
+impl MedianDeviationsParams {
+  pub fn new(denom: impl Into<String>) -> Self {
+    MedianDeviationsParams {
+      denom: denom.into(),
+    }
+  }
+}
+
 #[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
 pub struct MedianDeviationsParamsResponse {
+  #[serde(default)]
   pub median_deviations: Vec<DecCoin>,
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use cosmwasm_std::from_json;
+
+  #[test]
+  fn native_query_names() {
+    let spellings = [
+      (r#"{"oracle_parameters":{}}"#, r#"{"oracle_params":{}}"#),
+      (
+        r#"{"medians":{"denom":"UMEE"}}"#,
+        r#"{"medians_params":{"denom":"UMEE"}}"#,
+      ),
+      (
+        r#"{"exchange_rates":{"denom":"UMEE"}}"#,
+        r#"{"get_exchange_rate_base":{"denom":"UMEE"}}"#,
+      ),
+    ];
+    for (crate_name, native_name) in spellings {
+      let query: UmeeQueryOracle = from_json(crate_name).unwrap();
+      assert_eq!(query, from_json::<UmeeQueryOracle>(native_name).unwrap());
+    }
+  }
+
+  #[test]
+  fn sparse_responses() {
+    let rates: ExchangeRatesResponse = from_json(b"{}").unwrap();
+    assert!(rates.exchange_rates.is_empty());
+    let active: ActiveExchangeRatesResponse = from_json(b"{}").unwrap();
+    assert!(active.active_rates.is_empty());
+    let misses: MissCounterResponse = from_json(b"{}").unwrap();
+    assert_eq!(0, misses.miss_counter);
+    let window: SlashWindowResponse = from_json(b"{}").unwrap();
+    assert_eq!(0, window.window_progress);
+    let prevotes: AggregatePrevotesResponse = from_json(b"{}").unwrap();
+    assert!(prevotes.aggregate_prevotes.is_empty());
+    let votes: AggregateVotesResponse = from_json(b"{}").unwrap();
+    assert!(votes.aggregate_votes.is_empty());
+    let medians: MediansParamsResponse = from_json(b"{}").unwrap();
+    assert!(medians.medians.is_empty());
+    let deviations: MedianDeviationsParamsResponse = from_json(b"{}").unwrap();
+    assert!(deviations.median_deviations.is_empty());
+  }
+}
@@ -0,0 +1,135 @@
+use cosmwasm_std::{Decimal, Uint128};
+
+// apy_to_apr returns the yearly rate that, compounded periods_per_year
+// times, gives the apy. The periodic rate is exact up to the 18 decimal
+// places of Decimal (rounded down) before being multiplied by the periods.
+pub fn apy_to_apr(apy: Decimal, periods_per_year: u32) -> Decimal {
+  periodic_rate(apy, u64::from(periods_per_year)) * Decimal::from_ratio(periods_per_year, 1u32)
+}
+
+// apy_to_per_block returns the rate accrued at each block that, compounded
+// over blocks_per_year blocks, gives the apy, rounded down to the last of
+// the 18 decimal places of Decimal.
+pub fn apy_to_per_block(apy: Decimal, blocks_per_year: u64) -> Decimal {
+  periodic_rate(apy, blocks_per_year)
+}
+
+// compute_exchange_rate returns the base tokens redeemed by one uToken, the
+// token market size over the uToken supply. A market without uTokens has
+// the rate of one the module starts every market with, and a ratio above
+// the Decimal range is capped at its max.
+pub fn compute_exchange_rate(token_market_size: Uint128, utoken_supply: Uint128) -> Decimal {
+  if utoken_supply.is_zero() {
+    return Decimal::one();
+  }
+  Decimal::checked_from_ratio(token_market_size, utoken_supply).unwrap_or(Decimal::MAX)
+}
+
+// periodic_rate finds the rate r where (1 + r)^periods = 1 + apy by
+// bisection over the Decimal atomics, zero periods yield a zero rate.
+fn periodic_rate(apy: Decimal, periods: u64) -> Decimal {
+  if periods == 0 || apy.is_zero() {
+    return Decimal::zero();
+  }
+  let target = Decimal::one() + apy;
+  // low always compounds to at most the target and high above it
+  let mut low = Decimal::zero();
+  let mut high = apy + Decimal::raw(1);
+  // each step halves the interval, 128 of them cover the u128 atomics
+  for _ in 0..128 {
+    let gap = high - low;
+    if gap <= Decimal::raw(1) {
+      break;
+    }
+    let mid = low + Decimal::raw(gap.atomics().u128() / 2);
+    match checked_pow(Decimal::one() + mid, periods) {
+      Some(value) if value <= target => low = mid,
+      _ => high = mid,
+    }
+  }
+  low
+}
+
+// checked_pow raises base to a u64 exponent, None on overflow.
+fn checked_pow(base: Decimal, mut exp: u64) -> Option<Decimal> {
+  let mut result = Decimal::one();
+  let mut base = base;
+  while exp > 0 {
+    if exp & 1 == 1 {
+      result = result.checked_mul(base).ok()?;
+    }
+    exp >>= 1;
+    if exp > 0 {
+      base = base.checked_mul(base).ok()?;
+    }
+  }
+  Some(result)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use std::str::FromStr;
+
+  // asserts that both values are within tolerance of each other
+  fn assert_close(expected: &str, value: Decimal, tolerance: &str) {
+    let expected = Decimal::from_str(expected).unwrap();
+    let diff = if value > expected {
+      value - expected
+    } else {
+      expected - value
+    };
+    assert!(
+      diff <= Decimal::from_str(tolerance).unwrap(),
+      "expected {} got {}",
+      expected,
+      value
+    );
+  }
+
+  #[test]
+  fn apy_conversions() {
+    let apy = Decimal::from_str("0.1").unwrap();
+    // 1.1^(1/12) - 1 = 0.00797414042890374...
+    assert_close(
+      "0.095689685146844892",
+      apy_to_apr(apy, 12),
+      "0.000000000001",
+    );
+    // 1.1^(1/5256000) - 1 = 0.0000000181335960175...
+    assert_close(
+      "0.000000018133596017",
+      apy_to_per_block(apy, 5_256_000),
+      "0.000000000000001",
+    );
+    assert_eq!(apy, apy_to_apr(apy, 1));
+    assert_eq!(Decimal::zero(), apy_to_apr(Decimal::zero(), 12));
+    assert_eq!(Decimal::zero(), apy_to_per_block(apy, 0));
+  }
+
+  #[test]
+  fn exchange_rate() {
+    // genesis, before the first supply mints any uToken
+    assert_eq!(
+      Decimal::one(),
+      compute_exchange_rate(Uint128::zero(), Uint128::zero())
+    );
+    assert_eq!(
+      Decimal::one(),
+      compute_exchange_rate(Uint128::new(500), Uint128::zero())
+    );
+    assert_eq!(
+      Decimal::one(),
+      compute_exchange_rate(Uint128::new(1000), Uint128::new(1000))
+    );
+    // after the interest accrued to the suppliers
+    assert_eq!(
+      Decimal::from_str("1.05").unwrap(),
+      compute_exchange_rate(Uint128::new(1050), Uint128::new(1000))
+    );
+    assert_eq!(
+      Decimal::MAX,
+      compute_exchange_rate(Uint128::MAX, Uint128::new(1))
+    );
+  }
+}
@@ -2,6 +2,37 @@ use cosmwasm_std::Decimal;
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
+// UTOKEN_PREFIX is the denom prefix of the uTokens minted by the leverage
+// module in exchange of the supplied base tokens.
+pub const UTOKEN_PREFIX: &str = "u/";
+
+// utoken_denom returns the uToken denom of a base denom.
+pub fn utoken_denom(base_denom: &str) -> String {
+  format!("{}{}", UTOKEN_PREFIX, base_denom)
+}
+
+// looks_like_symbol_denom tells if a denom has the shape of an oracle
+// symbol denom (e.g. ATOM): uppercase letters, without any lowercase
+// letter or path.
+pub fn looks_like_symbol_denom(denom: &str) -> bool {
+  !denom.contains('/')
+    && denom.chars().any(|c| c.is_ascii_uppercase())
+    && !denom.chars().any(|c| c.is_ascii_lowercase())
+}
+
+// looks_like_base_denom tells if a denom has the shape of a leverage base
+// denom (e.g. uatom or ibc/27394F...) or of a uToken.
+pub fn looks_like_base_denom(denom: &str) -> bool {
+  if denom.starts_with("ibc/") || denom.starts_with(UTOKEN_PREFIX) {
+    return true;
+  }
+  denom.len() > 1
+    && denom.starts_with('u')
+    && denom
+      .chars()
+      .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit())
+}
+
 // Token defines a token, along with its capital metadata, in the Umee capital
 // facility that can be loaned and borrowed.
 #[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
@@ -105,3 +136,203 @@ pub struct Token {
   // for the affected Token.
   historic_medians: u32,
 }
+
+impl Token {
+  // base_denom returns the denomination of the underlying base token.
+  pub fn base_denom(&self) -> &str {
+    self.base_denom.as_deref().unwrap_or_default()
+  }
+
+  // symbol_denom returns the denom the oracle prices the token under.
+  pub fn symbol_denom(&self) -> &str {
+    self.symbol_denom.as_deref().unwrap_or_default()
+  }
+
+  // reserve_factor returns the portion of the borrow interest of the token
+  // the module keeps as reserves.
+  pub fn reserve_factor(&self) -> Decimal {
+    self.reserve_factor
+  }
+
+  // min_collateral_liquidity returns the minimum ratio between the
+  // available and the collateralized amount of the token.
+  pub fn min_collateral_liquidity(&self) -> Decimal {
+    self.min_collateral_liquidity
+  }
+
+  // max_supply returns the maximum amount of tokens the protocol can hold,
+  // zero means that there is no limit.
+  pub fn max_supply(&self) -> Decimal {
+    self.max_supply
+  }
+
+  // max_supply_utilization returns the maximum ratio between the borrowed
+  // and the supplied amount of the token reachable by new borrows.
+  pub fn max_supply_utilization(&self) -> Decimal {
+    self.max_supply_utilization
+  }
+
+  // base_borrow_rate returns the borrow interest rate at zero utilization.
+  pub fn base_borrow_rate(&self) -> Decimal {
+    self.base_borrow_rate
+  }
+
+  // kink_borrow_rate returns the borrow interest rate at the kink utilization.
+  pub fn kink_borrow_rate(&self) -> Decimal {
+    self.kink_borrow_rate
+  }
+
+  // max_borrow_rate returns the borrow interest rate at full utilization.
+  pub fn max_borrow_rate(&self) -> Decimal {
+    self.max_borrow_rate
+  }
+
+  // kink_utilization returns the utilization where the kink rate kicks off.
+  pub fn kink_utilization(&self) -> Decimal {
+    self.kink_utilization
+  }
+
+  // liquidation_threshold returns the portion of the collateral value of
+  // the token counted towards the liquidation threshold of a borrower.
+  pub fn liquidation_threshold(&self) -> Decimal {
+    self.liquidation_threshold
+  }
+
+  // liquidation_incentive returns the bonus portion of collateral
+  // liquidators receive when this token is the liquidation reward.
+  pub fn liquidation_incentive(&self) -> Decimal {
+    self.liquidation_incentive
+  }
+
+  // collateral_weight returns the portion of the value of the token
+  // counted towards the borrow limit, zero disables it as collateral.
+  pub fn collateral_weight(&self) -> Decimal {
+    self.collateral_weight
+  }
+
+  // borrow_factor returns the factor the module weights the borrowed value
+  // of the token with in the borrow limit, the minimum of 2 and one over
+  // the collateral weight as computed by the leverage module.
+  pub fn borrow_factor(&self) -> Decimal {
+    let two = Decimal::from_ratio(2u8, 1u8);
+    if self.collateral_weight <= Decimal::percent(50) {
+      return two;
+    }
+    Decimal::one() / self.collateral_weight
+  }
+
+  // supply_enabled returns if the token can be supplied, the flags
+  // omitted by the module are false.
+  pub fn supply_enabled(&self) -> bool {
+    self.enable_msg_supply.unwrap_or_default()
+  }
+
+  // borrow_enabled returns if the token can be borrowed.
+  pub fn borrow_enabled(&self) -> bool {
+    self.enable_msg_borrow.unwrap_or_default()
+  }
+
+  // blacklisted returns if the token is being removed from the protocol.
+  pub fn blacklisted(&self) -> bool {
+    self.blacklist.unwrap_or_default()
+  }
+
+  // collateral_enabled returns if the token counts as collateral, a
+  // blacklisted token is priced at zero so it never does.
+  pub fn collateral_enabled(&self) -> bool {
+    !self.blacklisted() && !self.collateral_weight.is_zero()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::RegisteredTokensResponse;
+  use cosmwasm_std::from_json;
+  use std::str::FromStr;
+
+  const REGISTRY_JSON: &str = r#"{"registry":[{"base_denom":"uumee","reserve_factor":"0.1","collateral_weight":"0.35","liquidation_threshold":"0.5","base_borrow_rate":"0.02","kink_borrow_rate":"0.15","max_borrow_rate":"1.5","kink_utilization":"0.7","liquidation_incentive":"0.1","symbol_denom":"UMEE","exponent":6,"enable_msg_supply":true,"enable_msg_borrow":true,"blacklist":false,"max_collateral_share":"1","max_supply_utilization":"0.9","min_collateral_liquidity":"0.3","max_supply":"100000000000","historic_medians":24}]}"#;
+
+  #[test]
+  fn registry_caps() {
+    let resp: RegisteredTokensResponse = from_json(REGISTRY_JSON).unwrap();
+    let token = &resp.registry[0];
+    assert_eq!("uumee", token.base_denom());
+    assert_eq!(
+      Decimal::from_str("0.3").unwrap(),
+      token.min_collateral_liquidity()
+    );
+    assert_eq!(
+      Decimal::from_str("100000000000").unwrap(),
+      token.max_supply()
+    );
+  }
+  #[test]
+  fn registry_liquidation_threshold() {
+    let resp: RegisteredTokensResponse = from_json(REGISTRY_JSON).unwrap();
+    let token = resp.get("uumee").unwrap();
+    assert_eq!(
+      Decimal::from_str("0.5").unwrap(),
+      token.liquidation_threshold()
+    );
+  }
+
+  #[test]
+  fn registry_borrow_factor() {
+    let heavy = REGISTRY_JSON.replace(
+      r#""collateral_weight":"0.35""#,
+      r#""collateral_weight":"0.8""#,
+    );
+    let resp: RegisteredTokensResponse = from_json(heavy.as_bytes()).unwrap();
+    assert_eq!(
+      Decimal::from_str("1.25").unwrap(),
+      resp.get("uumee").unwrap().borrow_factor()
+    );
+  }
+
+  #[test]
+  fn registry_flags() {
+    let resp: RegisteredTokensResponse = from_json(REGISTRY_JSON).unwrap();
+    let token = resp.get("uumee").unwrap();
+    assert_eq!(
+      Decimal::from_str("0.35").unwrap(),
+      token.collateral_weight()
+    );
+    assert!(token.supply_enabled());
+    assert!(token.borrow_enabled());
+    assert!(!token.blacklisted());
+    assert!(token.collateral_enabled());
+    // the collateral weight of 0.35 is below one half
+    assert_eq!(Decimal::from_ratio(2u8, 1u8), token.borrow_factor());
+
+    let sparse = REGISTRY_JSON.replace(
+      r#""enable_msg_supply":true,"enable_msg_borrow":true,"blacklist":false,"#,
+      "",
+    );
+    let resp: RegisteredTokensResponse = from_json(sparse.as_bytes()).unwrap();
+    let token = resp.get("uumee").unwrap();
+    assert!(!token.supply_enabled());
+    assert!(!token.borrow_enabled());
+    assert!(!token.blacklisted());
+  }
+
+  #[test]
+  fn denom_shapes() {
+    for symbol in ["UMEE", "ATOM", "USDC.AXL", "WBTC"] {
+      assert!(looks_like_symbol_denom(symbol), "{}", symbol);
+      assert!(!looks_like_base_denom(symbol), "{}", symbol);
+    }
+    for base in [
+      "uumee",
+      "uatom",
+      "u/uumee",
+      "ibc/27394FB092D2ECCD56123C74F36E4C1F926001CE",
+    ] {
+      assert!(looks_like_base_denom(base), "{}", base);
+      assert!(!looks_like_symbol_denom(base), "{}", base);
+    }
+    // neither shape, left to the modules
+    assert!(!looks_like_symbol_denom("stATOM"));
+    assert!(!looks_like_base_denom("aevmos"));
+  }
+}
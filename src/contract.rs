@@ -1,48 +1,123 @@
 #[cfg(not(feature = "library"))]
 use cosmwasm_std::{
-  entry_point, Addr, Binary, ContractResult, Deps, DepsMut, Env, MessageInfo, QueryRequest,
-  Response, StdError, StdResult, SystemResult,
+  entry_point, Addr, Binary, Coin, ContractResult, Decimal, Decimal256, Deps, DepsMut, Env, Event,
+  MessageInfo, QueryRequest, Reply, Response, StdError, StdResult, SystemResult, Uint128,
+};
+use cosmwasm_std::{
+  from_json, to_json_binary, to_json_vec, BankQuery,
+  DenomMetadataResponse as BankDenomMetadataResponse, Storage,
 };
-use cosmwasm_std::{from_json, to_json_binary, to_json_vec};
 use cw2::set_contract_version;
 use cw_umee_types::error::ContractError;
-use cw_umee_types::query_incentive::{
-  AccountBondsParams, AccountBondsResponse, ActualRatesParams, ActualRatesResponse,
+use cw_umee_types::{
+  compute_exchange_rate, format_usd, is_umee_address, looks_like_symbol_denom, merge_coins,
+  round_decimal, umee_response, umee_submsg_response, utoken_denom, AcceptListParams,
+  AcceptListResponse, AccountBalancesParams, AccountBalancesResponse, AccountBondsParams,
+  AccountBondsResponse, AccountSummaryParams, AccountSummaryResponse, ActiveExchangeRatesParams,
+  ActiveExchangeRatesResponse, ActualRatesParams, ActualRatesResponse, AggregatePrevoteParams,
+  AggregatePrevoteResponse, AggregatePrevotesParams, AggregatePrevotesResponse,
+  AggregateVoteParams, AggregateVoteResponse, AggregateVotesParams, AggregateVotesResponse,
+  AllReservesParams, AllReservesResponse, AvailableWithdrawParams, AvailableWithdrawResponse,
+  BadDebtTotalParams, BadDebtTotalResponse, BadDebtsParams, BadDebtsResponse,
+  BatchAccountSummaryParams, BatchAccountSummaryResponse, BondParams, BorrowFactorsParams,
+  BorrowFactorsResponse, BorrowParams, CapsUsageParams, CapsUsageResponse,
+  CollateralValueAtPriceParams, CollateralValueAtPriceResponse, CollateralizeParams,
   CompletedIncentiveProgramsParams, CompletedIncentiveProgramsResponse, CurrentRatesParams,
-  CurrentRatesResponse, IncentiveParametersParams, IncentiveParametersResponse,
-  IncentiveProgramParams, IncentiveProgramResponse, LastRewardTimeParams, LastRewardTimeResponse,
-  OngoingIncentiveProgramsParams, OngoingIncentiveProgramsResponse, PendingRewardsParams,
-  PendingRewardsResponse, TotalBondedParams, TotalBondedResponse, TotalUnbondingParams,
-  TotalUnbondingResponse, UpcomingIncentiveProgramsParams, UpcomingIncentiveProgramsResponse,
-};
-use cw_umee_types::query_leverage::{
-  BadDebtsParams, BadDebtsResponse, MaxBorrowParams, MaxBorrowResponse, MaxWithdrawParams,
-  MaxWithdrawResponse,
+  CurrentRatesResponse, DecollateralizeParams, DelegateFeedConsentParams, DenomKind,
+  DenomStatusParams, DenomStatusResponse, ExchangeRatesBatchParams, ExchangeRatesBatchResponse,
+  ExchangeRatesParams, ExchangeRatesResponse, FeederDelegationParams, FeederDelegationResponse,
+  IncentiveParametersParams, IncentiveParametersResponse, IncentiveProgramParams,
+  IncentiveProgramResponse, InterestRateModelParams, InterestRateModelResponse,
+  LastRewardTimeParams, LastRewardTimeResponse, LeverageParametersParams,
+  LeverageParametersResponse, LiquidatableBorrowersParams, LiquidatableBorrowersResponse,
+  LiquidateMsgResponse, LiquidateParams, LiquidationPriceParams, LiquidationPriceResponse,
+  LiquidationTargetsParams, LiquidationTargetsResponse, MarketEnabledParams, MarketEnabledResponse,
+  MarketRowParams, MarketRowResponse, MarketSummaryParams, MarketSummaryResponse, MaxBorrowParams,
+  MaxBorrowResponse, MaxCollateralizeParams, MaxCollateralizeResponse, MaxWithdrawParams,
+  MaxWithdrawResponse, MedianDeviationsParams, MedianDeviationsParamsResponse, MediansParams,
+  MediansParamsResponse, MetokenIndexPricesParams, MetokenIndexPricesResponse,
+  MetokenIndexbalancesParams, MetokenIndexbalancesResponse, MetokenIndexesParams,
+  MetokenIndexesResponse, MetokenParametersParams, MetokenParametersResponse,
+  MetokenRedeemfeeParams, MetokenRedeemfeeResponse, MetokenSwapfeeParams, MetokenSwapfeeResponse,
+  MissCounterParams, MissCounterResponse, MsgMaxWithdrawParams, MsgTypes,
+  OngoingIncentiveProgramsParams, OngoingIncentiveProgramsResponse, OracleParametersParams,
+  OracleParametersResponse, PendingRewardsParams, PendingRewardsResponse, RegisteredTokensParams,
+  RegisteredTokensResponse, RepayParams, ReserveInfoParams, ReserveInfoResponse,
+  SimulateLiquidationParams, SimulateLiquidationResponse, SimulateSupplyParams,
+  SimulateSupplyResponse, SlashWindowParams, SlashWindowResponse, SpecialBorrowLimitParams,
+  SpecialBorrowLimitResponse, StructUmeeMsg, StructUmeeQuery, SupplyMsgResponse, SupplyParams,
+  TimeToLiquidationParams, TimeToLiquidationResponse, TopSupplyMarketsParams,
+  TopSupplyMarketsResponse, TotalBondedParams, TotalBondedResponse, TotalUnbondingParams,
+  TotalUnbondingResponse, UTokenSupplyParams, UTokenSupplyResponse, UmeeMsg, UmeeMsgLeverage,
+  UmeeQuery, UmeeQueryIncentive, UmeeQueryLeverage, UmeeQueryMeToken, UmeeQueryOracle,
+  UpcomingIncentiveProgramsParams, UpcomingIncentiveProgramsResponse, WithdrawMsgResponse,
+  WithdrawParams, ASSIGNED_QUERIES, UMEE_BECH32_PREFIX, UTOKEN_PREFIX,
 };
-use cw_umee_types::query_metoken::{
-  MetokenIndexPricesParams, MetokenIndexPricesResponse, MetokenIndexbalancesParams,
-  MetokenIndexbalancesResponse, MetokenIndexesParams, MetokenIndexesResponse,
-  MetokenParametersParams, MetokenParametersResponse, MetokenRedeemfeeParams,
-  MetokenRedeemfeeResponse, MetokenSwapfeeParams, MetokenSwapfeeResponse, UmeeQueryMeToken,
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet};
+use std::convert::TryFrom;
+
+use crate::msg::{
+  AdjustCollateralParams, AssignedIdsResponse, BorrowLimitUsedResponse, CachedTvlResponse,
+  ChainQueryKind, CollateralBreakdownResponse, DenomMetadataResponse, ExecuteMsg,
+  FullPositionResponse, GuardedWithdrawParams, InstantiateMsg, IsLiquidatableResponse,
+  MaxWithdrawReplyResponse, MigrateMsg, OwnerResponse, PositionPnlResponse, QueryMsg,
+  RatesResponse, RebalanceParams, ResolveAliasResponse, SelfPositionResponse, SetupFeederParams,
+  TvlResponse, UnwindParams,
 };
-use cw_umee_types::query_oracle::{
-  MedianDeviationsParams, MedianDeviationsParamsResponse, MediansParams, MediansParamsResponse,
-};
-use cw_umee_types::{
-  AccountBalancesParams, AccountBalancesResponse, AccountSummaryParams, ActiveExchangeRatesParams,
-  ActiveExchangeRatesResponse, AggregatePrevoteParams, AggregatePrevoteResponse,
-  AggregatePrevotesParams, AggregatePrevotesResponse, AggregateVoteParams, AggregateVoteResponse,
-  AggregateVotesParams, AggregateVotesResponse, ExchangeRatesParams, ExchangeRatesResponse,
-  FeederDelegationParams, FeederDelegationResponse, LeverageParametersParams,
-  LeverageParametersResponse, LiquidationTargetsParams, LiquidationTargetsResponse,
-  MarketSummaryParams, MarketSummaryResponse, MissCounterParams, MissCounterResponse,
-  OracleParametersParams, OracleParametersResponse, RegisteredTokensParams,
-  RegisteredTokensResponse, SlashWindowParams, SlashWindowResponse, StructUmeeMsg, StructUmeeQuery,
-  UmeeMsg, UmeeMsgLeverage, UmeeQuery, UmeeQueryIncentive, UmeeQueryLeverage, UmeeQueryOracle,
+use crate::querier::LeverageQuerier;
+use crate::state::{
+  default_chain_queries, default_check_denoms, StateV2, CURRENT_STATE_VERSION, DENOM_ALIASES,
+  LEGACY_STATE, LIQUIDATION_SNAPSHOT, MAX_WITHDRAW_COLLATERAL, POSITION_SNAPSHOTS, REPLY_BALANCES,
+  STATE, STATE_VERSION, TVL_CACHE, UNWIND, WITHDRAW_MIN_RECEIVED,
 };
 
-use crate::msg::{ExecuteMsg, InstantiateMsg, OwnerResponse, QueryMsg};
-use crate::state::{State, STATE};
+// reply ids of the supply and withdraw sent as submessages by their own
+// execute messages, the Umee passthrough sends them without reply
+pub const REPLY_SUPPLY: u64 = 1;
+pub const REPLY_WITHDRAW: u64 = 2;
+
+// reply ids of the unwind steps, in the order they are sent
+pub const REPLY_UNWIND_REPAY: u64 = 4;
+pub const REPLY_UNWIND_DECOLLATERALIZE: u64 = 5;
+pub const REPLY_UNWIND_WITHDRAW: u64 = 6;
+
+// reply id of the max withdraw sent as submessage
+pub const REPLY_MAX_WITHDRAW: u64 = 7;
+
+// reply id of the supply followed by a collateralize of the minted uTokens
+pub const REPLY_SUPPLY_THEN_COLLATERALIZE: u64 = 8;
+
+// reply id of the liquidation, its reply sets the settled amounts as data
+pub const REPLY_LIQUIDATE: u64 = 9;
+
+// default and maximum page sizes of the paginated queries
+const DEFAULT_PAGE_LIMIT: u32 = 10;
+const MAX_PAGE_LIMIT: u32 = 30;
+
+// maximum size of the registered tokens response, larger registries are
+// close to the query response limit and fail with obscure truncation errors
+pub const MAX_REGISTERED_TOKENS_RESPONSE_BYTES: usize = 128 * 1024;
+
+// maximum registered tokens the total value locked and the reserves iterate
+// over, each one costs a market summary query, about the gas of a bank query
+pub const MAX_TVL_MARKETS: usize = 50;
+
+// maximum addresses of a batch account summary, each one costs an
+// account summary query
+pub const MAX_BATCH_ACCOUNTS: usize = 50;
+
+// maximum length of the memo of a WithMemo message, the memo is emitted as
+// an attribute and only meant to carry a client id
+pub const MAX_MEMO_LENGTH: usize = 256;
+
+// decimal places of the USD display strings of the derived responses
+pub const USD_DISPLAY_PLACES: u32 = 2;
+
+// highest supply or borrow apy accepted from a market summary, 10000%,
+// anything above it is treated as corrupt rate data
+pub const MAX_SANE_APY: Decimal256 = Decimal256::raw(100_000_000_000_000_000_000);
 
 // version info for migration info
 const CONTRACT_NAME: &str = "crates.io:umee-cosmwasm";
@@ -55,764 +130,1531 @@ pub fn instantiate(
   deps: DepsMut,
   _env: Env,
   info: MessageInfo,
-  _: InstantiateMsg,
+  msg: InstantiateMsg,
 ) -> Result<Response, ContractError> {
-  let state = State {
+  // never clobbers the ownership and the version of an existing state
+  if STATE.may_load(deps.storage)?.is_some() {
+    return Err(ContractError::AlreadyInstantiated {});
+  }
+
+  // fails fast at deploy time instead of at the first custom query
+  let umee_enabled = match capability_check(deps.as_ref()) {
+    Ok(()) => true,
+    Err(err) => {
+      if msg.require_umee.unwrap_or(true) {
+        return Err(ContractError::UmeeUnavailable {
+          reason: err.to_string(),
+        });
+      }
+      false
+    }
+  };
+
+  let chain_queries = msg.chain_queries.unwrap_or_else(default_chain_queries);
+  if !cfg!(feature = "stargate-queries") && chain_queries.contains(&ChainQueryKind::Stargate) {
+    return Err(ContractError::CustomError {
+      val: String::from("stargate chain queries need the stargate-queries feature"),
+    });
+  }
+
+  let state = StateV2 {
     owner: info.sender.clone(),
+    pending_owner: None,
+    umee_enabled,
+    chain_queries,
+    check_denoms: msg.check_denoms.unwrap_or_else(default_check_denoms),
   };
   set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
   STATE.save(deps.storage, &state)?;
+  STATE_VERSION.save(deps.storage, &CURRENT_STATE_VERSION)?;
+
+  Ok(
+    Response::new()
+      .add_attribute("action", "instantiate")
+      .add_attribute("owner", info.sender)
+      .add_attribute("umee_enabled", umee_enabled.to_string()),
+  )
+}
+
+// migrate translates the stored state to the current layout, one version
+// at a time, and records the new contract version
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn migrate(deps: DepsMut, _env: Env, _msg: MigrateMsg) -> Result<Response, ContractError> {
+  let from = STATE_VERSION.may_load(deps.storage)?.unwrap_or(1);
+  if from > CURRENT_STATE_VERSION {
+    return Err(ContractError::CustomError {
+      val: format!(
+        "state version {} is newer than the {} of this code",
+        from, CURRENT_STATE_VERSION
+      ),
+    });
+  }
+
+  if from < 2 {
+    let legacy = LEGACY_STATE.load(deps.storage)?;
+    STATE.save(deps.storage, &StateV2::from(legacy))?;
+    LEGACY_STATE.remove(deps.storage);
+  }
+  STATE_VERSION.save(deps.storage, &CURRENT_STATE_VERSION)?;
+  set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
 
   Ok(
     Response::new()
-      .add_attribute("method", "instantiate")
-      .add_attribute("owner", info.sender),
+      .add_attribute("action", "migrate")
+      .add_attribute("from_state_version", from.to_string())
+      .add_attribute("state_version", CURRENT_STATE_VERSION.to_string()),
   )
 }
 
+// capability_check probes the umee custom querier with the cheap oracle
+// params query, it errors if the chain lacks the "umee" feature
+pub fn capability_check(deps: Deps) -> StdResult<()> {
+  let request = QueryRequest::Custom(StructUmeeQuery::oracle_parameters(
+    OracleParametersParams {},
+  ));
+  query_chain(deps, &request)?;
+  Ok(())
+}
+
 // executes changes to the state of the contract, it receives messages DepsMut
 // that contains the contract state with write permissions
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn execute(
   deps: DepsMut,
-  _env: Env,
+  env: Env,
   info: MessageInfo,
-  msg: ExecuteMsg,
+  mut msg: ExecuteMsg,
 ) -> Result<Response<StructUmeeMsg>, ContractError> {
+  resolve_execute_aliases(deps.storage, &mut msg)?;
   match msg {
     // receives the new owner and tries to change it in the contract state
     ExecuteMsg::ChangeOwner { new_owner } => try_change_owner(deps, info, new_owner),
     ExecuteMsg::Umee(UmeeMsg::Leverage(execute_leverage_msg)) => {
-      execute_leverage(execute_leverage_msg)
+      execute_leverage(deps, env, execute_leverage_msg)
+    }
+    ExecuteMsg::Supply(supply_params) => execute_supply(deps, env, supply_params, REPLY_SUPPLY),
+    ExecuteMsg::Withdraw(GuardedWithdrawParams {
+      asset,
+      min_received,
+    }) => {
+      let withdraw = UmeeMsgLeverage::Withdraw(WithdrawParams::new(asset.clone()));
+      withdraw.validate_denoms()?;
+      ensure_nonzero(&asset)?;
+      execute_withdraw(deps, env, WithdrawParams::new(asset), min_received)
+    }
+    ExecuteMsg::SupplyAndBond(supply_params) => {
+      execute_supply_and_bond(deps.as_ref(), env, supply_params)
+    }
+    ExecuteMsg::Rebalance(rebalance_params) => execute_rebalance(rebalance_params),
+    ExecuteMsg::Unwind(unwind_params) => execute_unwind(deps, env, unwind_params),
+    ExecuteMsg::SetupFeeder(setup_feeder_params) => {
+      try_setup_feeder(deps, info, setup_feeder_params)
+    }
+    ExecuteMsg::RefreshTvlCache {} => execute_refresh_tvl_cache(deps, env),
+    ExecuteMsg::SupplySchedule(schedule) => execute_supply_schedule(deps.as_ref(), schedule),
+    ExecuteMsg::AdjustCollateral(adjust_collateral_params) => {
+      execute_adjust_collateral(deps.as_ref(), env, adjust_collateral_params)
+    }
+    ExecuteMsg::WithdrawAll { supplier } => execute_withdraw_all(deps.as_ref(), env, supplier),
+    ExecuteMsg::BatchLeverage(batch) => execute_batch_leverage(deps.as_ref(), batch),
+    ExecuteMsg::CollateralizeAll { borrower, denom } => {
+      execute_collateralize_all(deps.as_ref(), env, borrower, denom)
+    }
+    ExecuteMsg::RepayFromBalance { borrower, denom } => {
+      execute_repay_from_balance(deps, env, borrower, denom)
+    }
+    ExecuteMsg::WithMemo { memo, inner } => execute_with_memo(deps, env, info, memo, *inner),
+    ExecuteMsg::SnapshotPosition { address } => execute_snapshot_position(deps, env, info, address),
+    ExecuteMsg::SetDenomAlias { alias, denom } => try_set_denom_alias(deps, info, alias, denom),
+    ExecuteMsg::SupplyThenCollateralize(supply_params) => {
+      execute_supply(deps, env, supply_params, REPLY_SUPPLY_THEN_COLLATERALIZE)
     }
-    ExecuteMsg::Supply(supply_params) => StructUmeeMsg::supply(supply_params),
   }
 }
 
-// tries to change the owner, but it could fail and respond as Unauthorized
-pub fn try_change_owner(
+// execute_refresh_tvl_cache stores the current total value locked along
+// with the block height it was computed at.
+fn execute_refresh_tvl_cache(
   deps: DepsMut,
-  info: MessageInfo,
-  new_owner: Addr,
+  env: Env,
 ) -> Result<Response<StructUmeeMsg>, ContractError> {
-  STATE.update(deps.storage, |mut state| -> Result<_, ContractError> {
-    if info.sender != state.owner {
-      return Err(ContractError::Unauthorized {});
-    }
-    state.owner = new_owner;
-    Ok(state)
-  })?;
-  Ok(Response::<StructUmeeMsg>::new().add_attribute("method", "change_owner"))
+  let cache = CachedTvlResponse {
+    height: env.block.height,
+    tvl: query_total_value_locked(&deps.as_ref())?,
+  };
+  TVL_CACHE.save(deps.storage, &cache)?;
+
+  Ok(
+    Response::new()
+      .add_attribute("action", "refresh_tvl_cache")
+      .add_attribute("height", cache.height.to_string()),
+  )
 }
 
-// execute_leverage handles the execution of every msg of leverage umee native modules
-fn execute_leverage(
-  execute_leverage_msg: UmeeMsgLeverage,
+// execute_with_memo executes the inner message and adds the memo to its
+// response as the client_memo attribute, a memo can't wrap another one.
+fn execute_with_memo(
+  deps: DepsMut,
+  env: Env,
+  info: MessageInfo,
+  memo: String,
+  inner: ExecuteMsg,
 ) -> Result<Response<StructUmeeMsg>, ContractError> {
-  match execute_leverage_msg {
-    UmeeMsgLeverage::Supply(supply_params) => StructUmeeMsg::supply(supply_params),
-    UmeeMsgLeverage::Withdraw(withdraw_params) => StructUmeeMsg::withdraw(withdraw_params),
-    UmeeMsgLeverage::MaxWithdraw(max_withdraw_params) => {
-      StructUmeeMsg::max_withdraw(max_withdraw_params)
-    }
-    UmeeMsgLeverage::Collateralize(collateralize_params) => {
-      StructUmeeMsg::collateralize(collateralize_params)
-    }
-    UmeeMsgLeverage::Decollateralize(decollateralize_params) => {
-      StructUmeeMsg::decollateralize(decollateralize_params)
-    }
-    UmeeMsgLeverage::Borrow(borrow_params) => StructUmeeMsg::borrow(borrow_params),
-    UmeeMsgLeverage::MaxBorrow(borrow_params) => StructUmeeMsg::max_borrow(borrow_params),
-    UmeeMsgLeverage::Repay(repay_params) => StructUmeeMsg::repay(repay_params),
-    UmeeMsgLeverage::Liquidate(liquidate_params) => StructUmeeMsg::liquidate(liquidate_params),
-    UmeeMsgLeverage::SupplyCollateral(supply_collateralize_params) => {
-      StructUmeeMsg::supply_collateral(supply_collateralize_params)
-    }
+  if memo.len() > MAX_MEMO_LENGTH {
+    return Err(ContractError::CustomError {
+      val: format!(
+        "memo of {} bytes, above the {} bytes limit",
+        memo.len(),
+        MAX_MEMO_LENGTH
+      ),
+    });
+  }
+  if let ExecuteMsg::WithMemo { .. } = inner {
+    return Err(ContractError::CustomError {
+      val: String::from("a memo can't wrap another memo"),
+    });
   }
+
+  Ok(execute(deps, env, info, inner)?.add_attribute("client_memo", memo))
 }
 
-// queries doesn't change the state, but it open the state with read permissions
-// it can also query from native modules "bank, stake, custom..."
-// returns an json wrapped data, like:
-// {
-//   "data": ...
-// }
-#[cfg_attr(not(feature = "library"), entry_point)]
-pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
-  match msg {
-    // returns OwnerResponse the current contract owner
-    // expected json input:
-    // {
-    //   "get_owner": {}
-    // }
-    // successful json output:
-    // {
-    //   "data": {
-    //     "owner": "umee1y6xz2ggfc0pcsmyjlekh0j9pxh6hk87ymc9due"
-    //   }
-    // }
-    QueryMsg::GetOwner {} => to_json_binary(&query_owner(deps)?),
+// execute_snapshot_position stores the net value of the account summary of
+// the address along with the block height it was computed at. Only the
+// address itself or the owner can move its PositionPnl baseline.
+fn execute_snapshot_position(
+  deps: DepsMut,
+  env: Env,
+  info: MessageInfo,
+  address: Addr,
+) -> Result<Response<StructUmeeMsg>, ContractError> {
+  let address = deps.api.addr_validate(address.as_str())?;
+  if info.sender != address {
+    ensure_owner(deps.as_ref(), &info)?;
+  }
+  let value = net_value(deps.as_ref(), &address)?;
+  POSITION_SNAPSHOTS.save(deps.storage, &address, &(env.block.height, value))?;
 
-    // queries for anything availabe from the blockchain native modules
-    // "iterator, staking, stargate, custom"
-    // example json input for custom module:
-    // {
-    //   "chain": {
-    //     "custom": {
-    //       "assigned_query": uint16,
-    //       "query_func_name": {
-    //         ...
-    //       }
-    //     }
-    //   }
-    // }
-    // successful json output:
-    // {
-    //   "data": {
-    //     ...
-    //   }
-    // }
-    QueryMsg::Chain(request) => query_chain(deps, &request),
+  Ok(
+    Response::new()
+      .add_attribute("action", "snapshot_position")
+      .add_attribute("address", address)
+      .add_attribute("net_value", value.to_string()),
+  )
+}
 
-    QueryMsg::Umee(umee_query_box) => query_umee(deps, _env, *umee_query_box),
+// execute_withdraw_all sends a max withdraw per denom supplied by the
+// contract, up to MAX_TVL_MARKETS, skipping the markets without any
+// liquidity left to withdraw.
+fn execute_withdraw_all(
+  deps: Deps,
+  env: Env,
+  supplier: Addr,
+) -> Result<Response<StructUmeeMsg>, ContractError> {
+  let contract = env.contract.address;
+  if supplier != contract {
+    return Err(ContractError::CustomError {
+      val: format!(
+        "withdraw all can only act on the contract supply {}, not on {}",
+        contract, supplier
+      ),
+    });
+  }
 
-    // consumes the query_chain wrapping the JSON to call directly
-    // the ExchangeRates query from the oracle umee native module
-    // expected json input:
-    // {
-    //   "get_exchange_rate_base": {
-    //     "denom": "uumee"
-    //   }
-    // }
-    // successful json output:
-    // {
-    //   "data": {
-    //     "borrowed": [
-    //       {
-    //         "denom": "uumee",
-    //         "amount": "50001"
-    //       }
-    //     ]
-    //   }
-    // }
-    QueryMsg::ExchangeRates(exchange_rates_params) => {
-      to_json_binary(&query_exchange_rates(deps, exchange_rates_params)?)
-    }
-    QueryMsg::RegisteredTokens(registered_tokens_params) => {
-      to_json_binary(&query_registered_tokens(deps, registered_tokens_params)?)
+  let supplied = query_account_balances(deps, AccountBalancesParams::new(contract))?.supplied;
+  if supplied.is_empty() {
+    return Err(ContractError::CustomError {
+      val: String::from("withdraw all without any supplied denom"),
+    });
+  }
+  if supplied.len() > MAX_TVL_MARKETS {
+    return Err(ContractError::CustomError {
+      val: format!(
+        "{} supplied denoms, above the {} markets limit of withdraw all",
+        supplied.len(),
+        MAX_TVL_MARKETS
+      ),
+    });
+  }
+  dedup_denoms(
+    "withdraw all",
+    supplied.iter().map(|coin| coin.denom.as_str()),
+  )?;
+
+  let mut msgs = vec![];
+  let mut skipped = vec![];
+  for coin in supplied {
+    let available = query_available_withdraw(deps, AvailableWithdrawParams::new(&coin.denom))?;
+    if available.amount.amount.is_zero() {
+      skipped.push(coin.denom);
+      continue;
     }
-    QueryMsg::LeverageParameters(leverage_parameters_params) => to_json_binary(
-      &query_leverage_parameters(deps, leverage_parameters_params)?,
-    ),
+    msgs.push(StructUmeeMsg::from(UmeeMsgLeverage::MaxWithdraw(
+      MsgMaxWithdrawParams::new(coin.denom),
+    )));
+  }
+  if msgs.is_empty() {
+    return Err(ContractError::CustomError {
+      val: format!("no liquidity to withdraw any of {}", skipped.join(", ")),
+    });
   }
+
+  Ok(
+    Response::new()
+      .add_attribute("action", "withdraw_all")
+      .add_attribute("count", msgs.len().to_string())
+      .add_attribute("skipped", skipped.join(","))
+      .add_messages(msgs),
+  )
 }
 
-// query_umee contains the umee leverage available queries
-fn query_umee(deps: Deps, _env: Env, umee_msg: UmeeQuery) -> StdResult<Binary> {
-  match umee_msg {
-    // consumes the query_chain wrapped by Umee Leverage enums
-    // to clarift the JSON queries to umee leverage native module
-    // example json input:
-    // {
-    //   "umee": {
-    //     "leverage": {
-    //       "query_func_name": {
-    //         ...
-    //       }
-    //     }
-    //   }
-    // }
-    // successful json output:
-    // {
-    //   "data": {
-    //     ...
-    //   }
-    // }
-    UmeeQuery::Leverage(leverage) => query_leverage(deps, _env, leverage),
+// execute_adjust_collateral collateralizes or decollateralizes the delta
+// between the contract collateral of the denom and the target.
+fn execute_adjust_collateral(
+  deps: Deps,
+  env: Env,
+  adjust_collateral_params: AdjustCollateralParams,
+) -> Result<Response<StructUmeeMsg>, ContractError> {
+  let contract = env.contract.address;
+  if adjust_collateral_params.borrower != contract {
+    return Err(ContractError::CustomError {
+      val: format!(
+        "adjust collateral can only act on the contract position {}, not on {}",
+        contract, adjust_collateral_params.borrower
+      ),
+    });
+  }
 
-    // consumes the query_chain wrapped by Umee Leverage enums
-    // to clarift the JSON queries to umee leverage native module
-    // example json input:
-    // {
-    //   "umee": {
-    //     "oracle": {
-    //       "query_func_name": {
-    //         ...
-    //       }
-    //     }
-    //   }
-    // }
-    // successful json output:
-    // {
-    //   "data": {
-    //     ...
-    //   }
-    // }
-    UmeeQuery::Oracle(oracle) => query_oracle(deps, _env, oracle),
-    // incentive
-    UmeeQuery::Incentive(incentive) => query_incentive(deps, _env, incentive),
-    UmeeQuery::Metoken(metoken) => query_metoken(deps, _env, metoken),
+  let udenom = utoken_denom(&adjust_collateral_params.denom);
+  let target = adjust_collateral_params.target;
+  let collateral = balance_of(
+    &query_account_balances(deps, AccountBalancesParams::new(contract.clone()))?.collateral,
+    &udenom,
+  );
+
+  let msg = if target > collateral {
+    let delta = target - collateral;
+    let available = deps.querier.query_balance(&contract, &udenom)?.amount;
+    if delta > available {
+      return Err(ContractError::CustomError {
+        val: format!(
+          "collateralizing {}{} needs more than the {}{} held",
+          delta, udenom, available, udenom
+        ),
+      });
+    }
+    UmeeMsgLeverage::Collateralize(CollateralizeParams::new(Coin::new(delta.u128(), &udenom)))
+  } else if target < collateral {
+    let delta = collateral - target;
+    UmeeMsgLeverage::Decollateralize(DecollateralizeParams::new(Coin::new(delta.u128(), &udenom)))
+  } else {
+    return Ok(
+      Response::new()
+        .add_attribute("action", "adjust_collateral")
+        .add_attribute("denom", &udenom)
+        .add_attribute("direction", "none"),
+    );
+  };
+
+  let umee_msg = StructUmeeMsg::from(msg);
+  let direction = umee_msg.assigned_str();
+  Ok(
+    umee_response("adjust_collateral", umee_msg)
+      .add_attribute("denom", &udenom)
+      .add_attribute("direction", direction),
+  )
+}
+
+// execute_collateralize_all collateralizes the whole uToken bank balance of
+// the denom held by the contract, which has to be the borrower.
+fn execute_collateralize_all(
+  deps: Deps,
+  env: Env,
+  borrower: Addr,
+  denom: String,
+) -> Result<Response<StructUmeeMsg>, ContractError> {
+  let contract = env.contract.address;
+  if borrower != contract {
+    return Err(ContractError::CustomError {
+      val: format!(
+        "collateralize all can only act on the contract position {}, not on {}",
+        contract, borrower
+      ),
+    });
   }
+
+  let udenom = utoken_denom(&denom);
+  let held = deps.querier.query_balance(&contract, &udenom)?;
+  if held.amount.is_zero() {
+    return Err(ContractError::CustomError {
+      val: format!("no {} held to collateralize", udenom),
+    });
+  }
+
+  let umee_msg = StructUmeeMsg::from(UmeeMsgLeverage::Collateralize(CollateralizeParams::new(
+    held.clone(),
+  )));
+  Ok(umee_response("collateralize_all", umee_msg).add_attribute("amount", held.to_string()))
 }
 
-// returns the current owner of the contract from the state
-fn query_owner(deps: Deps) -> StdResult<OwnerResponse> {
-  let state = STATE.load(deps.storage)?;
-  Ok(OwnerResponse { owner: state.owner })
+// execute_repay_from_balance repays the debt of the contract with its whole
+// balance of the denom, the module repays at most the debt. Only the
+// contract can be the borrower since the leverage module repays the debt
+// of the signer.
+fn execute_repay_from_balance(
+  deps: DepsMut,
+  env: Env,
+  borrower: Addr,
+  denom: String,
+) -> Result<Response<StructUmeeMsg>, ContractError> {
+  let held = deps.querier.query_balance(&env.contract.address, &denom)?;
+  if held.amount.is_zero() {
+    return Err(ContractError::CustomError {
+      val: format!("no {} held to repay", denom),
+    });
+  }
+  if borrower != env.contract.address {
+    return Err(ContractError::CustomError {
+      val: format!(
+        "the leverage module only repays the debt of the signer {}, not of {}, use a liquidation instead",
+        env.contract.address, borrower
+      ),
+    });
+  }
+
+  let repay = UmeeMsgLeverage::Repay(RepayParams::new(held.clone()));
+  repay.validate_denoms()?;
+  Ok(
+    umee_response("repay_from_balance", StructUmeeMsg::from(repay))
+      .add_attribute("amount", held.to_string()),
+  )
 }
 
-// query_chain queries for any availabe query in the chain native modules
-fn query_chain(deps: Deps, request: &QueryRequest<StructUmeeQuery>) -> StdResult<Binary> {
-  let raw = to_json_vec(request).map_err(|serialize_err| {
-    StdError::generic_err(format!("Serializing QueryRequest: {}", serialize_err))
-  })?;
-  match deps.querier.raw_query(&raw) {
-    SystemResult::Err(system_err) => Err(StdError::generic_err(format!(
-      "Querier system error: {}",
-      system_err
-    ))),
-    SystemResult::Ok(ContractResult::Err(contract_err)) => Err(StdError::generic_err(format!(
-      "Querier contract error: {}",
-      contract_err
-    ))),
-    SystemResult::Ok(ContractResult::Ok(value)) => Ok(value),
+// execute_unwind starts the unwind of the contract position in a denom, the
+// replies of its steps send the next one
+fn execute_unwind(
+  deps: DepsMut,
+  env: Env,
+  unwind_params: UnwindParams,
+) -> Result<Response<StructUmeeMsg>, ContractError> {
+  if unwind_params.account != env.contract.address {
+    return Err(ContractError::CustomError {
+      val: format!(
+        "unwind can only act on the contract position {}, not on {}",
+        env.contract.address, unwind_params.account
+      ),
+    });
+  }
+  if UNWIND.may_load(deps.storage)?.is_some() {
+    return Err(ContractError::CustomError {
+      val: String::from("an unwind is already in progress"),
+    });
   }
+
+  UNWIND.save(deps.storage, &unwind_params.repay_denom)?;
+  unwind_next(deps, &env, REPLY_UNWIND_REPAY, "unwind")
 }
 
-// query_leverage contains the umee leverage available queries
-fn query_leverage(deps: Deps, _env: Env, msg: UmeeQueryLeverage) -> StdResult<Binary> {
-  match msg {
-    UmeeQueryLeverage::LeverageParameters(leverage_parameters_params) => to_json_binary(
-      &query_leverage_parameters(deps, leverage_parameters_params)?,
-    ),
-    UmeeQueryLeverage::RegisteredTokens(registered_tokens_params) => {
-      to_json_binary(&query_registered_tokens(deps, registered_tokens_params)?)
-    }
-    UmeeQueryLeverage::MarketSummary(market_summary_params) => {
-      to_json_binary(&query_market_summary(deps, market_summary_params)?)
-    }
-    UmeeQueryLeverage::AccountBalances(account_balances_params) => {
-      to_json_binary(&query_account_balances(deps, account_balances_params)?)
-    }
-    UmeeQueryLeverage::AccountSummary(account_summary_params) => {
-      to_json_binary(&query_account_summary(deps, account_summary_params)?)
-    }
-    UmeeQueryLeverage::LiquidationTargets(liquidation_targets_params) => to_json_binary(
-      &query_liquidation_targets(deps, liquidation_targets_params)?,
-    ),
-    UmeeQueryLeverage::BadDebts(bad_debts_params) => {
-      to_json_binary(&query_bad_debts(deps, bad_debts_params)?)
-    }
-    UmeeQueryLeverage::MaxWithdraw(max_withdraw_params) => {
-      to_json_binary(&query_max_withdraw(deps, max_withdraw_params)?)
-    }
-    UmeeQueryLeverage::MaxBorrow(max_borrow_params) => {
-      to_json_binary(&query_max_borrow(deps, max_borrow_params)?)
-    }
-  }
-}
+// unwind_next sends the first unwind step, from first_step on, that has
+// something to do on the current position. Steps are only sent forward,
+// so an unwind takes at most three submessages
+fn unwind_next(
+  deps: DepsMut,
+  env: &Env,
+  first_step: u64,
+  action: &str,
+) -> Result<Response<StructUmeeMsg>, ContractError> {
+  let denom = UNWIND.load(deps.storage)?;
+  let udenom = utoken_denom(&denom);
+  let contract = &env.contract.address;
+
+  for step in first_step..=REPLY_UNWIND_WITHDRAW {
+    let msg = match step {
+      REPLY_UNWIND_REPAY => {
+        let borrowed = balance_of(
+          &query_account_balances(deps.as_ref(), AccountBalancesParams::new(contract.clone()))?
+            .borrowed,
+          &denom,
+        );
+        let available = deps.querier.query_balance(contract, &denom)?.amount;
+        let amount = borrowed.min(available);
+        (!amount.is_zero())
+          .then(|| UmeeMsgLeverage::Repay(RepayParams::new(Coin::new(amount.u128(), &denom))))
+      }
+      REPLY_UNWIND_DECOLLATERALIZE => {
+        let balances =
+          query_account_balances(deps.as_ref(), AccountBalancesParams::new(contract.clone()))?;
+        let mut amount = balance_of(&balances.collateral, &udenom);
+        // with debt still open the module rejects a decollateralize breaking
+        // the borrow limit, only the collateral the max withdraw frees goes
+        if balances.borrowed.iter().any(|coin| !coin.amount.is_zero()) {
+          let max_withdraw = query_max_withdraw(
+            deps.as_ref(),
+            MaxWithdrawParams::new(contract.clone(), &denom),
+          )?;
+          let wallet = deps.querier.query_balance(contract, &udenom)?.amount;
+          amount = amount.min(max_withdraw.u_tokens.amount.saturating_sub(wallet));
+        }
+        (!amount.is_zero()).then(|| {
+          UmeeMsgLeverage::Decollateralize(DecollateralizeParams::new(Coin::new(
+            amount.u128(),
+            &udenom,
+          )))
+        })
+      }
+      _ => {
+        let amount = deps.querier.query_balance(contract, &udenom)?.amount;
+        (!amount.is_zero()).then(|| {
+          UmeeMsgLeverage::Withdraw(WithdrawParams::new(Coin::new(amount.u128(), &udenom)))
+        })
+      }
+    };
 
-// query_incentive
-fn query_incentive(deps: Deps, _env: Env, msg: UmeeQueryIncentive) -> StdResult<Binary> {
-  match msg {
-    UmeeQueryIncentive::IncentiveParameters(incentive_params) => {
-      to_json_binary(&query_incentive_params(deps, incentive_params)?)
-    }
-    UmeeQueryIncentive::TotalBonded(params) => to_json_binary(&query_total_bonded(deps, params)?),
-    UmeeQueryIncentive::TotalUnbonding(params) => {
-      to_json_binary(&query_total_unbonding(deps, params)?)
-    }
-    UmeeQueryIncentive::AccountBonds(params) => to_json_binary(&query_account_bonds(deps, params)?),
-    UmeeQueryIncentive::PendingRewards(params) => {
-      to_json_binary(&query_pending_rewards(deps, params)?)
-    }
-    UmeeQueryIncentive::CompletedIncentivePrograms(params) => {
-      to_json_binary(&query_completed_incentive_programs(deps, params)?)
-    }
-    UmeeQueryIncentive::OngoingIncentivePrograms(params) => {
-      to_json_binary(&query_ongoing_incentive_programs(deps, params)?)
-    }
-    UmeeQueryIncentive::UpcomingIncentivePrograms(params) => {
-      to_json_binary(&query_upcoming_incentive_programs(deps, params)?)
-    }
-    UmeeQueryIncentive::IncentiveProgram(params) => {
-      to_json_binary(&query_incentive_program(deps, params)?)
-    }
-    UmeeQueryIncentive::CurrentRates(params) => to_json_binary(&query_current_rates(deps, params)?),
-    UmeeQueryIncentive::ActualRates(params) => to_json_binary(&query_actutal_rates(deps, params)?),
-    UmeeQueryIncentive::LastRewardTime(params) => {
-      to_json_binary(&query_last_reward_time(deps, params)?)
+    if let Some(msg) = msg {
+      let umee_msg = StructUmeeMsg::from(msg);
+      let event = Event::new("unwind")
+        .add_attribute("step", umee_msg.assigned_str())
+        .add_attribute("denom", denom);
+      return Ok(umee_submsg_response(action, umee_msg, step).add_event(event));
     }
   }
+
+  UNWIND.remove(deps.storage);
+  Ok(
+    Response::new().add_attribute("action", action).add_event(
+      Event::new("unwind")
+        .add_attribute("step", "done")
+        .add_attribute("denom", denom),
+    ),
+  )
 }
 
-// query_metoken
-fn query_metoken(deps: Deps, _env: Env, msg: UmeeQueryMeToken) -> StdResult<Binary> {
-  match msg {
-    UmeeQueryMeToken::MetokenParameters(params) => {
-      to_json_binary(&query_metoken_params(deps, params)?)
-    }
-    UmeeQueryMeToken::MetokenIndexes(params) => {
-      to_json_binary(&query_metoken_indexes(deps, params)?)
-    }
-    UmeeQueryMeToken::MetokenSwapfee(params) => {
-      to_json_binary(&query_metoken_swapfee(deps, params)?)
-    }
-    UmeeQueryMeToken::MetokenRedeemfee(params) => {
-      to_json_binary(&query_metoken_redeemfee(deps, params)?)
-    }
-    UmeeQueryMeToken::MetokenIndexbalances(params) => {
-      to_json_binary(&query_metoken_indexbalances(deps, params)?)
-    }
-    UmeeQueryMeToken::MetokenIndexPrices(params) => {
-      to_json_binary(&query_metoken_indexprice(deps, params)?)
-    }
-  }
+// balance_of returns the amount of the denom in the coins
+fn balance_of(coins: &[Coin], denom: &str) -> Uint128 {
+  coins
+    .iter()
+    .find(|coin| coin.denom == denom)
+    .map(|coin| coin.amount)
+    .unwrap_or_default()
 }
 
-// query_metoken_indexprice
-fn query_metoken_indexprice(
+// execute_supply_schedule sends one supply per entry keeping their order,
+// an invalid entry fails the whole schedule.
+fn execute_supply_schedule(
   deps: Deps,
-  params: MetokenIndexPricesParams,
-) -> StdResult<MetokenIndexPricesResponse> {
-  let request = QueryRequest::Custom(StructUmeeQuery::metoken_indexprice(params));
-  let response: MetokenIndexPricesResponse;
-  match query_chain(deps, &request) {
-    Err(err) => {
-      return Err(err);
-    }
-    Ok(binary) => {
-      match from_json::<MetokenIndexPricesResponse>(&binary) {
-        Err(err) => {
-          return Err(err);
-        }
-        Ok(resp) => response = resp,
-      };
-    }
+  schedule: Vec<SupplyParams>,
+) -> Result<Response<StructUmeeMsg>, ContractError> {
+  if schedule.is_empty() {
+    return Err(ContractError::CustomError {
+      val: String::from("supply schedule without any entry"),
+    });
   }
 
-  Ok(response)
+  // scheduled sums the entries of each denom, an entry checking the caps
+  // checks the supplies scheduled up to it
+  let mut scheduled: BTreeMap<String, Uint128> = BTreeMap::new();
+  let mut msgs = vec![];
+  for (i, supply_params) in schedule.into_iter().enumerate() {
+    let total = scheduled
+      .entry(supply_params.asset.denom.clone())
+      .or_default();
+    *total = total.saturating_add(supply_params.asset.amount);
+    let scheduled_params = SupplyParams {
+      asset: Coin::new(total.u128(), &supply_params.asset.denom),
+      check_caps: supply_params.check_caps,
+    };
+    let msg = UmeeMsgLeverage::Supply(supply_params);
+    msg
+      .validate_denoms()
+      .and_then(|_| msg.coins().into_iter().try_for_each(ensure_nonzero))
+      .and_then(|_| check_supply_caps(deps, &scheduled_params))
+      .map_err(|err| ContractError::CustomError {
+        val: format!("supply schedule entry {}: {}", i, err),
+      })?;
+    msgs.push(StructUmeeMsg::from(msg));
+  }
+
+  Ok(
+    Response::new()
+      .add_attribute("action", "supply_schedule")
+      .add_attribute("count", msgs.len().to_string())
+      .add_messages(msgs),
+  )
 }
 
-// query_metoken_indexbalances
-fn query_metoken_indexbalances(
-  deps: Deps,
-  params: MetokenIndexbalancesParams,
-) -> StdResult<MetokenIndexbalancesResponse> {
-  let request = QueryRequest::Custom(StructUmeeQuery::metoken_indexbalances(params));
-  let response: MetokenIndexbalancesResponse;
-  match query_chain(deps, &request) {
-    Err(err) => {
-      return Err(err);
-    }
-    Ok(binary) => {
-      match from_json::<MetokenIndexbalancesResponse>(&binary) {
-        Err(err) => {
-          return Err(err);
-        }
-        Ok(resp) => response = resp,
-      };
-    }
+// execute_rebalance emits every decollateralize message followed by every
+// collateralize message, so the collateral composition changes atomically
+fn execute_rebalance(
+  rebalance_params: RebalanceParams,
+) -> Result<Response<StructUmeeMsg>, ContractError> {
+  if rebalance_params.decollateralize.is_empty() && rebalance_params.collateralize.is_empty() {
+    return Err(ContractError::CustomError {
+      val: String::from("rebalance without any collateral change"),
+    });
+  }
+  if let Some(coin) = rebalance_params.collateralize.iter().find(|coin| {
+    rebalance_params
+      .decollateralize
+      .iter()
+      .any(|other| other.denom == coin.denom)
+  }) {
+    return Err(ContractError::CustomError {
+      val: format!(
+        "denom {} is both decollateralized and collateralized",
+        coin.denom
+      ),
+    });
   }
 
-  Ok(response)
+  dedup_denoms(
+    "rebalance",
+    rebalance_params
+      .decollateralize
+      .iter()
+      .chain(&rebalance_params.collateralize)
+      .map(|coin| coin.denom.as_str()),
+  )?;
+
+  let msgs: Vec<UmeeMsgLeverage> = rebalance_params
+    .decollateralize
+    .into_iter()
+    .map(|asset| UmeeMsgLeverage::Decollateralize(DecollateralizeParams { asset }))
+    .chain(
+      rebalance_params
+        .collateralize
+        .into_iter()
+        .map(|asset| UmeeMsgLeverage::Collateralize(CollateralizeParams { asset })),
+    )
+    .collect();
+  for msg in &msgs {
+    msg.validate_denoms()?;
+  }
+
+  Ok(
+    Response::new()
+      .add_attribute("action", "rebalance")
+      .add_messages(msgs.into_iter().map(StructUmeeMsg::from)),
+  )
 }
 
-// query_metoken_redeemfee
-fn query_metoken_redeemfee(
+// execute_supply_and_bond supplies the asset, collateralizes the uTokens
+// minted by the supply and bonds them in the incentive module. The uToken
+// amount is computed from the current uToken exchange rate, which can't
+// change between the messages of the same transaction
+fn execute_supply_and_bond(
   deps: Deps,
-  params: MetokenRedeemfeeParams,
-) -> StdResult<MetokenRedeemfeeResponse> {
-  let request = QueryRequest::Custom(StructUmeeQuery::metoken_redeemfee(params));
-  let response: MetokenRedeemfeeResponse;
-  match query_chain(deps, &request) {
-    Err(err) => {
-      return Err(err);
-    }
-    Ok(binary) => {
-      match from_json::<MetokenRedeemfeeResponse>(&binary) {
-        Err(err) => {
-          return Err(err);
-        }
-        Ok(resp) => response = resp,
-      };
-    }
+  env: Env,
+  supply_params: SupplyParams,
+) -> Result<Response<StructUmeeMsg>, ContractError> {
+  UmeeMsgLeverage::Supply(supply_params.clone()).validate_denoms()?;
+  ensure_nonzero(&supply_params.asset)?;
+  check_supply_caps(deps, &supply_params)?;
+  let market_summary = query_market_summary(
+    deps,
+    MarketSummaryParams {
+      denom: supply_params.asset.denom.clone(),
+    },
+  )?;
+  let u_token = Coin {
+    denom: utoken_denom(&supply_params.asset.denom),
+    amount: to_utoken_amount(
+      supply_params.asset.amount,
+      market_summary.utoken_exchange_rate,
+    )?,
+  };
+
+  let supply = StructUmeeMsg::from(UmeeMsgLeverage::Supply(supply_params));
+  let collateralize = StructUmeeMsg::from(UmeeMsgLeverage::Collateralize(CollateralizeParams {
+    asset: u_token.clone(),
+  }));
+  let bond = BondParams {
+    account: env.contract.address,
+    u_token,
+  };
+
+  Ok(
+    Response::new()
+      .add_attribute("action", "supply_and_bond")
+      .add_message(supply)
+      .add_message(collateralize)
+      .add_message(bond),
+  )
+}
+
+// to_utoken_amount converts a base token amount to uTokens rounding
+// down, the same way as the leverage module mints uTokens
+fn to_utoken_amount(amount: Uint128, exchange_rate: Decimal256) -> StdResult<Uint128> {
+  let utokens = Decimal256::from_ratio(amount, 1u128)
+    .checked_div(exchange_rate)
+    .map_err(|err| StdError::generic_err(format!("uToken exchange rate: {}", err)))?;
+  decimal_to_uint128(utokens)
+}
+
+// decimal_to_uint128 converts the decimal token amounts returned by the
+// native modules to coin amounts, rounding down
+fn decimal_to_uint128(amount: Decimal256) -> StdResult<Uint128> {
+  Uint128::try_from(amount.to_uint_floor()).map_err(StdError::from)
+}
+
+// tries to change the owner, but it could fail and respond as Unauthorized
+pub fn try_change_owner(
+  deps: DepsMut,
+  info: MessageInfo,
+  new_owner: Addr,
+) -> Result<Response<StructUmeeMsg>, ContractError> {
+  ensure_owner(deps.as_ref(), &info)?;
+  ensure_umee_address(&new_owner)?;
+  STATE.update(deps.storage, |mut state| -> Result<_, ContractError> {
+    state.owner = new_owner;
+    Ok(state)
+  })?;
+  Ok(Response::<StructUmeeMsg>::new().add_attribute("action", "change_owner"))
+}
+
+// try_set_denom_alias maps the alias to the denom, replacing any denom the
+// alias was mapped to. Only the owner can set an alias
+fn try_set_denom_alias(
+  deps: DepsMut,
+  info: MessageInfo,
+  alias: String,
+  denom: String,
+) -> Result<Response<StructUmeeMsg>, ContractError> {
+  ensure_owner(deps.as_ref(), &info)?;
+  if alias.trim().is_empty() {
+    return Err(ContractError::CustomError {
+      val: String::from("empty denom alias"),
+    });
+  }
+  // an alias shadowing a registered denom would redirect every message
+  // and query naming that token
+  let registry = query_registered_tokens(deps.as_ref(), RegisteredTokensParams {})?;
+  if registry.get(&alias).is_some() {
+    return Err(ContractError::InvalidDenom {
+      denom: alias,
+      reason: String::from("a registered base denom can't be an alias"),
+    });
+  }
+  if denom.trim().is_empty() || looks_like_symbol_denom(&denom) {
+    return Err(ContractError::InvalidDenom {
+      denom,
+      reason: String::from("an alias has to map to the base denom of a token"),
+    });
   }
 
-  Ok(response)
+  DENOM_ALIASES.save(deps.storage, alias.clone(), &denom)?;
+  Ok(
+    Response::new()
+      .add_attribute("action", "set_denom_alias")
+      .add_attribute("alias", alias)
+      .add_attribute("denom", denom),
+  )
 }
 
-// query_metoken_swapfee
-fn query_metoken_swapfee(
-  deps: Deps,
-  params: MetokenSwapfeeParams,
-) -> StdResult<MetokenSwapfeeResponse> {
-  let request = QueryRequest::Custom(StructUmeeQuery::metoken_swapfee(params));
-  let response: MetokenSwapfeeResponse;
-  match query_chain(deps, &request) {
-    Err(err) => {
-      return Err(err);
-    }
-    Ok(binary) => {
-      match from_json::<MetokenSwapfeeResponse>(&binary) {
-        Err(err) => {
-          return Err(err);
-        }
-        Ok(resp) => response = resp,
-      };
-    }
+// resolve_denom replaces a denom alias set by SetDenomAlias with its denom,
+// a denom without alias is kept as is
+fn resolve_denom(storage: &dyn Storage, denom: &mut String) -> StdResult<()> {
+  if let Some(resolved) = DENOM_ALIASES.may_load(storage, denom.clone())? {
+    *denom = resolved;
   }
+  Ok(())
+}
 
-  Ok(response)
+// resolve_utoken_denom resolves the alias of a uToken denom field, both
+// u/<alias> and the bare alias name the uToken of the aliased denom
+fn resolve_utoken_denom(storage: &dyn Storage, denom: &mut String) -> StdResult<()> {
+  let alias = denom.strip_prefix(UTOKEN_PREFIX).unwrap_or(denom);
+  if let Some(resolved) = DENOM_ALIASES.may_load(storage, alias.to_string())? {
+    *denom = utoken_denom(&resolved);
+  }
+  Ok(())
 }
 
-// query_metoken_indexes
-fn query_metoken_indexes(
-  deps: Deps,
-  params: MetokenIndexesParams,
-) -> StdResult<MetokenIndexesResponse> {
-  let request = QueryRequest::Custom(StructUmeeQuery::metoken_indexes(params));
-  let response: MetokenIndexesResponse;
-  match query_chain(deps, &request) {
-    Err(err) => {
-      return Err(err);
+// resolve_execute_aliases resolves the denoms named by the message before
+// it is validated, the inner message of WithMemo is resolved once executed
+fn resolve_execute_aliases(storage: &dyn Storage, msg: &mut ExecuteMsg) -> StdResult<()> {
+  let denoms = match msg {
+    ExecuteMsg::Umee(UmeeMsg::Leverage(leverage_msg)) => leverage_msg.denoms_mut(),
+    ExecuteMsg::Supply(supply_params)
+    | ExecuteMsg::SupplyAndBond(supply_params)
+    | ExecuteMsg::SupplyThenCollateralize(supply_params) => vec![&mut supply_params.asset.denom],
+    ExecuteMsg::SupplySchedule(schedule) => schedule
+      .iter_mut()
+      .map(|supply_params| &mut supply_params.asset.denom)
+      .collect(),
+    ExecuteMsg::BatchLeverage(batch) => batch
+      .iter_mut()
+      .flat_map(UmeeMsgLeverage::denoms_mut)
+      .collect(),
+    ExecuteMsg::Unwind(unwind_params) => vec![&mut unwind_params.repay_denom],
+    ExecuteMsg::AdjustCollateral(adjust_collateral_params) => {
+      vec![&mut adjust_collateral_params.denom]
     }
-    Ok(binary) => {
-      match from_json::<MetokenIndexesResponse>(&binary) {
-        Err(err) => {
-          return Err(err);
-        }
-        Ok(resp) => response = resp,
-      };
+    ExecuteMsg::CollateralizeAll { denom, .. } | ExecuteMsg::RepayFromBalance { denom, .. } => {
+      vec![denom]
     }
-  }
-
-  Ok(response)
+    // the withdraw and the rebalance move uTokens, which have no alias
+    ExecuteMsg::ChangeOwner { .. }
+    | ExecuteMsg::Withdraw(_)
+    | ExecuteMsg::Rebalance(_)
+    | ExecuteMsg::SetupFeeder(_)
+    | ExecuteMsg::RefreshTvlCache {}
+    | ExecuteMsg::WithdrawAll { .. }
+    | ExecuteMsg::WithMemo { .. }
+    | ExecuteMsg::SnapshotPosition { .. }
+    | ExecuteMsg::SetDenomAlias { .. } => vec![],
+  };
+  denoms
+    .into_iter()
+    .try_for_each(|denom| resolve_denom(storage, denom))
 }
 
-// query_metoken_params
-fn query_metoken_params(
-  deps: Deps,
-  params: MetokenParametersParams,
-) -> StdResult<MetokenParametersResponse> {
-  let request = QueryRequest::Custom(StructUmeeQuery::metoken_parameters(params));
-  let response: MetokenParametersResponse;
-  match query_chain(deps, &request) {
-    Err(err) => {
-      return Err(err);
+// resolve_query_aliases resolves the denoms named by the queries before
+// they are validated, the queries naming only addresses, symbol denoms or
+// meToken denoms are left as is
+fn resolve_query_aliases(storage: &dyn Storage, msg: &mut QueryMsg) -> StdResult<()> {
+  let denoms = match msg {
+    QueryMsg::Umee(umee_query) => return resolve_umee_query_aliases(storage, umee_query),
+    QueryMsg::Raw { query } => return resolve_umee_query_aliases(storage, query),
+    QueryMsg::ExchangeRates(exchange_rates_params) => {
+      exchange_rates_params.base_denom_mut().into_iter().collect()
     }
-    Ok(binary) => {
-      match from_json::<MetokenParametersResponse>(&binary) {
-        Err(err) => {
-          return Err(err);
-        }
-        Ok(resp) => response = resp,
-      };
+    QueryMsg::DenomMetadata { denom } | QueryMsg::Rates { denom, .. } => vec![denom],
+    // the chain requests are passed through as they are
+    QueryMsg::Chain(_)
+    | QueryMsg::GetOwner {}
+    | QueryMsg::RegisteredTokens(_)
+    | QueryMsg::LeverageParameters(_)
+    | QueryMsg::BorrowLimitUsed { .. }
+    | QueryMsg::IsLiquidatable { .. }
+    | QueryMsg::TotalValueLocked {}
+    | QueryMsg::CollateralBreakdown { .. }
+    | QueryMsg::CachedTvl { .. }
+    | QueryMsg::SelfPosition {}
+    | QueryMsg::PositionPnl { .. }
+    | QueryMsg::FullPosition { .. }
+    | QueryMsg::AssignedIds {}
+    | QueryMsg::ResolveAlias { .. } => vec![],
+  };
+  denoms
+    .into_iter()
+    .try_for_each(|denom| resolve_denom(storage, denom))
+}
+
+// resolve_umee_query_aliases resolves the denoms named by a query of any
+// umee module, the incentive module names uTokens
+fn resolve_umee_query_aliases(storage: &dyn Storage, query: &mut UmeeQuery) -> StdResult<()> {
+  let denoms = match query {
+    UmeeQuery::Leverage(leverage_query) => leverage_query.denoms_mut(),
+    UmeeQuery::Oracle(oracle_query) => oracle_query.base_denoms_mut(),
+    UmeeQuery::Metoken(metoken_query) => metoken_query.asset_denoms_mut(),
+    UmeeQuery::Incentive(incentive_query) => {
+      return incentive_query
+        .utoken_denoms_mut()
+        .into_iter()
+        .try_for_each(|denom| resolve_utoken_denom(storage, denom))
     }
-  }
+  };
+  denoms
+    .into_iter()
+    .try_for_each(|denom| resolve_denom(storage, denom))
+}
 
-  Ok(response)
+// ensure_owner responds as Unauthorized if the sender is not the owner
+fn ensure_owner(deps: Deps, info: &MessageInfo) -> Result<(), ContractError> {
+  if info.sender != STATE.load(deps.storage)?.owner {
+    return Err(ContractError::Unauthorized {});
+  }
+  Ok(())
 }
 
-// query_last_reward_time
-fn query_last_reward_time(
+// execute_batch_leverage attaches the leverage messages in the input order,
+// the chain runs them one after the other and reverts all of them if one
+// fails. The messages are sent without reply, the replies of the single
+// Supply, Withdraw and Borrow messages don't apply to a batch.
+fn execute_batch_leverage(
   deps: Deps,
-  params: LastRewardTimeParams,
-) -> StdResult<LastRewardTimeResponse> {
-  let request = QueryRequest::Custom(StructUmeeQuery::last_reward_time(params));
+  batch: Vec<UmeeMsgLeverage>,
+) -> Result<Response<StructUmeeMsg>, ContractError> {
+  if batch.is_empty() {
+    return Err(ContractError::CustomError {
+      val: String::from("batch leverage without any message"),
+    });
+  }
 
-  let response: LastRewardTimeResponse;
-  match query_chain(deps, &request) {
-    Err(err) => {
-      return Err(err);
+  // moves tracks the direction of every position changed by the batch,
+  // keyed by the position and the base denom
+  let mut moves: BTreeMap<(&str, String), (&str, bool)> = BTreeMap::new();
+  let mut touched: Vec<(&str, String)> = vec![];
+  let mut clamped = vec![];
+  let mut msgs = vec![];
+  for (i, mut msg) in batch.into_iter().enumerate() {
+    msg
+      .validate_denoms()
+      .and_then(|_| msg.coins().into_iter().try_for_each(ensure_nonzero))
+      .and_then(|_| match &msg {
+        UmeeMsgLeverage::Supply(supply_params) => check_supply_caps(deps, supply_params),
+        _ => Ok(()),
+      })
+      .map_err(|err| ContractError::CustomError {
+        val: format!("batch leverage entry {}: {}", i, err),
+      })?;
+    if let UmeeMsgLeverage::Borrow(borrow_params) = &mut msg {
+      if let Some(requested) =
+        clamp_borrow(deps, borrow_params).map_err(|err| ContractError::CustomError {
+          val: format!("batch leverage entry {}: {}", i, err),
+        })?
+      {
+        clamped.push(requested.to_string());
+      }
     }
-    Ok(binary) => {
-      match from_json::<LastRewardTimeResponse>(&binary) {
-        Err(err) => {
-          return Err(err);
+    for (position, denom, action, increases) in position_moves(&msg) {
+      let base = denom
+        .strip_prefix(UTOKEN_PREFIX)
+        .unwrap_or(denom)
+        .to_string();
+      touched.push((position, base.clone()));
+      match moves.get(&(position, base.clone())) {
+        Some((other, other_increases)) if *other_increases != increases => {
+          return Err(ContractError::CustomError {
+            val: format!(
+              "batch leverage can't mix {} and {} of {}",
+              other, action, base
+            ),
+          });
         }
-        Ok(resp) => response = resp,
-      };
+        Some(_) => {}
+        None => {
+          moves.insert((position, base), (action, increases));
+        }
+      }
     }
+    msgs.push(StructUmeeMsg::from(msg));
+  }
+  // a position moved twice in the same direction is a repeated entry
+  for position in ["supply", "collateral", "debt"] {
+    dedup_denoms(
+      &format!("batch leverage {} position", position),
+      touched
+        .iter()
+        .filter(|(touched_position, _)| *touched_position == position)
+        .map(|(_, denom)| denom.as_str()),
+    )?;
   }
 
-  Ok(response)
+  let res = Response::new()
+    .add_attribute("action", "batch_leverage")
+    .add_attribute("count", msgs.len().to_string())
+    .add_messages(msgs);
+  if clamped.is_empty() {
+    return Ok(res);
+  }
+  Ok(res.add_attribute("borrow_clamped", clamped.join(",")))
 }
 
-// query_actutal_rates
-fn query_actutal_rates(deps: Deps, params: ActualRatesParams) -> StdResult<ActualRatesResponse> {
-  let request = QueryRequest::Custom(StructUmeeQuery::actual_rates(params));
-
-  let response: ActualRatesResponse;
-  match query_chain(deps, &request) {
-    Err(err) => {
-      return Err(err);
+// position_moves returns the positions a leverage message changes with the
+// denom it names, the action and whether it increases the position.
+fn position_moves(msg: &UmeeMsgLeverage) -> Vec<(&'static str, &str, &'static str, bool)> {
+  match msg {
+    UmeeMsgLeverage::Supply(params) => vec![("supply", &params.asset.denom, "supply", true)],
+    UmeeMsgLeverage::Withdraw(params) => {
+      vec![("supply", &params.asset.denom, "withdraw", false)]
     }
-    Ok(binary) => {
-      match from_json::<ActualRatesResponse>(&binary) {
-        Err(err) => {
-          return Err(err);
-        }
-        Ok(resp) => response = resp,
-      };
+    UmeeMsgLeverage::MaxWithdraw(params) => {
+      vec![("supply", &params.denom, "max_withdraw", false)]
     }
+    UmeeMsgLeverage::Collateralize(params) => {
+      vec![("collateral", &params.asset.denom, "collateralize", true)]
+    }
+    UmeeMsgLeverage::Decollateralize(params) => {
+      vec![("collateral", &params.asset.denom, "decollateralize", false)]
+    }
+    UmeeMsgLeverage::SupplyCollateral(params) => vec![
+      ("supply", &params.asset.denom, "supply_collateral", true),
+      ("collateral", &params.asset.denom, "supply_collateral", true),
+    ],
+    UmeeMsgLeverage::Borrow(params) => vec![("debt", &params.asset.denom, "borrow", true)],
+    UmeeMsgLeverage::MaxBorrow(params) => {
+      vec![("debt", &params.denom.denom, "max_borrow", true)]
+    }
+    UmeeMsgLeverage::Repay(params) => vec![("debt", &params.asset.denom, "repay", false)],
+    UmeeMsgLeverage::Liquidate(_) => vec![],
   }
+}
 
-  Ok(response)
+// dedup_denoms rejects the batches listing a denom more than once, every
+// entry is processed on its own so a repeated denom would be processed twice.
+fn dedup_denoms<'a>(
+  batch: &str,
+  denoms: impl IntoIterator<Item = &'a str>,
+) -> Result<(), ContractError> {
+  let mut seen = BTreeSet::new();
+  for denom in denoms {
+    if !seen.insert(denom) {
+      return Err(ContractError::CustomError {
+        val: format!("{} lists the denom {} more than once", batch, denom),
+      });
+    }
+  }
+  Ok(())
 }
 
-// query_current_rates
-fn query_current_rates(deps: Deps, params: CurrentRatesParams) -> StdResult<CurrentRatesResponse> {
-  let request = QueryRequest::Custom(StructUmeeQuery::current_rates(params));
+// ensure_umee_address rejects the addresses of other chains, an account
+// of another chain can't hold or use the umee positions.
+fn ensure_umee_address(addr: &Addr) -> Result<(), ContractError> {
+  if !is_umee_address(addr.as_str()) {
+    return Err(ContractError::InvalidAddress {
+      address: addr.to_string(),
+      reason: format!("not an {} bech32 address", UMEE_BECH32_PREFIX),
+    });
+  }
+  Ok(())
+}
 
-  let response: CurrentRatesResponse;
-  match query_chain(deps, &request) {
-    Err(err) => {
-      return Err(err);
-    }
-    Ok(binary) => {
-      match from_json::<CurrentRatesResponse>(&binary) {
-        Err(err) => {
-          return Err(err);
-        }
-        Ok(resp) => response = resp,
-      };
-    }
+// try_setup_feeder sends the oracle feed consent of the operator to the feeder
+fn try_setup_feeder(
+  deps: DepsMut,
+  info: MessageInfo,
+  setup_feeder_params: SetupFeederParams,
+) -> Result<Response<StructUmeeMsg>, ContractError> {
+  let SetupFeederParams { operator, feeder } = setup_feeder_params;
+  ensure_owner(deps.as_ref(), &info)?;
+  ensure_umee_address(&feeder)?;
+  Ok(
+    Response::new()
+      .add_attribute("action", "setup_feeder")
+      .add_attribute("operator", operator.as_str())
+      .add_attribute("feeder", feeder.as_str())
+      .add_message(DelegateFeedConsentParams {
+        operator,
+        delegate: feeder,
+      }),
+  )
+}
+
+// check_supply_caps fails a supply asking for check_caps when it would take
+// the supplied amount of the market above the max supply of the token, a
+// zero max supply has no limit.
+fn check_supply_caps(deps: Deps, supply_params: &SupplyParams) -> Result<(), ContractError> {
+  if !supply_params.check_caps {
+    return Ok(());
+  }
+  let asset = &supply_params.asset;
+  let registry = query_registered_tokens(deps, RegisteredTokensParams {})?;
+  let token = registry
+    .get(&asset.denom)
+    .ok_or_else(|| ContractError::CustomError {
+      val: format!("denom {} not registered", asset.denom),
+    })?;
+  let cap = Decimal256::from(token.max_supply());
+  if cap.is_zero() {
+    return Ok(());
   }
 
-  Ok(response)
+  let market = query_market_summary(deps, MarketSummaryParams::new(&asset.denom))?;
+  let headroom = cap.saturating_sub(market.supplied);
+  if Decimal256::from_ratio(asset.amount, 1u128) > headroom {
+    return Err(ContractError::CustomError {
+      val: format!(
+        "supply cap exceeded: supplying {} leaves {}{} of headroom under the {}{} max supply",
+        asset, headroom, asset.denom, cap, asset.denom
+      ),
+    });
+  }
+  Ok(())
 }
 
-// query_incentive_program
-fn query_incentive_program(
+// execute_borrow sends the borrow, clamped by clamp_borrow when it asks
+// for max_available.
+fn execute_borrow(
   deps: Deps,
-  params: IncentiveProgramParams,
-) -> StdResult<IncentiveProgramResponse> {
-  let request = QueryRequest::Custom(StructUmeeQuery::incentive_program(params));
+  mut borrow_params: BorrowParams,
+) -> Result<Response<StructUmeeMsg>, ContractError> {
+  let clamped = clamp_borrow(deps, &mut borrow_params)?;
+  let res = StructUmeeMsg::borrow(borrow_params)?;
+  Ok(match clamped {
+    Some(requested) => res.add_attribute("borrow_clamped", requested.to_string()),
+    None => res,
+  })
+}
 
-  let response: IncentiveProgramResponse;
-  match query_chain(deps, &request) {
-    Err(err) => {
-      return Err(err);
-    }
-    Ok(binary) => {
-      match from_json::<IncentiveProgramResponse>(&binary) {
-        Err(err) => {
-          return Err(err);
-        }
-        Ok(resp) => response = resp,
-      };
-    }
+// clamp_borrow lowers a borrow asking for max_available to the amount the
+// market still has available to borrow, so it goes through on a market
+// short of liquidity instead of being rejected. It returns the coin asked
+// for when the borrow was clamped.
+fn clamp_borrow(
+  deps: Deps,
+  borrow_params: &mut BorrowParams,
+) -> Result<Option<Coin>, ContractError> {
+  if !borrow_params.max_available {
+    return Ok(None);
+  }
+  let asset = &mut borrow_params.asset;
+  let market = query_market_summary(deps, MarketSummaryParams::new(&asset.denom))?;
+  if Decimal256::from_ratio(asset.amount, 1u128) <= market.available_borrow {
+    return Ok(None);
   }
 
-  Ok(response)
+  let available = decimal_to_uint128(market.available_borrow)?;
+  if available.is_zero() {
+    return Err(ContractError::CustomError {
+      val: format!("nothing available to borrow in the {} market", asset.denom),
+    });
+  }
+  let requested = asset.clone();
+  asset.amount = available;
+  Ok(Some(requested))
 }
 
-// query_upcoming_incentive_programs
-fn query_upcoming_incentive_programs(
-  deps: Deps,
-  params: UpcomingIncentiveProgramsParams,
-) -> StdResult<UpcomingIncentiveProgramsResponse> {
-  let request = QueryRequest::Custom(StructUmeeQuery::upcoming_incentive_programs(params));
-
-  let response: UpcomingIncentiveProgramsResponse;
-  match query_chain(deps, &request) {
-    Err(err) => {
-      return Err(err);
-    }
-    Ok(binary) => {
-      match from_json::<UpcomingIncentiveProgramsResponse>(&binary) {
-        Err(err) => {
-          return Err(err);
-        }
-        Ok(resp) => response = resp,
-      };
-    }
+// ensure_nonzero rejects a coin without any amount, the leverage module
+// would only reject the no-op message once it is delivered.
+fn ensure_nonzero(coin: &Coin) -> Result<(), ContractError> {
+  if coin.amount.is_zero() {
+    return Err(ContractError::ZeroAmount {
+      denom: coin.denom.clone(),
+    });
   }
+  Ok(())
+}
 
-  Ok(response)
+// execute_supply sends the supply as a submessage, saving the contract
+// uToken balance so its reply can tell how many uTokens were minted
+fn execute_supply(
+  deps: DepsMut,
+  env: Env,
+  supply_params: SupplyParams,
+  reply_id: u64,
+) -> Result<Response<StructUmeeMsg>, ContractError> {
+  let supply = UmeeMsgLeverage::Supply(supply_params.clone());
+  supply.validate_denoms()?;
+  ensure_nonzero(&supply_params.asset)?;
+  check_supply_caps(deps.as_ref(), &supply_params)?;
+  save_reply_balances(deps, &env, &[utoken_denom(&supply_params.asset.denom)])?;
+  submsg_chain(supply, reply_id)
 }
 
-// query_ongoing_incentive_programs
-fn query_ongoing_incentive_programs(
-  deps: Deps,
-  params: OngoingIncentiveProgramsParams,
-) -> StdResult<OngoingIncentiveProgramsResponse> {
-  let request = QueryRequest::Custom(StructUmeeQuery::ongoing_incentive_programs(params));
+// execute_withdraw sends the withdraw as a submessage, saving the contract
+// base token balance for its reply. The min_received guard is stored for
+// the reply and cleared by a withdraw without guard
+fn execute_withdraw(
+  deps: DepsMut,
+  env: Env,
+  withdraw_params: WithdrawParams,
+  min_received: Option<Uint128>,
+) -> Result<Response<StructUmeeMsg>, ContractError> {
+  match min_received {
+    Some(min_received) => WITHDRAW_MIN_RECEIVED.save(deps.storage, &min_received)?,
+    None => WITHDRAW_MIN_RECEIVED.remove(deps.storage),
+  }
+  let udenom = &withdraw_params.asset.denom;
+  let denom = udenom.strip_prefix(UTOKEN_PREFIX).unwrap_or(udenom);
+  save_reply_balances(deps, &env, &[denom.to_string()])?;
+  submsg_chain(UmeeMsgLeverage::Withdraw(withdraw_params), REPLY_WITHDRAW)
+}
 
-  let response: OngoingIncentiveProgramsResponse;
-  match query_chain(deps, &request) {
-    Err(err) => {
-      return Err(err);
+// execute_leverage handles the execution of every msg of leverage umee native modules
+fn execute_leverage(
+  deps: DepsMut,
+  env: Env,
+  execute_leverage_msg: UmeeMsgLeverage,
+) -> Result<Response<StructUmeeMsg>, ContractError> {
+  execute_leverage_msg.validate_denoms()?;
+  execute_leverage_msg
+    .coins()
+    .into_iter()
+    .try_for_each(ensure_nonzero)?;
+  match execute_leverage_msg {
+    UmeeMsgLeverage::Supply(supply_params) => {
+      check_supply_caps(deps.as_ref(), &supply_params)?;
+      StructUmeeMsg::supply(supply_params)
     }
-    Ok(binary) => {
-      match from_json::<OngoingIncentiveProgramsResponse>(&binary) {
-        Err(err) => {
-          return Err(err);
-        }
-        Ok(resp) => response = resp,
-      };
+    UmeeMsgLeverage::Withdraw(withdraw_params) => StructUmeeMsg::withdraw(withdraw_params),
+    UmeeMsgLeverage::MaxWithdraw(max_withdraw_params) => {
+      execute_max_withdraw(deps, env, max_withdraw_params)
+    }
+    UmeeMsgLeverage::Collateralize(collateralize_params) => {
+      StructUmeeMsg::collateralize(collateralize_params)
+    }
+    UmeeMsgLeverage::Decollateralize(decollateralize_params) => {
+      StructUmeeMsg::decollateralize(decollateralize_params)
+    }
+    UmeeMsgLeverage::Borrow(borrow_params) => execute_borrow(deps.as_ref(), borrow_params),
+    UmeeMsgLeverage::MaxBorrow(borrow_params) => StructUmeeMsg::max_borrow(borrow_params),
+    UmeeMsgLeverage::Repay(repay_params) => StructUmeeMsg::repay(repay_params),
+    UmeeMsgLeverage::Liquidate(liquidate_params) => {
+      ensure_umee_address(&liquidate_params.borrower)?;
+      execute_liquidate(deps, env, liquidate_params)
+    }
+    UmeeMsgLeverage::SupplyCollateral(supply_collateralize_params) => {
+      StructUmeeMsg::supply_collateral(supply_collateralize_params)
     }
   }
-
-  Ok(response)
 }
 
-// query_completed_incentive_programs
-fn query_completed_incentive_programs(
-  deps: Deps,
-  params: CompletedIncentiveProgramsParams,
-) -> StdResult<CompletedIncentiveProgramsResponse> {
-  let request = QueryRequest::Custom(StructUmeeQuery::completed_incentive_programs(params));
-
-  let response: CompletedIncentiveProgramsResponse;
-  match query_chain(deps, &request) {
-    Err(err) => {
-      return Err(err);
-    }
-    Ok(binary) => {
-      match from_json::<CompletedIncentiveProgramsResponse>(&binary) {
-        Err(err) => {
-          return Err(err);
-        }
-        Ok(resp) => response = resp,
+// execute_max_withdraw saves the contract collateral and bank balances of
+// the denom before sending the max withdraw, its reply compares them with
+// the remaining ones. Without the collateral the max withdraw is still sent
+// and only its report is left out
+fn execute_max_withdraw(
+  deps: DepsMut,
+  env: Env,
+  max_withdraw_params: MsgMaxWithdrawParams,
+) -> Result<Response<StructUmeeMsg>, ContractError> {
+  let udenom = utoken_denom(&max_withdraw_params.denom);
+  match query_account_balances(
+    deps.as_ref(),
+    AccountBalancesParams::new(env.contract.address.clone()),
+  ) {
+    Ok(balances) => {
+      let collateral = Coin {
+        amount: balance_of(&balances.collateral, &udenom),
+        denom: udenom.clone(),
       };
+      MAX_WITHDRAW_COLLATERAL.save(deps.storage, &collateral)?;
     }
+    Err(_) => MAX_WITHDRAW_COLLATERAL.remove(deps.storage),
   }
+  save_reply_balances(deps, &env, &[udenom, max_withdraw_params.denom.clone()])?;
 
-  Ok(response)
+  submsg_chain(
+    UmeeMsgLeverage::MaxWithdraw(max_withdraw_params),
+    REPLY_MAX_WITHDRAW,
+  )
 }
 
-// query_account_bonds
-fn query_pending_rewards(
-  deps: Deps,
-  params: PendingRewardsParams,
-) -> StdResult<PendingRewardsResponse> {
-  let request = QueryRequest::Custom(StructUmeeQuery::pending_rewards(params));
-
-  let response: PendingRewardsResponse;
-  match query_chain(deps, &request) {
-    Err(err) => {
-      return Err(err);
-    }
-    Ok(binary) => {
-      match from_json::<PendingRewardsResponse>(&binary) {
-        Err(err) => {
-          return Err(err);
-        }
-        Ok(resp) => response = resp,
+// execute_liquidate saves the borrower debt of the repayment denom and its
+// collateral of the reward uTokens, along with the contract balance of the
+// reward denom, before sending the liquidation. Without the borrower
+// balances the liquidation is still sent and only its report is left out
+fn execute_liquidate(
+  deps: DepsMut,
+  env: Env,
+  liquidate_params: LiquidateParams,
+) -> Result<Response<StructUmeeMsg>, ContractError> {
+  let reward_denom = &liquidate_params.reward.denom;
+  let reward_udenom = if reward_denom.starts_with(UTOKEN_PREFIX) {
+    reward_denom.clone()
+  } else {
+    utoken_denom(reward_denom)
+  };
+  let borrower = liquidate_params.borrower.clone();
+  match query_account_balances(deps.as_ref(), AccountBalancesParams::new(borrower.clone())) {
+    Ok(balances) => {
+      let repayment_denom = &liquidate_params.repayment.denom;
+      let borrowed = Coin {
+        amount: balance_of(&balances.borrowed, repayment_denom),
+        denom: repayment_denom.clone(),
       };
+      let collateral = Coin {
+        amount: balance_of(&balances.collateral, &reward_udenom),
+        denom: reward_udenom,
+      };
+      LIQUIDATION_SNAPSHOT.save(deps.storage, &(borrower, borrowed, collateral))?;
     }
+    Err(_) => LIQUIDATION_SNAPSHOT.remove(deps.storage),
   }
+  save_reply_balances(deps, &env, std::slice::from_ref(reward_denom))?;
 
-  Ok(response)
+  submsg_chain(
+    UmeeMsgLeverage::Liquidate(liquidate_params),
+    REPLY_LIQUIDATE,
+  )
 }
 
-// query_account_bonds
-fn query_account_bonds(deps: Deps, params: AccountBondsParams) -> StdResult<AccountBondsResponse> {
-  let request = QueryRequest::Custom(StructUmeeQuery::account_bonds(params));
-
-  let response: AccountBondsResponse;
-  match query_chain(deps, &request) {
-    Err(err) => {
-      return Err(err);
-    }
-    Ok(binary) => {
-      match from_json::<AccountBondsResponse>(&binary) {
-        Err(err) => {
-          return Err(err);
-        }
-        Ok(resp) => response = resp,
-      };
-    }
+// submsg_chain sends the leverage msg as a submessage, so the reply
+// handler can report its outcome. It refuses a msg the chain wouldn't
+// recognize
+fn submsg_chain(
+  msg: UmeeMsgLeverage,
+  reply_id: u64,
+) -> Result<Response<StructUmeeMsg>, ContractError> {
+  let umee_msg = StructUmeeMsg::from(msg);
+  if !umee_msg.valid() {
+    return Err(ContractError::CustomError {
+      val: String::from("invalid umee msg"),
+    });
   }
+  Ok(umee_submsg_response(
+    &umee_msg.assigned_str(),
+    umee_msg,
+    reply_id,
+  ))
+}
 
-  Ok(response)
+// save_reply_balances saves the contract bank balances of the denoms before
+// a submessage. The native modules answer contracts with protobuf data, if
+// any, so the replies measure the outcome from the balances instead
+fn save_reply_balances(deps: DepsMut, env: &Env, denoms: &[String]) -> StdResult<()> {
+  let balances = denoms
+    .iter()
+    .map(|denom| deps.querier.query_balance(&env.contract.address, denom))
+    .collect::<StdResult<Vec<Coin>>>()?;
+  REPLY_BALANCES.save(deps.storage, &balances)
 }
 
-// query_total_unbonding
-fn query_total_unbonding(
-  deps: Deps,
-  params: TotalUnbondingParams,
-) -> StdResult<TotalUnbondingResponse> {
-  let request = QueryRequest::Custom(StructUmeeQuery::total_unbonding(params));
+// reply_balances clears the balances saved by save_reply_balances and
+// returns each of them along with the current amount of its denom
+fn reply_balances(deps: DepsMut, env: &Env) -> StdResult<Vec<(Coin, Uint128)>> {
+  let before = REPLY_BALANCES.load(deps.storage)?;
+  REPLY_BALANCES.remove(deps.storage);
+  before
+    .into_iter()
+    .map(|coin| {
+      let after = deps
+        .querier
+        .query_balance(&env.contract.address, &coin.denom)?;
+      Ok((coin, after.amount))
+    })
+    .collect()
+}
 
-  let response: TotalUnbondingResponse;
-  match query_chain(deps, &request) {
-    Err(err) => {
-      return Err(err);
-    }
-    Ok(binary) => {
-      match from_json::<TotalUnbondingResponse>(&binary) {
-        Err(err) => {
-          return Err(err);
-        }
-        Ok(resp) => response = resp,
-      };
+// reply_received returns the coin the contract gained during the submessage
+// of the single balance saved before it
+fn reply_received(deps: DepsMut, env: &Env) -> StdResult<Coin> {
+  match reply_balances(deps, env)?.as_slice() {
+    [(before, after)] => Ok(Coin {
+      amount: after.saturating_sub(before.amount),
+      denom: before.denom.clone(),
+    }),
+    balances => Err(StdError::generic_err(format!(
+      "expected one balance saved for the reply, found {}",
+      balances.len()
+    ))),
+  }
+}
+
+// reply receives the result of the leverage messages sent as submessages
+// and sets their outcome, measured from the balances of the contract, as
+// the response data, so callers invoking this contract as a submessage can
+// decode it. The data of the native module response is protobuf and is
+// ignored
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn reply(
+  deps: DepsMut,
+  env: Env,
+  msg: Reply,
+) -> Result<Response<StructUmeeMsg>, ContractError> {
+  if let Err(err) = msg.result.into_result() {
+    return Err(ContractError::CustomError { val: err });
+  }
+  match msg.id {
+    REPLY_SUPPLY => reply_supply(deps, env),
+    REPLY_WITHDRAW => reply_withdraw(deps, env),
+    REPLY_MAX_WITHDRAW => reply_max_withdraw(deps, env),
+    REPLY_SUPPLY_THEN_COLLATERALIZE => reply_supply_then_collateralize(deps, env),
+    REPLY_LIQUIDATE => reply_liquidate(deps, env),
+    REPLY_UNWIND_REPAY | REPLY_UNWIND_DECOLLATERALIZE | REPLY_UNWIND_WITHDRAW => {
+      unwind_next(deps, &env, msg.id + 1, "reply_unwind")
     }
+    id => Err(ContractError::CustomError {
+      val: format!("unknown reply id: {}", id),
+    }),
   }
+}
 
-  Ok(response)
+// reply_report sets the report as the response data, a report that
+// couldn't be measured is left out with a report_error attribute instead
+// of failing the message it reports on
+fn reply_report<T: Serialize>(
+  report: StdResult<T>,
+  method: &str,
+) -> Result<Response<StructUmeeMsg>, ContractError> {
+  let res = Response::new().add_attribute("action", format!("reply_{}", method));
+  match report {
+    Ok(report) => Ok(res.set_data(to_json_binary(&report)?)),
+    Err(err) => Ok(res.add_attribute("report_error", err.to_string())),
+  }
 }
 
-// query_total_bonded
-fn query_total_bonded(deps: Deps, params: TotalBondedParams) -> StdResult<TotalBondedResponse> {
-  let request = QueryRequest::Custom(StructUmeeQuery::total_bonded(params));
+// reply_supply sets the uTokens minted by the supply as the response data
+fn reply_supply(deps: DepsMut, env: Env) -> Result<Response<StructUmeeMsg>, ContractError> {
+  let report = reply_received(deps, &env).map(|received| SupplyMsgResponse { received });
+  reply_report(report, "supply")
+}
 
-  let response: TotalBondedResponse;
-  match query_chain(deps, &request) {
-    Err(err) => {
-      return Err(err);
-    }
-    Ok(binary) => {
-      match from_json::<TotalBondedResponse>(&binary) {
-        Err(err) => {
-          return Err(err);
-        }
-        Ok(resp) => response = resp,
-      };
-    }
+// reply_supply_then_collateralize collateralizes the uTokens minted by the
+// supply, measured from the contract uToken balance
+fn reply_supply_then_collateralize(
+  deps: DepsMut,
+  env: Env,
+) -> Result<Response<StructUmeeMsg>, ContractError> {
+  let received = reply_received(deps, &env)?;
+  if received.amount.is_zero() {
+    return Err(ContractError::CustomError {
+      val: format!("the supply minted no {}", received.denom),
+    });
   }
+  let collateralize = UmeeMsgLeverage::Collateralize(CollateralizeParams {
+    asset: received.clone(),
+  });
 
-  Ok(response)
+  Ok(
+    umee_response(
+      "reply_supply_then_collateralize",
+      StructUmeeMsg::from(collateralize),
+    )
+    .set_data(to_json_binary(&SupplyMsgResponse { received })?),
+  )
 }
 
-// query_incentive_params
-fn query_incentive_params(
-  deps: Deps,
-  incentive_params: IncentiveParametersParams,
-) -> StdResult<IncentiveParametersResponse> {
-  let request = QueryRequest::Custom(StructUmeeQuery::incentive_params(incentive_params));
+// reply_max_withdraw sets the uTokens burned, the base tokens received and
+// the uTokens decollateralized by the max withdraw as the response data
+fn reply_max_withdraw(deps: DepsMut, env: Env) -> Result<Response<StructUmeeMsg>, ContractError> {
+  reply_report(max_withdraw_report(deps, &env), "max_withdraw")
+}
 
-  let incentive_params_response: IncentiveParametersResponse;
-  match query_chain(deps, &request) {
-    Err(err) => {
-      return Err(err);
+// max_withdraw_report measures the max withdraw from the collateral and
+// bank balances saved by execute_max_withdraw. The uTokens burned are the
+// ones spent from the bank balance plus the decollateralized ones
+fn max_withdraw_report(mut deps: DepsMut, env: &Env) -> StdResult<MaxWithdrawReplyResponse> {
+  let before = MAX_WITHDRAW_COLLATERAL.may_load(deps.storage)?;
+  MAX_WITHDRAW_COLLATERAL.remove(deps.storage);
+  let (utokens, base) = match reply_balances(deps.branch(), env)?.as_slice() {
+    [utokens, base] => (utokens.clone(), base.clone()),
+    balances => {
+      return Err(StdError::generic_err(format!(
+        "expected two balances saved for the max withdraw, found {}",
+        balances.len()
+      )))
     }
-    Ok(binary) => {
-      match from_json::<IncentiveParametersResponse>(&binary) {
-        Err(err) => {
-          return Err(err);
-        }
-        Ok(response) => incentive_params_response = response,
-      };
+  };
+  let before = before.ok_or_else(|| StdError::not_found("max withdraw collateral"))?;
+  let balances = query_account_balances(
+    deps.as_ref(),
+    AccountBalancesParams::new(env.contract.address.clone()),
+  )?;
+  let released = before
+    .amount
+    .saturating_sub(balance_of(&balances.collateral, &before.denom));
+
+  Ok(MaxWithdrawReplyResponse {
+    withdrawn: Coin {
+      amount: utokens.0.amount.saturating_sub(utokens.1) + released,
+      denom: utokens.0.denom,
+    },
+    received: Coin {
+      amount: base.1.saturating_sub(base.0.amount),
+      denom: base.0.denom,
+    },
+    collateral_released: Coin {
+      amount: released,
+      denom: before.denom,
+    },
+  })
+}
+
+// reply_withdraw sets the base tokens received by the withdraw as the
+// response data, and fails the withdraw if they are less than the minimum
+// set by the withdraw message or can't be measured to check it
+fn reply_withdraw(deps: DepsMut, env: Env) -> Result<Response<StructUmeeMsg>, ContractError> {
+  let min_received = WITHDRAW_MIN_RECEIVED.may_load(deps.storage)?;
+  WITHDRAW_MIN_RECEIVED.remove(deps.storage);
+  let received = reply_received(deps, &env);
+
+  if let Some(min_received) = min_received {
+    let amount = match &received {
+      Ok(received) => received.amount,
+      Err(err) => {
+        return Err(ContractError::CustomError {
+          val: format!("withdraw minimum of {} not verified: {}", min_received, err),
+        })
+      }
+    };
+    if amount < min_received {
+      return Err(ContractError::CustomError {
+        val: format!(
+          "withdraw received {} base tokens, below the minimum of {}",
+          amount, min_received
+        ),
+      });
     }
   }
+  let report = received.map(|received| WithdrawMsgResponse { received });
+  reply_report(report, "withdraw")
+}
 
-  Ok(incentive_params_response)
+// reply_liquidate sets the amounts repaid, liquidated and rewarded by the
+// liquidation as the response data
+fn reply_liquidate(deps: DepsMut, env: Env) -> Result<Response<StructUmeeMsg>, ContractError> {
+  reply_report(liquidation_report(deps, &env), "liquidate")
 }
 
-// query_oracle contains the umee oracle available queries
-fn query_oracle(deps: Deps, _env: Env, msg: UmeeQueryOracle) -> StdResult<Binary> {
+// liquidation_report measures the liquidation from the borrower balances
+// and the contract reward balance saved by execute_liquidate. A reward in
+// the repayment denom is netted with the repayment in the bank balance
+fn liquidation_report(mut deps: DepsMut, env: &Env) -> StdResult<LiquidateMsgResponse> {
+  let snapshot = LIQUIDATION_SNAPSHOT.may_load(deps.storage)?;
+  LIQUIDATION_SNAPSHOT.remove(deps.storage);
+  let (reward_before, reward_after) = match reply_balances(deps.branch(), env)?.as_slice() {
+    [(before, after)] => (before.clone(), *after),
+    balances => {
+      return Err(StdError::generic_err(format!(
+        "expected one balance saved for the liquidation, found {}",
+        balances.len()
+      )))
+    }
+  };
+  let (borrower, borrowed, collateral) =
+    snapshot.ok_or_else(|| StdError::not_found("liquidation snapshot"))?;
+  let balances = query_account_balances(deps.as_ref(), AccountBalancesParams::new(borrower))?;
+  let repaid = borrowed
+    .amount
+    .saturating_sub(balance_of(&balances.borrowed, &borrowed.denom));
+  let liquidated = collateral
+    .amount
+    .saturating_sub(balance_of(&balances.collateral, &collateral.denom));
+  let reward_after = if reward_before.denom == borrowed.denom {
+    reward_after + repaid
+  } else {
+    reward_after
+  };
+
+  Ok(LiquidateMsgResponse {
+    repaid: Coin {
+      amount: repaid,
+      denom: borrowed.denom,
+    },
+    collateral: Coin {
+      amount: liquidated,
+      denom: collateral.denom,
+    },
+    reward: Coin {
+      amount: reward_after.saturating_sub(reward_before.amount),
+      denom: reward_before.denom,
+    },
+  })
+}
+
+// queries doesn't change the state, but it open the state with read permissions
+// it can also query from native modules "bank, stake, custom..."
+// returns an json wrapped data, like:
+// {
+//   "data": ...
+// }
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn query(deps: Deps, env: Env, mut msg: QueryMsg) -> StdResult<Binary> {
+  resolve_query_aliases(deps.storage, &mut msg)?;
   match msg {
-    // consumes the query_chain wrapped by Umee Leverage enums
-    // to clarift the JSON queries to umee leverage native module
-    // example json input:
+    // returns OwnerResponse the current contract owner
+    // expected json input:
     // {
-    //   "umee": {
-    //     "oracle": {
-    //       "exchange_rates": {
-    //         "denom": "uumee"
+    //   "get_owner": {}
+    // }
+    // successful json output:
+    // {
+    //   "data": {
+    //     "owner": "umee1y6xz2ggfc0pcsmyjlekh0j9pxh6hk87ymc9due"
+    //   }
+    // }
+    QueryMsg::GetOwner {} => to_json_binary(&query_owner(deps)?),
+
+    // queries for anything availabe from the blockchain native modules
+    // "iterator, staking, stargate, custom"
+    // example json input for custom module:
+    // {
+    //   "chain": {
+    //     "custom": {
+    //       "assigned_query": uint16,
+    //       "query_func_name": {
+    //         ...
     //       }
     //     }
     //   }
@@ -820,674 +1662,5314 @@ fn query_oracle(deps: Deps, _env: Env, msg: UmeeQueryOracle) -> StdResult<Binary
     // successful json output:
     // {
     //   "data": {
-    //     "exchange_rate_base": "0.0000032"
+    //     ...
     //   }
     // }
-    UmeeQueryOracle::ExchangeRates(exchange_rates_params) => {
+    QueryMsg::Chain(request) => {
+      check_chain_query(deps, &request)?;
+      query_chain(deps, &request)
+    }
+
+    QueryMsg::Umee(umee_query_box) => query_umee(deps, env, *umee_query_box),
+
+    // consumes the query_chain wrapping the JSON to call directly
+    // the ExchangeRates query from the oracle umee native module
+    // expected json input, "exchange_rates" is accepted too:
+    // {
+    //   "get_exchange_rate_base": {
+    //     "denom": "UMEE"
+    //   }
+    // }
+    // successful json output:
+    // {
+    //   "data": {
+    //     "exchange_rates": [
+    //       {
+    //         "denom": "UMEE",
+    //         "amount": "0.0000032"
+    //       }
+    //     ]
+    //   }
+    // }
+    QueryMsg::ExchangeRates(exchange_rates_params) => {
+      exchange_rates_params.validate()?;
       to_json_binary(&query_exchange_rates(deps, exchange_rates_params)?)
     }
-    UmeeQueryOracle::ActiveExchangeRates(active_exchange_rates_params) => to_json_binary(
-      &query_active_exchange_rates(deps, active_exchange_rates_params)?,
-    ),
-    UmeeQueryOracle::FeederDelegation(feeder_delegation_params) => {
-      to_json_binary(&query_feeder_delegation(deps, feeder_delegation_params)?)
+    QueryMsg::RegisteredTokens(registered_tokens_params) => {
+      to_json_binary(&query_registered_tokens(deps, registered_tokens_params)?)
+    }
+    QueryMsg::LeverageParameters(leverage_parameters_params) => to_json_binary(
+      &query_leverage_parameters(deps, leverage_parameters_params)?,
+    ),
+
+    // returns how much of the borrow limit of an address is being used
+    // expected json input:
+    // {
+    //   "borrow_limit_used": {
+    //     "address": "umee1y6xz2ggfc0pcsmyjlekh0j9pxh6hk87ymc9due"
+    //   }
+    // }
+    // successful json output:
+    // {
+    //   "data": {
+    //     "used": "0.73",
+    //     "limit": "1000",
+    //     "available": "270"
+    //   }
+    // }
+    QueryMsg::BorrowLimitUsed { address } => {
+      to_json_binary(&query_borrow_limit_used(&deps, address)?)
+    }
+
+    // returns the display denom, exponent and symbol from the bank metadata
+    // expected json input:
+    // {
+    //   "denom_metadata": {
+    //     "denom": "uumee"
+    //   }
+    // }
+    // successful json output:
+    // {
+    //   "data": {
+    //     "display": "umee",
+    //     "exponent": 6,
+    //     "symbol": "UMEE"
+    //   }
+    // }
+    QueryMsg::DenomMetadata { denom } => to_json_binary(&query_denom_metadata(deps, denom)?),
+
+    // returns if the address can be liquidated and its health factor
+    // expected json input:
+    // {
+    //   "is_liquidatable": {
+    //     "address": "umee1..."
+    //   }
+    // }
+    // successful json output:
+    // {
+    //   "data": {
+    //     "liquidatable": false,
+    //     "health_factor": "1.6"
+    //   }
+    // }
+    QueryMsg::IsLiquidatable { address } => to_json_binary(&query_is_liquidatable(&deps, address)?),
+
+    // returns the USD values locked in the leverage module
+    // expected json input:
+    // {
+    //   "total_value_locked": {}
+    // }
+    // successful json output:
+    // {
+    //   "data": {
+    //     "total_supplied_value": "1200000.5",
+    //     "total_borrowed_value": "400000",
+    //     "total_collateral_value": "900000"
+    //   }
+    // }
+    QueryMsg::TotalValueLocked {} => to_json_binary(&query_total_value_locked(&deps)?),
+
+    // returns the collateral of the address and its bonded part
+    // expected json input:
+    // {
+    //   "collateral_breakdown": {
+    //     "address": "umee1..."
+    //   }
+    // }
+    // successful json output:
+    // {
+    //   "data": {
+    //     "collateral": [{ "denom": "u/uumee", "amount": "900" }],
+    //     "bonded": [{ "denom": "u/uumee", "amount": "600" }]
+    //   }
+    // }
+    QueryMsg::CollateralBreakdown { address } => {
+      to_json_binary(&query_collateral_breakdown(deps, address)?)
+    }
+
+    // returns the total value locked stored by the last RefreshTvlCache
+    // expected json input:
+    // {
+    //   "cached_tvl": {
+    //     "max_age_blocks": 100
+    //   }
+    // }
+    // successful json output:
+    // {
+    //   "data": {
+    //     "height": 12345,
+    //     "tvl": {
+    //       "total_supplied_value": "1200000.5",
+    //       "total_borrowed_value": "400000",
+    //       "total_collateral_value": "900000"
+    //     }
+    //   }
+    // }
+    QueryMsg::CachedTvl { max_age_blocks } => {
+      to_json_binary(&query_cached_tvl(deps, env, max_age_blocks)?)
+    }
+
+    // returns the oracle price and the uToken exchange rate of a denom
+    // expected json input:
+    // {
+    //   "rates": {
+    //     "denom": "uumee"
+    //   }
+    // }
+    // successful json output:
+    // {
+    //   "data": {
+    //     "oracle_price": "0.0125",
+    //     "utoken_exchange_rate": "1.02"
+    //   }
+    // }
+    QueryMsg::Rates { denom, round_to } => {
+      ensure_registered(deps, &denom)?;
+      to_json_binary(&query_rates(deps, denom, round_to)?)
+    }
+    QueryMsg::Raw { query } => query_raw(deps, query),
+
+    // returns the leverage balances and summary of the contract address
+    // expected json input:
+    // {
+    //   "self_position": {}
+    // }
+    // successful json output:
+    // {
+    //   "data": {
+    //     "balances": { "supplied": [], "collateral": [], "borrowed": [] },
+    //     "summary": { "supplied_value": "0", ... }
+    //   }
+    // }
+    QueryMsg::SelfPosition {} => to_json_binary(&query_self_position(deps, env)?),
+    QueryMsg::PositionPnl { address } => to_json_binary(&query_position_pnl(deps, address)?),
+    QueryMsg::FullPosition { address } => to_json_binary(&query_full_position(&deps, address)?),
+    QueryMsg::AssignedIds {} => to_json_binary(&query_assigned_ids()),
+    QueryMsg::ResolveAlias { alias } => to_json_binary(&query_resolve_alias(deps, alias)?),
+  }
+}
+
+// query_resolve_alias reads the denom mapped to the alias
+fn query_resolve_alias(deps: Deps, alias: String) -> StdResult<ResolveAliasResponse> {
+  let denom = DENOM_ALIASES.may_load(deps.storage, alias.clone())?;
+  Ok(ResolveAliasResponse { alias, denom })
+}
+
+// query_umee contains the umee leverage available queries
+fn query_umee(deps: Deps, _env: Env, umee_msg: UmeeQuery) -> StdResult<Binary> {
+  match umee_msg {
+    // consumes the query_chain wrapped by Umee Leverage enums
+    // to clarift the JSON queries to umee leverage native module
+    // example json input:
+    // {
+    //   "umee": {
+    //     "leverage": {
+    //       "query_func_name": {
+    //         ...
+    //       }
+    //     }
+    //   }
+    // }
+    // successful json output:
+    // {
+    //   "data": {
+    //     ...
+    //   }
+    // }
+    UmeeQuery::Leverage(leverage) => query_leverage(deps, _env, leverage),
+
+    // consumes the query_chain wrapped by Umee Leverage enums
+    // to clarift the JSON queries to umee leverage native module
+    // example json input:
+    // {
+    //   "umee": {
+    //     "oracle": {
+    //       "query_func_name": {
+    //         ...
+    //       }
+    //     }
+    //   }
+    // }
+    // successful json output:
+    // {
+    //   "data": {
+    //     ...
+    //   }
+    // }
+    UmeeQuery::Oracle(oracle) => query_oracle(deps, _env, oracle),
+    // incentive
+    UmeeQuery::Incentive(incentive) => query_incentive(deps, _env, incentive),
+    UmeeQuery::Metoken(metoken) => query_metoken(deps, _env, metoken),
+  }
+}
+
+// returns the current owner of the contract from the state
+fn query_owner(deps: Deps) -> StdResult<OwnerResponse> {
+  let state = STATE.load(deps.storage)?;
+  Ok(OwnerResponse { owner: state.owner })
+}
+
+// query_borrow_limit_used computes the used portion of the borrow limit
+// from the account summary, the used ratio is one if the account
+// borrows without any borrow limit
+fn query_borrow_limit_used(
+  querier: &impl LeverageQuerier,
+  address: Addr,
+) -> StdResult<BorrowLimitUsedResponse> {
+  let summary = querier.account_summary(&address)?;
+  let available = summary.borrow_limit.saturating_sub(summary.borrowed_value);
+  Ok(BorrowLimitUsedResponse {
+    used: summary.borrow_limit_used(),
+    limit: summary.borrow_limit,
+    available,
+    limit_display: usd_display(summary.borrow_limit),
+    available_display: usd_display(available),
+  })
+}
+
+// query_is_liquidatable compares the borrowed value of the address
+// against its liquidation threshold, both from the account summary
+fn query_is_liquidatable(
+  querier: &impl LeverageQuerier,
+  address: Addr,
+) -> StdResult<IsLiquidatableResponse> {
+  let summary = querier.account_summary(&address)?;
+  // an account without debt has an infinite health factor, set as None
+  Ok(IsLiquidatableResponse {
+    liquidatable: summary.borrowed_value > summary.liquidation_threshold,
+    health_factor: summary.health_factor(),
+  })
+}
+
+// query_time_to_liquidation estimates the blocks until the borrowed value
+// of the address goes above its liquidation threshold at constant prices.
+// The debt grows linearly at the borrow apy of its markets weighted by the
+// value borrowed in each, so the estimate ignores compounding.
+fn query_time_to_liquidation(
+  querier: &impl LeverageQuerier,
+  time_to_liquidation_params: TimeToLiquidationParams,
+) -> StdResult<TimeToLiquidationResponse> {
+  let TimeToLiquidationParams {
+    address,
+    blocks_per_year,
+  } = time_to_liquidation_params;
+  let never = TimeToLiquidationResponse { blocks: None };
+  let summary = querier.account_summary(&address)?;
+  if summary.borrowed_value.is_zero() || summary.borrowed_value > summary.liquidation_threshold {
+    return Ok(never);
+  }
+
+  let mut borrowed_value = Decimal256::zero();
+  let mut yearly_interest = Decimal256::zero();
+  for coin in querier.account_balances(&address)?.borrowed {
+    let market = querier.market_summary(&coin.denom)?;
+    let value = market_value(&market, Decimal256::from_ratio(coin.amount, 1u128))?;
+    borrowed_value = checked_add(borrowed_value, value, "borrowed value")?;
+    yearly_interest = checked_add(
+      yearly_interest,
+      checked_mul(value, market.borrow_apy, "interest")?,
+      "interest",
+    )?;
+  }
+  if yearly_interest.is_zero() {
+    return Ok(never);
+  }
+
+  // the weighted apy applies to the borrowed value of the account summary
+  let borrow_apy = checked_div(yearly_interest, borrowed_value, "borrow apy")?;
+  let yearly_growth = checked_mul(summary.borrowed_value, borrow_apy, "interest")?;
+  let headroom = summary.liquidation_threshold - summary.borrowed_value;
+  let years = checked_div(headroom, yearly_growth, "years")?;
+  let blocks = checked_mul(
+    years,
+    Decimal256::from_ratio(blocks_per_year, 1u64),
+    "blocks",
+  )?;
+  // the first block strictly above the threshold, a count past u64 is never
+  let blocks = Uint128::try_from(blocks.to_uint_floor())
+    .ok()
+    .and_then(|blocks| u64::try_from(blocks.u128()).ok())
+    .and_then(|blocks| blocks.checked_add(1));
+  Ok(TimeToLiquidationResponse { blocks })
+}
+
+// query_total_value_locked sums the values of the market summary of every
+// registered token, the collateral is held in uTokens and converted to base
+// tokens with the uToken exchange rate before being valued
+fn query_total_value_locked(querier: &impl LeverageQuerier) -> StdResult<TvlResponse> {
+  let registry = querier.registered_tokens()?.registry;
+  if registry.len() > MAX_TVL_MARKETS {
+    return Err(StdError::generic_err(format!(
+      "{} registered tokens, above the {} markets limit of the total value locked",
+      registry.len(),
+      MAX_TVL_MARKETS
+    )));
+  }
+
+  let mut tvl = TvlResponse {
+    total_supplied_value: Decimal256::zero(),
+    total_borrowed_value: Decimal256::zero(),
+    total_collateral_value: Decimal256::zero(),
+    total_supplied_display: String::new(),
+    total_borrowed_display: String::new(),
+    total_collateral_display: String::new(),
+  };
+  for token in registry {
+    let market = querier.market_summary(token.base_denom())?;
+    let collateral = checked_mul(market.collateral, market.utoken_exchange_rate, "collateral")?;
+    tvl.total_supplied_value = checked_add(
+      tvl.total_supplied_value,
+      market_value(&market, market.supplied)?,
+      "supplied value",
+    )?;
+    tvl.total_borrowed_value = checked_add(
+      tvl.total_borrowed_value,
+      market_value(&market, market.borrowed)?,
+      "borrowed value",
+    )?;
+    tvl.total_collateral_value = checked_add(
+      tvl.total_collateral_value,
+      market_value(&market, collateral)?,
+      "collateral value",
+    )?;
+  }
+
+  Ok(with_tvl_display(tvl))
+}
+
+// with_tvl_display sets the display strings of the total values
+fn with_tvl_display(tvl: TvlResponse) -> TvlResponse {
+  TvlResponse {
+    total_supplied_display: usd_display(tvl.total_supplied_value),
+    total_borrowed_display: usd_display(tvl.total_borrowed_value),
+    total_collateral_display: usd_display(tvl.total_collateral_value),
+    ..tvl
+  }
+}
+
+// usd_display renders a USD value of a derived response for display
+fn usd_display(value: Decimal256) -> String {
+  format_usd(value, USD_DISPLAY_PLACES)
+}
+
+// market_value returns the USD value of an amount of base tokens
+// of the market, from its oracle price and exponent
+fn market_value(market: &MarketSummaryResponse, amount: Decimal256) -> StdResult<Decimal256> {
+  let value = checked_mul(amount, market.oracle_price, &market.symbol_denom)?;
+  checked_div(
+    value,
+    exponent_factor(market.exponent)?,
+    &market.symbol_denom,
+  )
+}
+
+// exponent_factor returns 10^exponent, the base tokens of a display token.
+fn exponent_factor(exponent: u32) -> StdResult<Decimal256> {
+  10u128
+    .checked_pow(exponent)
+    .map(|factor| Decimal256::from_ratio(factor, 1u128))
+    .ok_or_else(|| StdError::generic_err(format!("exponent {} overflows", exponent)))
+}
+
+// checked_add, checked_mul and checked_div fail with the context of the
+// operation instead of panicking and aborting the whole query.
+fn checked_add(a: Decimal256, b: Decimal256, context: &str) -> StdResult<Decimal256> {
+  a.checked_add(b)
+    .map_err(|err| StdError::generic_err(format!("{}: {}", context, err)))
+}
+
+fn checked_mul(a: Decimal256, b: Decimal256, context: &str) -> StdResult<Decimal256> {
+  a.checked_mul(b)
+    .map_err(|err| StdError::generic_err(format!("{}: {}", context, err)))
+}
+
+fn checked_div(a: Decimal256, b: Decimal256, context: &str) -> StdResult<Decimal256> {
+  a.checked_div(b)
+    .map_err(|err| StdError::generic_err(format!("{}: {}", context, err)))
+}
+
+// query_rates returns the uToken exchange rate from the market summary
+// and the oracle price of its symbol denom. A summary without the exchange
+// rate falls back to the rate computed from the supplied amount and the
+// bank supply of the uToken.
+fn query_rates(deps: Deps, denom: String, round_to: Option<u32>) -> StdResult<RatesResponse> {
+  let summary = query_market_summary(deps, MarketSummaryParams::new(&denom))?;
+  let utoken_exchange_rate = if summary.utoken_exchange_rate.is_zero() {
+    let market_size = decimal_to_uint128(summary.supplied)?;
+    let utoken_supply = deps.querier.query_supply(utoken_denom(&denom))?.amount;
+    Decimal256::from(compute_exchange_rate(market_size, utoken_supply))
+  } else {
+    summary.utoken_exchange_rate
+  };
+  let symbol = summary.symbol_denom;
+  let oracle_price = query_exchange_rates(deps, ExchangeRatesParams::new(symbol.clone()))?
+    .exchange_rates
+    .into_iter()
+    .find(|rate| rate.denom.eq_ignore_ascii_case(&symbol))
+    .ok_or_else(|| StdError::generic_err(format!("no oracle price for {}", symbol)))?
+    .amount;
+
+  let rates = RatesResponse {
+    oracle_price,
+    utoken_exchange_rate,
+  };
+  match round_to {
+    None => Ok(rates),
+    Some(places) => Ok(RatesResponse {
+      oracle_price: round_decimal(rates.oracle_price, places)?,
+      utoken_exchange_rate: round_decimal(rates.utoken_exchange_rate, places)?,
+    }),
+  }
+}
+
+// query_self_position returns the AccountBalances and AccountSummary of the
+// contract address.
+fn query_self_position(deps: Deps, env: Env) -> StdResult<SelfPositionResponse> {
+  let address = env.contract.address;
+  Ok(SelfPositionResponse {
+    balances: query_account_balances(deps, AccountBalancesParams::new(address.clone()))?,
+    summary: query_account_summary(deps, AccountSummaryParams::new(address))?,
+  })
+}
+
+// query_full_position composes the account balances of the address with
+// its account summary
+fn query_full_position(
+  querier: &impl LeverageQuerier,
+  address: Addr,
+) -> StdResult<FullPositionResponse> {
+  let balances = querier.account_balances(&address)?;
+  let summary = querier.account_summary(&address)?;
+
+  Ok(FullPositionResponse {
+    supplied: balances.supplied,
+    collateral: balances.collateral,
+    borrowed: balances.borrowed,
+    health_factor: summary.health_factor(),
+    supplied_value: summary.supplied_value,
+    collateral_value: summary.collateral_value,
+    borrowed_value: summary.borrowed_value,
+    supplied_display: usd_display(summary.supplied_value),
+    collateral_display: usd_display(summary.collateral_value),
+    borrowed_display: usd_display(summary.borrowed_value),
+  })
+}
+
+// net_value returns the supplied value of the address minus its borrowed
+// value, an account with more debt than supply is worth zero
+fn net_value(deps: Deps, address: &Addr) -> StdResult<Decimal256> {
+  let summary = query_account_summary(deps, AccountSummaryParams::new(address.clone()))?;
+  Ok(
+    summary
+      .supplied_value
+      .saturating_sub(summary.borrowed_value),
+  )
+}
+
+// query_position_pnl compares the current net value of the address with
+// the one stored by its last snapshot
+fn query_position_pnl(deps: Deps, address: Addr) -> StdResult<PositionPnlResponse> {
+  let (snapshot_height, snapshot_value) = POSITION_SNAPSHOTS
+    .may_load(deps.storage, &address)?
+    .ok_or_else(|| StdError::generic_err(format!("no position snapshot for {}", address)))?;
+  let current_value = net_value(deps, &address)?;
+  let profit = current_value.saturating_sub(snapshot_value);
+  let loss = snapshot_value.saturating_sub(current_value);
+
+  Ok(PositionPnlResponse {
+    snapshot_height,
+    snapshot_value,
+    current_value,
+    profit,
+    loss,
+    profit_display: usd_display(profit),
+    loss_display: usd_display(loss),
+  })
+}
+
+// query_assigned_ids lists the registry of the queries and msgs assigned
+// by the types crate, it does not depend on the chain
+fn query_assigned_ids() -> AssignedIdsResponse {
+  AssignedIdsResponse {
+    queries: ASSIGNED_QUERIES.iter().map(|q| q.to_string()).collect(),
+    messages: MsgTypes::ALL
+      .iter()
+      .map(|m| (m.clone(), m.name().to_string()))
+      .collect(),
+  }
+}
+
+// query_raw returns the bytes answered by the chain for a native query as
+// they are, the composed queries have no single chain response to return.
+// Like the Chain query, it only passes through the allowed chain queries
+fn query_raw(deps: Deps, query: UmeeQuery) -> StdResult<Binary> {
+  let native = query
+    .native()
+    .ok_or_else(|| StdError::generic_err("raw only supports the native umee queries"))?;
+  let request = QueryRequest::Custom(native);
+  check_chain_query(deps, &request)?;
+  query_chain(deps, &request)
+}
+
+// query_cached_tvl returns the stored total value locked if it was computed
+// at most max_age_blocks ago.
+fn query_cached_tvl(deps: Deps, env: Env, max_age_blocks: u64) -> StdResult<CachedTvlResponse> {
+  let cache = TVL_CACHE
+    .may_load(deps.storage)?
+    .ok_or_else(|| StdError::generic_err("the tvl cache was never refreshed"))?;
+  let age = env.block.height.saturating_sub(cache.height);
+  if age > max_age_blocks {
+    return Err(StdError::generic_err(format!(
+      "the tvl cache is {} blocks old, above the {} blocks max age",
+      age, max_age_blocks
+    )));
+  }
+  Ok(CachedTvlResponse {
+    tvl: with_tvl_display(cache.tvl),
+    ..cache
+  })
+}
+
+// query_collateral_breakdown composes the leverage account balances
+// with the incentive account bonds of the address
+fn query_collateral_breakdown(deps: Deps, address: Addr) -> StdResult<CollateralBreakdownResponse> {
+  let bonded = query_account_bonds(
+    deps,
+    AccountBondsParams {
+      address: address.to_string(),
+    },
+  )?
+  .bonded;
+  let collateral = query_account_balances(deps, AccountBalancesParams::new(address))?.collateral;
+
+  Ok(CollateralBreakdownResponse { collateral, bonded })
+}
+
+// query_denom_metadata queries the bank module metadata of the denom, the
+// exponent is taken from the denom unit of the display denom
+fn query_denom_metadata(deps: Deps, denom: String) -> StdResult<DenomMetadataResponse> {
+  let request = QueryRequest::Bank(BankQuery::DenomMetadata { denom });
+  let metadata = query_chain_json::<BankDenomMetadataResponse>(deps, &request)?.metadata;
+  let exponent = metadata
+    .denom_units
+    .iter()
+    .find(|unit| unit.denom == metadata.display)
+    .map(|unit| unit.exponent)
+    .unwrap_or_default();
+
+  Ok(DenomMetadataResponse {
+    display: metadata.display,
+    exponent,
+    symbol: metadata.symbol,
+  })
+}
+
+// check_chain_query rejects the Chain requests whose kind is not allowed by
+// the contract state. Only the requests coming from callers are checked, the
+// ones built by the composed queries always go through query_chain
+fn check_chain_query(deps: Deps, request: &QueryRequest<StructUmeeQuery>) -> StdResult<()> {
+  let kind = match request {
+    QueryRequest::Custom(_) => Some(ChainQueryKind::Custom),
+    QueryRequest::Bank(_) => Some(ChainQueryKind::Bank),
+    QueryRequest::Staking(_) => Some(ChainQueryKind::Staking),
+    QueryRequest::Stargate { .. } => Some(ChainQueryKind::Stargate),
+    _ => None,
+  };
+  let allowed = STATE.load(deps.storage)?.chain_queries;
+  match kind {
+    Some(kind) if allowed.contains(&kind) => Ok(()),
+    _ => Err(StdError::generic_err(format!(
+      "chain query not allowed, the contract only passes through {:?}",
+      allowed
+    ))),
+  }
+}
+
+// query_chain_json queries for any availabe query in the chain native
+// modules and decodes its json response
+fn query_chain_json<T: DeserializeOwned>(
+  deps: Deps,
+  request: &QueryRequest<StructUmeeQuery>,
+) -> StdResult<T> {
+  decode_response(&query_chain(deps, request)?)
+}
+
+// decode_response decodes the json response of a native module, the parse
+// errors carry the response length to tell truncated responses, usually
+// cut at a round size, from malformed ones
+fn decode_response<T: DeserializeOwned>(binary: &Binary) -> StdResult<T> {
+  from_json::<T>(binary).map_err(|err| match err {
+    StdError::ParseErr {
+      target_type, msg, ..
+    } => StdError::parse_err(
+      target_type,
+      format!("{} ({} bytes response)", msg, binary.len()),
+    ),
+    err => err,
+  })
+}
+
+// query_trace describes a native query by its assigned id with the denom
+// and the address of its params, if any
+#[cfg(any(test, feature = "debug-queries"))]
+fn query_trace(query: &StructUmeeQuery) -> Option<String> {
+  #[derive(serde::Deserialize)]
+  struct TraceParams {
+    denom: Option<String>,
+    address: Option<String>,
+  }
+
+  let fields: std::collections::BTreeMap<String, Option<TraceParams>> =
+    from_json(to_json_binary(query).ok()?).ok()?;
+  let (assigned, params) = fields
+    .into_iter()
+    .find_map(|(name, params)| params.map(|params| (name, params)))?;
+  Some(format!(
+    "umee query {} denom={} address={}",
+    assigned,
+    params.denom.unwrap_or_default(),
+    params.address.unwrap_or_default()
+  ))
+}
+
+// query_chain queries for any availabe query in the chain native modules
+fn query_chain(deps: Deps, request: &QueryRequest<StructUmeeQuery>) -> StdResult<Binary> {
+  #[cfg(feature = "debug-queries")]
+  if let QueryRequest::Custom(query) = request {
+    if let Some(trace) = query_trace(query) {
+      deps.api.debug(&trace);
+    }
+  }
+  let raw = to_json_vec(request).map_err(|serialize_err| {
+    StdError::generic_err(format!("Serializing QueryRequest: {}", serialize_err))
+  })?;
+  match deps.querier.raw_query(&raw) {
+    SystemResult::Err(system_err) => Err(StdError::generic_err(format!(
+      "Querier system error: {}",
+      system_err
+    ))),
+    SystemResult::Ok(ContractResult::Err(contract_err)) => Err(StdError::generic_err(format!(
+      "Querier contract error: {}",
+      contract_err
+    ))),
+    SystemResult::Ok(ContractResult::Ok(value)) => Ok(value),
+  }
+}
+
+// query_leverage contains the umee leverage available queries
+fn query_leverage(deps: Deps, _env: Env, msg: UmeeQueryLeverage) -> StdResult<Binary> {
+  msg.validate(deps.api)?;
+  match msg {
+    UmeeQueryLeverage::LeverageParameters(leverage_parameters_params) => to_json_binary(
+      &query_leverage_parameters(deps, leverage_parameters_params)?,
+    ),
+    UmeeQueryLeverage::RegisteredTokens(registered_tokens_params) => {
+      to_json_binary(&query_registered_tokens(deps, registered_tokens_params)?)
+    }
+    UmeeQueryLeverage::MarketSummary(market_summary_params) => {
+      ensure_registered(deps, &market_summary_params.denom)?;
+      to_json_binary(&query_market_summary(deps, market_summary_params)?)
+    }
+    UmeeQueryLeverage::AccountBalances(account_balances_params) => {
+      to_json_binary(&query_account_balances(deps, account_balances_params)?)
+    }
+    UmeeQueryLeverage::AccountSummary(account_summary_params) => {
+      to_json_binary(&query_account_summary(deps, account_summary_params)?)
+    }
+    UmeeQueryLeverage::LiquidationTargets(liquidation_targets_params) => to_json_binary(
+      &query_liquidation_targets(deps, liquidation_targets_params)?,
+    ),
+    UmeeQueryLeverage::BadDebts(bad_debts_params) => {
+      to_json_binary(&query_bad_debts(deps, bad_debts_params)?)
+    }
+    UmeeQueryLeverage::MaxWithdraw(max_withdraw_params) => {
+      to_json_binary(&query_max_withdraw(deps, max_withdraw_params)?)
+    }
+    UmeeQueryLeverage::MaxBorrow(max_borrow_params) => {
+      to_json_binary(&query_max_borrow(deps, max_borrow_params)?)
+    }
+    UmeeQueryLeverage::AvailableWithdraw(available_withdraw_params) => {
+      ensure_registered(deps, &available_withdraw_params.denom)?;
+      to_json_binary(&query_available_withdraw(deps, available_withdraw_params)?)
+    }
+    UmeeQueryLeverage::LiquidatableBorrowers(liquidatable_borrowers_params) => to_json_binary(
+      &query_liquidatable_borrowers(deps, liquidatable_borrowers_params)?,
+    ),
+    UmeeQueryLeverage::SpecialBorrowLimit(special_borrow_limit_params) => to_json_binary(
+      &query_special_borrow_limit(deps, special_borrow_limit_params)?,
+    ),
+    UmeeQueryLeverage::InterestRateModel(interest_rate_model_params) => to_json_binary(
+      &query_interest_rate_model(deps, interest_rate_model_params)?,
+    ),
+    UmeeQueryLeverage::SimulateLiquidation(simulate_liquidation_params) => to_json_binary(
+      &query_simulate_liquidation(deps, simulate_liquidation_params)?,
+    ),
+    UmeeQueryLeverage::CapsUsage(caps_usage_params) => {
+      to_json_binary(&query_caps_usage(deps, caps_usage_params)?)
+    }
+    UmeeQueryLeverage::BatchAccountSummary(batch_account_summary_params) => to_json_binary(
+      &query_batch_account_summary(deps, batch_account_summary_params)?,
+    ),
+    UmeeQueryLeverage::AllReserves(all_reserves_params) => {
+      to_json_binary(&query_all_reserves(&deps, all_reserves_params)?)
+    }
+    UmeeQueryLeverage::MaxCollateralize(max_collateralize_params) => {
+      to_json_binary(&query_max_collateralize(deps, max_collateralize_params)?)
+    }
+    UmeeQueryLeverage::UTokenSupply(utoken_supply_params) => {
+      ensure_registered(deps, &utoken_supply_params.denom)?;
+      to_json_binary(&query_utoken_supply(deps, utoken_supply_params)?)
+    }
+    UmeeQueryLeverage::TopSupplyMarkets(top_supply_markets_params) => {
+      to_json_binary(&query_top_supply_markets(deps, top_supply_markets_params)?)
+    }
+    UmeeQueryLeverage::DenomStatus(denom_status_params) => {
+      to_json_binary(&query_denom_status(deps, denom_status_params)?)
+    }
+    UmeeQueryLeverage::MarketEnabled(market_enabled_params) => {
+      to_json_binary(&query_market_enabled(deps, market_enabled_params)?)
+    }
+    UmeeQueryLeverage::BorrowFactors(borrow_factors_params) => {
+      to_json_binary(&query_borrow_factors(deps, borrow_factors_params)?)
+    }
+    UmeeQueryLeverage::MarketRow(market_row_params) => {
+      to_json_binary(&query_market_row(deps, market_row_params)?)
+    }
+    UmeeQueryLeverage::ReserveInfo(reserve_info_params) => {
+      to_json_binary(&query_reserve_info(deps, reserve_info_params)?)
+    }
+    UmeeQueryLeverage::CollateralValueAtPrice(collateral_value_at_price_params) => to_json_binary(
+      &query_collateral_value_at_price(deps, collateral_value_at_price_params)?,
+    ),
+    UmeeQueryLeverage::LiquidationPrice(liquidation_price_params) => {
+      to_json_binary(&query_liquidation_price(deps, liquidation_price_params)?)
+    }
+    UmeeQueryLeverage::TimeToLiquidation(time_to_liquidation_params) => to_json_binary(
+      &query_time_to_liquidation(&deps, time_to_liquidation_params)?,
+    ),
+    UmeeQueryLeverage::BadDebtTotal(bad_debt_total_params) => {
+      to_json_binary(&query_bad_debt_total(deps, bad_debt_total_params)?)
+    }
+    UmeeQueryLeverage::SimulateSupply(simulate_supply_params) => {
+      ensure_registered(deps, &simulate_supply_params.denom)?;
+      to_json_binary(&query_simulate_supply(deps, simulate_supply_params)?)
+    }
+  }
+}
+
+// query_utoken_supply returns the bank supply of the uToken of the denom.
+fn query_utoken_supply(
+  deps: Deps,
+  utoken_supply_params: UTokenSupplyParams,
+) -> StdResult<UTokenSupplyResponse> {
+  let utoken_supply = deps
+    .querier
+    .query_supply(utoken_denom(&utoken_supply_params.denom))?;
+  Ok(UTokenSupplyResponse { utoken_supply })
+}
+
+// query_denom_status reads the flags of the registered token of the denom,
+// an unregistered denom isn't an error.
+fn query_denom_status(
+  deps: Deps,
+  denom_status_params: DenomStatusParams,
+) -> StdResult<DenomStatusResponse> {
+  let registry = query_registered_tokens(deps, RegisteredTokensParams {})?;
+  let token = match registry.get(&denom_status_params.denom) {
+    Some(token) => token,
+    None => return Ok(DenomStatusResponse::default()),
+  };
+
+  Ok(DenomStatusResponse {
+    registered: true,
+    collateral_enabled: token.collateral_enabled(),
+    borrow_enabled: token.borrow_enabled(),
+    blacklisted: token.blacklisted(),
+  })
+}
+
+// query_market_enabled reads the enable flags of the registered token of
+// the denom, the flags of a blacklisted token are all off.
+fn query_market_enabled(
+  deps: Deps,
+  market_enabled_params: MarketEnabledParams,
+) -> StdResult<MarketEnabledResponse> {
+  let denom = market_enabled_params.denom;
+  let registry = query_registered_tokens(deps, RegisteredTokensParams {})?;
+  let token = registry
+    .get(&denom)
+    .ok_or_else(|| StdError::generic_err(format!("{} is not a registered token", denom)))?;
+
+  Ok(MarketEnabledResponse {
+    supply_enabled: token.supply_enabled() && !token.blacklisted(),
+    borrow_enabled: token.borrow_enabled() && !token.blacklisted(),
+    collateral_enabled: token.collateral_enabled(),
+  })
+}
+
+// query_borrow_factors reads the collateral weight and the borrow factor
+// of the registered token of the denom.
+fn query_borrow_factors(
+  deps: Deps,
+  borrow_factors_params: BorrowFactorsParams,
+) -> StdResult<BorrowFactorsResponse> {
+  let denom = borrow_factors_params.denom;
+  let registry = query_registered_tokens(deps, RegisteredTokensParams {})?;
+  let token = registry
+    .get(&denom)
+    .ok_or_else(|| StdError::generic_err(format!("{} is not a registered token", denom)))?;
+
+  Ok(BorrowFactorsResponse {
+    collateral_weight: token.collateral_weight(),
+    borrow_factor: token.borrow_factor(),
+  })
+}
+
+// query_market_row checks the denom is registered and reads the row of
+// its market out of a single market summary.
+fn query_market_row(
+  deps: Deps,
+  market_row_params: MarketRowParams,
+) -> StdResult<MarketRowResponse> {
+  let denom = market_row_params.denom;
+  ensure_registered(deps, &denom)?;
+  let market = query_market_summary(deps, MarketSummaryParams::new(&denom))?;
+
+  Ok(MarketRowResponse {
+    utilization: market.utilization(),
+    denom,
+    symbol_denom: market.symbol_denom,
+    supply_apy: market.supply_apy,
+    borrow_apy: market.borrow_apy,
+    total_supplied: market.supplied,
+    total_borrowed: market.borrowed,
+    oracle_price: market.oracle_price,
+  })
+}
+
+// query_reserve_info reads the reserve factor of the registered token of
+// the denom and values the reserves of its market at the oracle price.
+fn query_reserve_info(
+  deps: Deps,
+  reserve_info_params: ReserveInfoParams,
+) -> StdResult<ReserveInfoResponse> {
+  let denom = reserve_info_params.denom;
+  let registry = query_registered_tokens(deps, RegisteredTokensParams {})?;
+  let token = registry
+    .get(&denom)
+    .ok_or_else(|| StdError::generic_err(format!("{} is not a registered token", denom)))?;
+  let market = query_market_summary(deps, MarketSummaryParams::new(&denom))?;
+
+  Ok(ReserveInfoResponse {
+    reserve_factor: token.reserve_factor(),
+    reserves: Coin::new(decimal_to_uint128(market.reserved)?.u128(), &denom),
+    reserves_value: market_value(&market, market.reserved)?,
+  })
+}
+
+// query_collateral_value_at_price values each collateral of the address
+// like query_total_value_locked, at its overridden price if any. An
+// override matches the base denom or, ignoring the case, the symbol denom.
+fn query_collateral_value_at_price(
+  deps: Deps,
+  collateral_value_at_price_params: CollateralValueAtPriceParams,
+) -> StdResult<CollateralValueAtPriceResponse> {
+  let CollateralValueAtPriceParams {
+    address,
+    price_overrides,
+  } = collateral_value_at_price_params;
+  let balances = query_account_balances(deps, AccountBalancesParams::new(address))?;
+
+  let mut collateral_value = Decimal256::zero();
+  for coin in balances.collateral {
+    let denom = coin
+      .denom
+      .strip_prefix(UTOKEN_PREFIX)
+      .unwrap_or(&coin.denom);
+    let mut market = query_market_summary(deps, MarketSummaryParams::new(denom))?;
+    if let Some(price) = price_overrides
+      .iter()
+      .find(|price| price.denom == denom || price.denom.eq_ignore_ascii_case(&market.symbol_denom))
+    {
+      market.oracle_price = price.amount;
+    }
+    let collateral = checked_mul(
+      Decimal256::from_ratio(coin.amount, 1u128),
+      market.utoken_exchange_rate,
+      "collateral",
+    )?;
+    collateral_value = checked_add(
+      collateral_value,
+      market_value(&market, collateral)?,
+      "collateral value",
+    )?;
+  }
+
+  Ok(CollateralValueAtPriceResponse { collateral_value })
+}
+
+// query_liquidation_price solves the price of the collateral denom at which
+// the borrowed value of the address reaches its liquidation threshold. The
+// collateral adds its liquidation threshold portion to the threshold and a
+// debt in the same denom adds to the borrowed value, both move with the
+// price, the rest of the account summary is kept as is.
+fn query_liquidation_price(
+  deps: Deps,
+  liquidation_price_params: LiquidationPriceParams,
+) -> StdResult<LiquidationPriceResponse> {
+  let LiquidationPriceParams {
+    address,
+    collateral_denom: denom,
+  } = liquidation_price_params;
+  let registry = query_registered_tokens(deps, RegisteredTokensParams {})?;
+  let token = registry
+    .get(&denom)
+    .ok_or_else(|| StdError::generic_err(format!("{} is not a registered token", denom)))?;
+  let summary = query_account_summary(deps, AccountSummaryParams::new(address.clone()))?;
+  let balances = query_account_balances(deps, AccountBalancesParams::new(address))?;
+  let market = query_market_summary(deps, MarketSummaryParams::new(&denom))?;
+
+  let utokens = balance_of(&balances.collateral, &utoken_denom(&denom));
+  let collateral = checked_mul(
+    Decimal256::from_ratio(utokens, 1u128),
+    market.utoken_exchange_rate,
+    "collateral",
+  )?;
+  let exponent = exponent_factor(market.exponent)?;
+  // the threshold and borrowed value added by each USD of the price
+  let threshold_weight = checked_div(
+    checked_mul(
+      collateral,
+      Decimal256::from(token.liquidation_threshold()),
+      "liquidation threshold",
+    )?,
+    exponent,
+    "liquidation threshold",
+  )?;
+  let borrowed = balance_of(&balances.borrowed, &denom);
+  let borrowed_weight = checked_div(
+    Decimal256::from_ratio(borrowed, 1u128),
+    exponent,
+    "borrowed",
+  )?;
+  if threshold_weight <= borrowed_weight {
+    return Err(StdError::generic_err(format!(
+      "the {} debt outweighs its collateral, a lower price can't liquidate the position",
+      denom
+    )));
+  }
+
+  // borrowed + threshold_weight * price - threshold - borrowed_weight * price
+  // is the amount the price has to remove from the threshold
+  let price = market.oracle_price;
+  let above = checked_add(
+    summary.borrowed_value,
+    checked_mul(threshold_weight, price, "liquidation threshold")?,
+    "borrowed value",
+  )?;
+  let below = checked_add(
+    summary.liquidation_threshold,
+    checked_mul(borrowed_weight, price, "borrowed value")?,
+    "liquidation threshold",
+  )?;
+  let price = checked_div(
+    above.saturating_sub(below),
+    threshold_weight - borrowed_weight,
+    "liquidation price",
+  )?;
+
+  Ok(LiquidationPriceResponse { price })
+}
+
+// query_top_supply_markets sorts the markets of up to MAX_TVL_MARKETS
+// registered tokens by supply APY and keeps at most MAX_PAGE_LIMIT.
+fn query_top_supply_markets(
+  deps: Deps,
+  top_supply_markets_params: TopSupplyMarketsParams,
+) -> StdResult<TopSupplyMarketsResponse> {
+  let limit = top_supply_markets_params.limit.min(MAX_PAGE_LIMIT) as usize;
+  let registry = query_registered_tokens(deps, RegisteredTokensParams {})?.registry;
+  if registry.len() > MAX_TVL_MARKETS {
+    return Err(StdError::generic_err(format!(
+      "{} registered tokens, above the {} markets limit of the top supply markets",
+      registry.len(),
+      MAX_TVL_MARKETS
+    )));
+  }
+
+  let mut markets = vec![];
+  for token in registry {
+    let denom = token.base_denom().to_string();
+    let market = query_market_summary(deps, MarketSummaryParams::new(&denom))?;
+    markets.push((denom, market.supply_apy));
+  }
+  markets.sort_by(|(a_denom, a_apy), (b_denom, b_apy)| {
+    b_apy.cmp(a_apy).then_with(|| a_denom.cmp(b_denom))
+  });
+  markets.truncate(limit);
+
+  Ok(TopSupplyMarketsResponse { markets })
+}
+
+// query_bad_debt_total sums the borrowed amount of up to MAX_BATCH_ACCOUNTS
+// bad debt positions by denom and values each denom total.
+fn query_bad_debt_total(deps: Deps, _: BadDebtTotalParams) -> StdResult<BadDebtTotalResponse> {
+  let targets = query_bad_debts(deps, BadDebtsParams {})?.targets;
+  if targets.len() > MAX_BATCH_ACCOUNTS {
+    return Err(StdError::generic_err(format!(
+      "{} bad debts, above the {} accounts limit of the total",
+      targets.len(),
+      MAX_BATCH_ACCOUNTS
+    )));
+  }
+
+  let mut by_denom = vec![];
+  for target in targets {
+    let address = Addr::unchecked(target.address());
+    let borrowed = query_account_balances(deps, AccountBalancesParams::new(address))?.borrowed;
+    let debt = Coin {
+      amount: balance_of(&borrowed, target.denom()),
+      denom: target.denom().to_string(),
+    };
+    by_denom = merge_coins(&by_denom, &[debt])?;
+  }
+
+  let mut total_value = Decimal256::zero();
+  for debt in &by_denom {
+    let market = query_market_summary(deps, MarketSummaryParams::new(&debt.denom))?;
+    let value = market_value(&market, Decimal256::from_ratio(debt.amount, 1u128))?;
+    total_value = checked_add(total_value, value, "bad debt value")?;
+  }
+
+  Ok(BadDebtTotalResponse {
+    total_value,
+    by_denom,
+  })
+}
+
+// query_simulate_supply converts the supplied amount to uTokens with the
+// exchange rate of the market summary.
+fn query_simulate_supply(
+  deps: Deps,
+  simulate_supply_params: SimulateSupplyParams,
+) -> StdResult<SimulateSupplyResponse> {
+  let denom = simulate_supply_params.denom;
+  let market_summary = query_market_summary(
+    deps,
+    MarketSummaryParams {
+      denom: denom.clone(),
+    },
+  )?;
+
+  Ok(SimulateSupplyResponse {
+    utokens: Coin {
+      amount: to_utoken_amount(
+        simulate_supply_params.amount,
+        market_summary.utoken_exchange_rate,
+      )?,
+      denom: utoken_denom(&denom),
+    },
+  })
+}
+
+// query_max_collateralize returns the uTokens held by the address, the
+// module escrows the collateral so the bank balance is what is left to
+// collateralize.
+fn query_max_collateralize(
+  deps: Deps,
+  max_collateralize_params: MaxCollateralizeParams,
+) -> StdResult<MaxCollateralizeResponse> {
+  let address = max_collateralize_params.address;
+  let denom = max_collateralize_params.denom;
+  let udenom = if denom.starts_with(UTOKEN_PREFIX) {
+    denom
+  } else {
+    utoken_denom(&denom)
+  };
+  let balances = query_account_balances(deps, AccountBalancesParams::new(address.clone()))?;
+  let collateralized = balance_of(&balances.collateral, &udenom);
+  let available = deps.querier.query_balance(&address, &udenom)?;
+
+  Ok(MaxCollateralizeResponse {
+    available,
+    collateralized: Coin::new(collateralized.u128(), &udenom),
+  })
+}
+
+// query_all_reserves returns the reserves of up to MAX_TVL_MARKETS
+// registered tokens, one market summary query per token.
+fn query_all_reserves(
+  querier: &impl LeverageQuerier,
+  _: AllReservesParams,
+) -> StdResult<AllReservesResponse> {
+  let registry = querier.registered_tokens()?.registry;
+  if registry.len() > MAX_TVL_MARKETS {
+    return Err(StdError::generic_err(format!(
+      "{} registered tokens, above the {} markets limit of the reserves",
+      registry.len(),
+      MAX_TVL_MARKETS
+    )));
+  }
+
+  let mut reserves = vec![];
+  for token in registry {
+    let market = querier.market_summary(token.base_denom())?;
+    let amount = decimal_to_uint128(market.reserved)?;
+    if !amount.is_zero() {
+      reserves.push(Coin::new(amount.u128(), token.base_denom()));
+    }
+  }
+
+  Ok(AllReservesResponse { reserves })
+}
+
+// ensure_registered fails with a clear error when the denom is not a
+// registered token, unless the check was disabled at instantiation.
+fn ensure_registered(deps: Deps, denom: &str) -> StdResult<()> {
+  let check_denoms = STATE
+    .may_load(deps.storage)?
+    .map_or_else(default_check_denoms, |state| state.check_denoms);
+  if !check_denoms {
+    return Ok(());
+  }
+
+  let registry = query_registered_tokens(deps, RegisteredTokensParams {})?;
+  if registry.get(denom).is_some() {
+    return Ok(());
+  }
+  Err(StdError::generic_err(
+    ContractError::CustomError {
+      val: format!("denom {} not registered", denom),
+    }
+    .to_string(),
+  ))
+}
+
+// query_batch_account_summary returns the account summaries of up to
+// MAX_BATCH_ACCOUNTS addresses, the ones without any position are skipped.
+fn query_batch_account_summary(
+  deps: Deps,
+  batch_account_summary_params: BatchAccountSummaryParams,
+) -> StdResult<BatchAccountSummaryResponse> {
+  let addresses = batch_account_summary_params.addresses;
+  if addresses.len() > MAX_BATCH_ACCOUNTS {
+    return Err(StdError::generic_err(format!(
+      "{} addresses, above the {} addresses limit of a batch account summary",
+      addresses.len(),
+      MAX_BATCH_ACCOUNTS
+    )));
+  }
+
+  let mut summaries = vec![];
+  let mut skipped = vec![];
+  for address in addresses {
+    let summary = query_account_summary(deps, AccountSummaryParams::new(address.clone()))?;
+    if summary == AccountSummaryResponse::default() {
+      skipped.push(address);
+    } else {
+      summaries.push((address, summary));
+    }
+  }
+
+  Ok(BatchAccountSummaryResponse { summaries, skipped })
+}
+
+// query_caps_usage compares the supplied and borrowed amounts of the market
+// with the caps set by the registry settings of the token
+fn query_caps_usage(
+  deps: Deps,
+  caps_usage_params: CapsUsageParams,
+) -> StdResult<CapsUsageResponse> {
+  let denom = caps_usage_params.denom;
+  let registry = query_registered_tokens(deps, RegisteredTokensParams {})?;
+  let token = registry
+    .get(&denom)
+    .ok_or_else(|| StdError::generic_err(format!("{} is not a registered token", denom)))?;
+  let market = query_market_summary(deps, MarketSummaryParams::new(denom.clone()))?;
+
+  let supply_cap = Decimal256::from(token.max_supply());
+  let borrow_cap = checked_mul(
+    market.supplied,
+    Decimal256::from(token.max_supply_utilization()),
+    "borrow cap",
+  )?;
+
+  Ok(CapsUsageResponse {
+    supply_used: usage_ratio(market.supplied, supply_cap)?,
+    supply_cap: decimal_to_uint128(supply_cap)?,
+    borrow_used: usage_ratio(market.borrowed, borrow_cap)?,
+    borrow_cap: decimal_to_uint128(borrow_cap)?,
+  })
+}
+
+// usage_ratio returns the used portion of a cap, zero when there isn't any cap
+fn usage_ratio(amount: Decimal256, cap: Decimal256) -> StdResult<Decimal> {
+  if cap.is_zero() {
+    return Ok(Decimal::zero());
+  }
+  Decimal::try_from(checked_div(amount, cap, "usage")?)
+    .map_err(|err| StdError::generic_err(err.to_string()))
+}
+
+// query_simulate_liquidation values the repayment at the oracle price and
+// converts it, with the liquidation incentive of the reward token, to the
+// reward denom. Both sides are capped by the borrower debt and collateral
+fn query_simulate_liquidation(
+  deps: Deps,
+  simulate_liquidation_params: SimulateLiquidationParams,
+) -> StdResult<SimulateLiquidationResponse> {
+  let repay_denom = simulate_liquidation_params.repay.denom;
+  let reward_denom = simulate_liquidation_params.reward_denom;
+  let reward_base_denom = reward_denom
+    .strip_prefix(UTOKEN_PREFIX)
+    .unwrap_or(&reward_denom)
+    .to_string();
+
+  let balances = query_account_balances(
+    deps,
+    AccountBalancesParams::new(simulate_liquidation_params.borrower),
+  )?;
+  let repay_amount = simulate_liquidation_params
+    .repay
+    .amount
+    .min(balance_of(&balances.borrowed, &repay_denom));
+  let collateral = balance_of(&balances.collateral, &utoken_denom(&reward_base_denom));
+
+  let registry = query_registered_tokens(deps, RegisteredTokensParams {})?;
+  let incentive = registry
+    .get(&reward_base_denom)
+    .map(|token| token.liquidation_incentive())
+    .ok_or_else(|| {
+      StdError::generic_err(format!("{} is not a registered token", reward_base_denom))
+    })?;
+
+  let repay_market = query_market_summary(deps, MarketSummaryParams::new(repay_denom.clone()))?;
+  let reward_market =
+    query_market_summary(deps, MarketSummaryParams::new(reward_base_denom.clone()))?;
+  if reward_market.oracle_price.is_zero() || reward_market.utoken_exchange_rate.is_zero() {
+    return Err(StdError::generic_err(format!(
+      "{} has no price to value the reward",
+      reward_base_denom
+    )));
+  }
+
+  // uTokens of the reward for each repaid token
+  let repay_value = market_value(&repay_market, Decimal256::one())?;
+  let repay_value = checked_mul(
+    repay_value,
+    Decimal256::one() + Decimal256::from(incentive),
+    "liquidation incentive",
+  )?;
+  let reward_value = market_value(&reward_market, reward_market.utoken_exchange_rate)?;
+  let reward_per_repay = checked_div(repay_value, reward_value, "reward price")?;
+
+  let mut repay_amount = Decimal256::from_ratio(repay_amount, 1u128);
+  let mut reward_utokens = checked_mul(repay_amount, reward_per_repay, "reward")?;
+  let collateral = Decimal256::from_ratio(collateral, 1u128);
+  if reward_utokens > collateral {
+    let repay_share = checked_div(collateral, reward_utokens, "reward share")?;
+    repay_amount = checked_mul(repay_amount, repay_share, "repay")?;
+    reward_utokens = collateral;
+  }
+
+  let reward = if reward_denom.starts_with(UTOKEN_PREFIX) {
+    reward_utokens
+  } else {
+    checked_mul(reward_utokens, reward_market.utoken_exchange_rate, "reward")?
+  };
+
+  Ok(SimulateLiquidationResponse {
+    repay: Coin {
+      denom: repay_denom,
+      amount: decimal_to_uint128(repay_amount)?,
+    },
+    reward: Coin {
+      denom: reward_denom,
+      amount: decimal_to_uint128(reward)?,
+    },
+  })
+}
+
+// query_interest_rate_model reads the borrow rate curve of the denom
+// from its registry settings
+fn query_interest_rate_model(
+  deps: Deps,
+  interest_rate_model_params: InterestRateModelParams,
+) -> StdResult<InterestRateModelResponse> {
+  let denom = interest_rate_model_params.denom;
+  let registry = query_registered_tokens(deps, RegisteredTokensParams {})?;
+  let token = registry
+    .get(&denom)
+    .ok_or_else(|| StdError::generic_err(format!("{} is not a registered token", denom)))?;
+
+  Ok(InterestRateModelResponse {
+    base_rate: token.base_borrow_rate(),
+    kink_utilization: token.kink_utilization(),
+    kink_rate: token.kink_borrow_rate(),
+    max_rate: token.max_borrow_rate(),
+  })
+}
+
+// query_special_borrow_limit returns the borrow limit computed by the
+// module, which weights the special asset pairs above the regular collateral
+fn query_special_borrow_limit(
+  deps: Deps,
+  special_borrow_limit_params: SpecialBorrowLimitParams,
+) -> StdResult<SpecialBorrowLimitResponse> {
+  let summary = query_account_summary(
+    deps,
+    AccountSummaryParams {
+      address: special_borrow_limit_params.address,
+    },
+  )?;
+
+  Ok(SpecialBorrowLimitResponse {
+    borrow_limit: summary.borrow_limit,
+  })
+}
+
+// query_liquidatable_borrowers pages through the liquidation targets and
+// keeps the ones borrowing the denom, healthy borrowers are not listed.
+// Each inspected address costs one account balances query, so a page
+// inspects at most limit targets and returns the cursor to the next one.
+fn query_liquidatable_borrowers(
+  deps: Deps,
+  liquidatable_borrowers_params: LiquidatableBorrowersParams,
+) -> StdResult<LiquidatableBorrowersResponse> {
+  let limit = liquidatable_borrowers_params
+    .limit
+    .unwrap_or(DEFAULT_PAGE_LIMIT)
+    .min(MAX_PAGE_LIMIT) as usize;
+  let mut targets = query_liquidation_targets(deps, LiquidationTargetsParams {})?.targets;
+  targets.sort();
+  targets.dedup();
+  if let Some(start_after) = &liquidatable_borrowers_params.start_after {
+    targets.retain(|target| target.as_str() > start_after.as_str());
+  }
+  let more = targets.len() > limit;
+  targets.truncate(limit);
+
+  let mut borrowers = vec![];
+  for target in &targets {
+    let address = Addr::unchecked(target);
+    let balances = query_account_balances(
+      deps,
+      AccountBalancesParams {
+        address: address.clone(),
+      },
+    )?;
+    if let Some(borrowed) = balances
+      .borrowed
+      .into_iter()
+      .find(|coin| coin.denom == liquidatable_borrowers_params.denom && !coin.amount.is_zero())
+    {
+      borrowers.push((address, borrowed));
+    }
+  }
+
+  Ok(LiquidatableBorrowersResponse {
+    borrowers,
+    start_after: targets.pop().filter(|_| more).map(Addr::unchecked),
+  })
+}
+
+// query_available_withdraw returns the free liquidity of the market
+// from the market summary
+fn query_available_withdraw(
+  deps: Deps,
+  available_withdraw_params: AvailableWithdrawParams,
+) -> StdResult<AvailableWithdrawResponse> {
+  let denom = available_withdraw_params.denom;
+  let market_summary = query_market_summary(
+    deps,
+    MarketSummaryParams {
+      denom: denom.clone(),
+    },
+  )?;
+
+  Ok(AvailableWithdrawResponse {
+    amount: Coin {
+      denom,
+      amount: decimal_to_uint128(market_summary.available_withdraw)?,
+    },
+  })
+}
+
+// query_incentive
+fn query_incentive(deps: Deps, _env: Env, msg: UmeeQueryIncentive) -> StdResult<Binary> {
+  match msg {
+    UmeeQueryIncentive::IncentiveParameters(incentive_params) => {
+      to_json_binary(&query_incentive_params(deps, incentive_params)?)
+    }
+    UmeeQueryIncentive::TotalBonded(params) => to_json_binary(&query_total_bonded(deps, params)?),
+    UmeeQueryIncentive::TotalUnbonding(params) => {
+      to_json_binary(&query_total_unbonding(deps, params)?)
+    }
+    UmeeQueryIncentive::AccountBonds(params) => to_json_binary(&query_account_bonds(deps, params)?),
+    UmeeQueryIncentive::PendingRewards(params) => {
+      to_json_binary(&query_pending_rewards(deps, params)?)
+    }
+    UmeeQueryIncentive::CompletedIncentivePrograms(params) => {
+      to_json_binary(&query_completed_incentive_programs(deps, params)?)
+    }
+    UmeeQueryIncentive::OngoingIncentivePrograms(params) => {
+      to_json_binary(&query_ongoing_incentive_programs(deps, params)?)
+    }
+    UmeeQueryIncentive::UpcomingIncentivePrograms(params) => {
+      to_json_binary(&query_upcoming_incentive_programs(deps, params)?)
+    }
+    UmeeQueryIncentive::IncentiveProgram(params) => {
+      to_json_binary(&query_incentive_program(deps, params)?)
+    }
+    UmeeQueryIncentive::CurrentRates(params) => to_json_binary(&query_current_rates(deps, params)?),
+    UmeeQueryIncentive::ActualRates(params) => to_json_binary(&query_actutal_rates(deps, params)?),
+    UmeeQueryIncentive::LastRewardTime(params) => {
+      to_json_binary(&query_last_reward_time(deps, params)?)
+    }
+  }
+}
+
+// query_metoken
+fn query_metoken(deps: Deps, _env: Env, msg: UmeeQueryMeToken) -> StdResult<Binary> {
+  match msg {
+    UmeeQueryMeToken::MetokenParameters(params) => {
+      to_json_binary(&query_metoken_params(deps, params)?)
+    }
+    UmeeQueryMeToken::MetokenIndexes(params) => {
+      to_json_binary(&query_metoken_indexes(deps, params)?)
+    }
+    UmeeQueryMeToken::MetokenSwapfee(params) => {
+      to_json_binary(&query_metoken_swapfee(deps, params)?)
+    }
+    UmeeQueryMeToken::MetokenRedeemfee(params) => {
+      to_json_binary(&query_metoken_redeemfee(deps, params)?)
+    }
+    UmeeQueryMeToken::MetokenIndexbalances(params) => {
+      to_json_binary(&query_metoken_indexbalances(deps, params)?)
+    }
+    UmeeQueryMeToken::MetokenIndexPrices(params) => {
+      to_json_binary(&query_metoken_indexprice(deps, params)?)
+    }
+  }
+}
+
+// query_metoken_indexprice
+fn query_metoken_indexprice(
+  deps: Deps,
+  params: MetokenIndexPricesParams,
+) -> StdResult<MetokenIndexPricesResponse> {
+  let request = QueryRequest::Custom(StructUmeeQuery::metoken_indexprice(params));
+
+  query_chain_json(deps, &request)
+}
+
+// query_metoken_indexbalances
+fn query_metoken_indexbalances(
+  deps: Deps,
+  params: MetokenIndexbalancesParams,
+) -> StdResult<MetokenIndexbalancesResponse> {
+  let request = QueryRequest::Custom(StructUmeeQuery::metoken_indexbalances(params));
+
+  query_chain_json(deps, &request)
+}
+
+// query_metoken_redeemfee
+fn query_metoken_redeemfee(
+  deps: Deps,
+  params: MetokenRedeemfeeParams,
+) -> StdResult<MetokenRedeemfeeResponse> {
+  let request = QueryRequest::Custom(StructUmeeQuery::metoken_redeemfee(params));
+
+  query_chain_json(deps, &request)
+}
+
+// query_metoken_swapfee
+fn query_metoken_swapfee(
+  deps: Deps,
+  params: MetokenSwapfeeParams,
+) -> StdResult<MetokenSwapfeeResponse> {
+  let request = QueryRequest::Custom(StructUmeeQuery::metoken_swapfee(params));
+
+  query_chain_json(deps, &request)
+}
+
+// query_metoken_indexes
+fn query_metoken_indexes(
+  deps: Deps,
+  params: MetokenIndexesParams,
+) -> StdResult<MetokenIndexesResponse> {
+  let request = QueryRequest::Custom(StructUmeeQuery::metoken_indexes(params));
+
+  query_chain_json(deps, &request)
+}
+
+// query_metoken_params
+fn query_metoken_params(
+  deps: Deps,
+  params: MetokenParametersParams,
+) -> StdResult<MetokenParametersResponse> {
+  let request = QueryRequest::Custom(StructUmeeQuery::metoken_parameters(params));
+
+  query_chain_json(deps, &request)
+}
+
+// query_last_reward_time
+fn query_last_reward_time(
+  deps: Deps,
+  params: LastRewardTimeParams,
+) -> StdResult<LastRewardTimeResponse> {
+  let request = QueryRequest::Custom(StructUmeeQuery::last_reward_time(params));
+  query_chain_json(deps, &request)
+}
+
+// query_actutal_rates
+fn query_actutal_rates(
+  deps: Deps,
+  mut params: ActualRatesParams,
+) -> StdResult<ActualRatesResponse> {
+  let round_to = params.round_to.take();
+  let request = QueryRequest::Custom(StructUmeeQuery::actual_rates(params));
+  let mut response: ActualRatesResponse = query_chain_json(deps, &request)?;
+  if let Some(places) = round_to {
+    response.apy = Decimal::try_from(round_decimal(response.apy.into(), places)?)
+      .map_err(|err| StdError::generic_err(err.to_string()))?;
+  }
+  Ok(response)
+}
+
+// query_current_rates
+fn query_current_rates(deps: Deps, params: CurrentRatesParams) -> StdResult<CurrentRatesResponse> {
+  let request = QueryRequest::Custom(StructUmeeQuery::current_rates(params));
+  query_chain_json(deps, &request)
+}
+
+// query_incentive_program
+fn query_incentive_program(
+  deps: Deps,
+  params: IncentiveProgramParams,
+) -> StdResult<IncentiveProgramResponse> {
+  let request = QueryRequest::Custom(StructUmeeQuery::incentive_program(params));
+  query_chain_json(deps, &request)
+}
+
+// query_upcoming_incentive_programs
+fn query_upcoming_incentive_programs(
+  deps: Deps,
+  params: UpcomingIncentiveProgramsParams,
+) -> StdResult<UpcomingIncentiveProgramsResponse> {
+  let request = QueryRequest::Custom(StructUmeeQuery::upcoming_incentive_programs(params));
+  query_chain_json(deps, &request)
+}
+
+// query_ongoing_incentive_programs
+fn query_ongoing_incentive_programs(
+  deps: Deps,
+  params: OngoingIncentiveProgramsParams,
+) -> StdResult<OngoingIncentiveProgramsResponse> {
+  let request = QueryRequest::Custom(StructUmeeQuery::ongoing_incentive_programs(params));
+  query_chain_json(deps, &request)
+}
+
+// query_completed_incentive_programs
+fn query_completed_incentive_programs(
+  deps: Deps,
+  params: CompletedIncentiveProgramsParams,
+) -> StdResult<CompletedIncentiveProgramsResponse> {
+  let request = QueryRequest::Custom(StructUmeeQuery::completed_incentive_programs(params));
+  query_chain_json(deps, &request)
+}
+
+// query_account_bonds
+fn query_pending_rewards(
+  deps: Deps,
+  params: PendingRewardsParams,
+) -> StdResult<PendingRewardsResponse> {
+  let request = QueryRequest::Custom(StructUmeeQuery::pending_rewards(params));
+  query_chain_json(deps, &request)
+}
+
+// query_account_bonds
+fn query_account_bonds(deps: Deps, params: AccountBondsParams) -> StdResult<AccountBondsResponse> {
+  let request = QueryRequest::Custom(StructUmeeQuery::account_bonds(params));
+  query_chain_json(deps, &request)
+}
+
+// query_total_unbonding
+fn query_total_unbonding(
+  deps: Deps,
+  params: TotalUnbondingParams,
+) -> StdResult<TotalUnbondingResponse> {
+  let request = QueryRequest::Custom(StructUmeeQuery::total_unbonding(params));
+  query_chain_json(deps, &request)
+}
+
+// query_total_bonded
+fn query_total_bonded(deps: Deps, params: TotalBondedParams) -> StdResult<TotalBondedResponse> {
+  let request = QueryRequest::Custom(StructUmeeQuery::total_bonded(params));
+  query_chain_json(deps, &request)
+}
+
+// query_incentive_params
+fn query_incentive_params(
+  deps: Deps,
+  incentive_params: IncentiveParametersParams,
+) -> StdResult<IncentiveParametersResponse> {
+  let request = QueryRequest::Custom(StructUmeeQuery::incentive_params(incentive_params));
+
+  query_chain_json(deps, &request)
+}
+
+// query_oracle contains the umee oracle available queries
+fn query_oracle(deps: Deps, _env: Env, msg: UmeeQueryOracle) -> StdResult<Binary> {
+  msg.validate()?;
+  match msg {
+    // consumes the query_chain wrapped by Umee Leverage enums
+    // to clarift the JSON queries to umee leverage native module
+    // example json input:
+    // {
+    //   "umee": {
+    //     "oracle": {
+    //       "exchange_rates": {
+    //         "denom": "uumee"
+    //       }
+    //     }
+    //   }
+    // }
+    // successful json output:
+    // {
+    //   "data": {
+    //     "exchange_rate_base": "0.0000032"
+    //   }
+    // }
+    UmeeQueryOracle::ExchangeRates(exchange_rates_params) => {
+      to_json_binary(&query_exchange_rates(deps, exchange_rates_params)?)
+    }
+    UmeeQueryOracle::ActiveExchangeRates(active_exchange_rates_params) => to_json_binary(
+      &query_active_exchange_rates(deps, active_exchange_rates_params)?,
+    ),
+    UmeeQueryOracle::FeederDelegation(feeder_delegation_params) => {
+      to_json_binary(&query_feeder_delegation(deps, feeder_delegation_params)?)
     }
     UmeeQueryOracle::MissCounter(miss_counter_params) => {
       to_json_binary(&query_miss_counter(deps, miss_counter_params)?)
     }
-    UmeeQueryOracle::SlashWindow(slash_window_params) => {
-      to_json_binary(&query_slash_window(deps, slash_window_params)?)
+    UmeeQueryOracle::SlashWindow(slash_window_params) => {
+      to_json_binary(&query_slash_window(deps, slash_window_params)?)
+    }
+    UmeeQueryOracle::AggregatePrevote(aggregate_prevote_params) => {
+      to_json_binary(&query_aggregate_prevote(deps, aggregate_prevote_params)?)
+    }
+    UmeeQueryOracle::AggregatePrevotes(aggregate_prevotes_params) => {
+      to_json_binary(&query_aggregate_prevotes(deps, aggregate_prevotes_params)?)
+    }
+    UmeeQueryOracle::AggregateVote(aggregate_vote_params) => {
+      to_json_binary(&query_aggregate_vote(deps, aggregate_vote_params)?)
+    }
+    UmeeQueryOracle::AggregateVotes(aggregate_votes_params) => {
+      to_json_binary(&query_aggregate_votes(deps, aggregate_votes_params)?)
+    }
+    UmeeQueryOracle::OracleParameters(oracle_parameters_params) => {
+      to_json_binary(&query_oracle_parameters(deps, oracle_parameters_params)?)
+    }
+    UmeeQueryOracle::Medians(median_params) => to_json_binary(&query_medians(deps, median_params)?),
+    UmeeQueryOracle::MedianDeviations(median_deviations_params) => {
+      to_json_binary(&query_median_deviations(deps, median_deviations_params)?)
+    }
+    UmeeQueryOracle::ExchangeRatesBatch(exchange_rates_batch_params) => {
+      dedup_denoms(
+        "exchange rates batch",
+        exchange_rates_batch_params
+          .denoms
+          .iter()
+          .map(String::as_str),
+      )
+      .map_err(|err| StdError::generic_err(err.to_string()))?;
+      to_json_binary(&query_exchange_rates_batch(
+        deps,
+        exchange_rates_batch_params,
+      )?)
+    }
+    UmeeQueryOracle::AcceptList(accept_list_params) => {
+      to_json_binary(&query_accept_list(deps, accept_list_params)?)
+    }
+  }
+}
+
+// query_accept_list returns the oracle accepted denoms out of the oracle params.
+fn query_accept_list(deps: Deps, _: AcceptListParams) -> StdResult<AcceptListResponse> {
+  let response = query_oracle_parameters(deps, OracleParametersParams {})?;
+
+  Ok(AcceptListResponse {
+    denoms: response.params.accept_list(),
+  })
+}
+
+// query_exchange_rates_batch queries the exchange rate of each denom,
+// a denom without a price is reported as missing instead of failing the batch
+fn query_exchange_rates_batch(
+  deps: Deps,
+  exchange_rates_batch_params: ExchangeRatesBatchParams,
+) -> StdResult<ExchangeRatesBatchResponse> {
+  let mut rates = vec![];
+  let mut missing = vec![];
+  for denom in exchange_rates_batch_params.denoms {
+    match query_exchange_rates(deps, ExchangeRatesParams::new(denom.clone())) {
+      Ok(response) => match response
+        .exchange_rates
+        .into_iter()
+        .find(|rate| rate.denom.eq_ignore_ascii_case(&denom))
+      {
+        Some(rate) => rates.push(rate),
+        None => missing.push(denom),
+      },
+      Err(_) => missing.push(denom),
+    }
+  }
+
+  Ok(ExchangeRatesBatchResponse { rates, missing })
+}
+
+// query_registered_tokens receives the get all registered tokens
+// query params and creates an query request to the native modules
+// with query_chain wrapping the response to the actual
+// RegisteredTokensResponse struct
+pub(crate) fn query_registered_tokens(
+  deps: Deps,
+  registered_tokens_params: RegisteredTokensParams,
+) -> StdResult<RegisteredTokensResponse> {
+  let request = QueryRequest::Custom(StructUmeeQuery::registered_tokens(registered_tokens_params));
+
+  let binary = query_chain(deps, &request)?;
+  if binary.len() > MAX_REGISTERED_TOKENS_RESPONSE_BYTES {
+    return Err(StdError::generic_err(format!(
+      "RegisteredTokens response has {} bytes, above the {} bytes limit, \
+       query the tokens individually or with pagination",
+      binary.len(),
+      MAX_REGISTERED_TOKENS_RESPONSE_BYTES
+    )));
+  }
+  decode_response(&binary)
+}
+
+// query_leverage_parameters creates an query request to the native modules
+// with query_chain wrapping the response to the actual
+// LeverageParametersResponse struct
+fn query_leverage_parameters(
+  deps: Deps,
+  leverage_parameters_params: LeverageParametersParams,
+) -> StdResult<LeverageParametersResponse> {
+  let request = QueryRequest::Custom(StructUmeeQuery::leverage_parameters(
+    leverage_parameters_params,
+  ));
+
+  query_chain_json(deps, &request)
+}
+
+// query_account_balances creates an query request to the native modules
+// with query_chain wrapping the response to the actual
+// AccountBalancesResponse struct.
+pub(crate) fn query_account_balances(
+  deps: Deps,
+  account_balances_params: AccountBalancesParams,
+) -> StdResult<AccountBalancesResponse> {
+  let request = QueryRequest::Custom(StructUmeeQuery::account_balances(account_balances_params));
+
+  let binary = query_chain(deps, &request)?;
+  // an account without any position may be answered with an empty payload
+  if binary.is_empty() {
+    return Ok(AccountBalancesResponse::default());
+  }
+  decode_response(&binary)
+}
+
+// query_account_summary creates an query request to the native modules
+// with query_chain wrapping the response to the actual
+// AccountsummaryResponse struct.
+pub(crate) fn query_account_summary(
+  deps: Deps,
+  account_summary_params: AccountSummaryParams,
+) -> StdResult<AccountSummaryResponse> {
+  let request = QueryRequest::Custom(StructUmeeQuery::account_summary(account_summary_params));
+
+  let binary = query_chain(deps, &request)?;
+  // an account without any position may be answered with an empty payload
+  if binary.is_empty() {
+    return Ok(AccountSummaryResponse::default());
+  }
+  decode_response(&binary)
+}
+
+// query_liquidation_targets creates an query request to the native modules
+// with query_chain wrapping the response to the actual
+// LiquidationTargetsResponse struct.
+fn query_liquidation_targets(
+  deps: Deps,
+  liquidation_targets_params: LiquidationTargetsParams,
+) -> StdResult<LiquidationTargetsResponse> {
+  let request = QueryRequest::Custom(StructUmeeQuery::liquidation_targets(
+    liquidation_targets_params,
+  ));
+
+  query_chain_json(deps, &request)
+}
+
+fn query_bad_debts(deps: Deps, bad_debts_params: BadDebtsParams) -> StdResult<BadDebtsResponse> {
+  let request = QueryRequest::Custom(StructUmeeQuery::bad_debts_parameters(bad_debts_params));
+
+  query_chain_json(deps, &request)
+}
+
+// query_max_withdraw
+fn query_max_withdraw(
+  deps: Deps,
+  max_withdraw_params: MaxWithdrawParams,
+) -> StdResult<MaxWithdrawResponse> {
+  let request = QueryRequest::Custom(StructUmeeQuery::max_withdraw_params(max_withdraw_params));
+
+  query_chain_json(deps, &request)
+}
+
+// query_max_borrow
+fn query_max_borrow(
+  deps: Deps,
+  max_borrow_params: MaxBorrowParams,
+) -> StdResult<MaxBorrowResponse> {
+  let request = QueryRequest::Custom(StructUmeeQuery::max_borrow_params(max_borrow_params));
+
+  query_chain_json(deps, &request)
+}
+
+// query_market_summary creates an query request to the native modules
+// with query_chain wrapping the response to the actual
+// MarketSummaryResponse struct.
+pub(crate) fn query_market_summary(
+  deps: Deps,
+  market_summary_params: MarketSummaryParams,
+) -> StdResult<MarketSummaryResponse> {
+  let denom = market_summary_params.denom.clone();
+  let request = QueryRequest::Custom(StructUmeeQuery::market_summary(market_summary_params));
+
+  // negative apys already fail to decode as Decimal256
+  let response: MarketSummaryResponse = query_chain_json(deps, &request)?;
+  sanity_check_apy(&denom, "supply", response.supply_apy)?;
+  sanity_check_apy(&denom, "borrow", response.borrow_apy)?;
+  Ok(response)
+}
+
+// sanity_check_apy rejects an apy above MAX_SANE_APY.
+fn sanity_check_apy(denom: &str, kind: &str, apy: Decimal256) -> StdResult<()> {
+  if apy > MAX_SANE_APY {
+    return Err(StdError::generic_err(format!(
+      "{} {} apy {} is above the {} ceiling, the market data looks corrupt",
+      denom, kind, apy, MAX_SANE_APY
+    )));
+  }
+  Ok(())
+}
+
+// query_exchange_rates receives the get exchange rate base
+// query params and creates an query request to the native modules
+// with query_chain wrapping the response to the actual
+// ExchangeRatesResponse struct. A base denom is first replaced by the
+// symbol denom of its registered token, the one the oracle prices
+fn query_exchange_rates(
+  deps: Deps,
+  mut exchange_rates_params: ExchangeRatesParams,
+) -> StdResult<ExchangeRatesResponse> {
+  let round_to = exchange_rates_params.round_to.take();
+  if exchange_rates_params.denom_kind == DenomKind::Base {
+    let denom = &exchange_rates_params.denom;
+    let registry = query_registered_tokens(deps, RegisteredTokensParams {})?;
+    let token = registry
+      .get(denom)
+      .ok_or_else(|| StdError::generic_err(format!("{} is not a registered token", denom)))?;
+    exchange_rates_params.denom = token.symbol_denom().to_string();
+    exchange_rates_params.denom_kind = DenomKind::Symbol;
+  }
+  let request = QueryRequest::Custom(StructUmeeQuery::exchange_rates(exchange_rates_params));
+
+  let mut response: ExchangeRatesResponse = query_chain_json(deps, &request)?;
+  if let Some(places) = round_to {
+    for rate in response.exchange_rates.iter_mut() {
+      rate.amount = round_decimal(rate.amount, places)?;
+    }
+  }
+  Ok(response)
+}
+
+// query_active_exchange_rates receives the get exchange rate base
+// query params and creates an query request to the native modules
+// with query_chain wrapping the response to the actual
+// ActiveExchangeRatesResponse struct
+fn query_active_exchange_rates(
+  deps: Deps,
+  active_exchange_rates_params: ActiveExchangeRatesParams,
+) -> StdResult<ActiveExchangeRatesResponse> {
+  let request = QueryRequest::Custom(StructUmeeQuery::active_exchange_rates(
+    active_exchange_rates_params,
+  ));
+
+  query_chain_json(deps, &request)
+}
+
+// query_feeder_delegation receives the get exchange rate base
+// query params and creates an query request to the native modules
+// with query_chain wrapping the response to the actual
+// FeederDelegationResponse struct
+fn query_feeder_delegation(
+  deps: Deps,
+  feeder_delegation_params: FeederDelegationParams,
+) -> StdResult<FeederDelegationResponse> {
+  let request = QueryRequest::Custom(StructUmeeQuery::feeder_delegation(feeder_delegation_params));
+
+  query_chain_json(deps, &request)
+}
+
+// query_miss_counter receives the get exchange rate base
+// query params and creates an query request to the native modules
+// with query_chain wrapping the response to the actual
+// MissCounterResponse struct
+fn query_miss_counter(
+  deps: Deps,
+  miss_counter_params: MissCounterParams,
+) -> StdResult<MissCounterResponse> {
+  let request = QueryRequest::Custom(StructUmeeQuery::miss_counter(miss_counter_params));
+
+  query_chain_json(deps, &request)
+}
+
+// query_slash_window receives the slash window
+// query params and creates an query request to the native modules
+// with query_chain wrapping the response to the actual
+// SlashWindowResponse struct
+fn query_slash_window(
+  deps: Deps,
+  slash_window_params: SlashWindowParams,
+) -> StdResult<SlashWindowResponse> {
+  let request = QueryRequest::Custom(StructUmeeQuery::slash_window(slash_window_params));
+
+  query_chain_json(deps, &request)
+}
+
+// query_aggregate_prevote receives the get exchange rate base
+// query params and creates an query request to the native modules
+// with query_chain wrapping the response to the actual
+// AggregatePrevoteResponse struct
+fn query_aggregate_prevote(
+  deps: Deps,
+  aggregate_prevote_params: AggregatePrevoteParams,
+) -> StdResult<AggregatePrevoteResponse> {
+  let request = QueryRequest::Custom(StructUmeeQuery::aggregate_prevote(aggregate_prevote_params));
+
+  query_chain_json(deps, &request)
+}
+
+// query_aggregate_prevotes receives the get exchange rate base
+// query params and creates an query request to the native modules
+// with query_chain wrapping the response to the actual
+// AggregatePrevotesResponse struct
+fn query_aggregate_prevotes(
+  deps: Deps,
+  aggregate_prevotes_params: AggregatePrevotesParams,
+) -> StdResult<AggregatePrevotesResponse> {
+  let request = QueryRequest::Custom(StructUmeeQuery::aggregate_prevotes(
+    aggregate_prevotes_params,
+  ));
+
+  query_chain_json(deps, &request)
+}
+
+// query_aggregate_vote receives the get exchange rate base
+// query params and creates an query request to the native modules
+// with query_chain wrapping the response to the actual
+// AggregateVoteResponse struct
+fn query_aggregate_vote(
+  deps: Deps,
+  aggregate_vote_params: AggregateVoteParams,
+) -> StdResult<AggregateVoteResponse> {
+  let request = QueryRequest::Custom(StructUmeeQuery::aggregate_vote(aggregate_vote_params));
+
+  query_chain_json(deps, &request)
+}
+
+// query_aggregate_votes receives the get exchange rate base
+// query params and creates an query request to the native modules
+// with query_chain wrapping the response to the actual
+// AggregateVotesResponse struct
+fn query_aggregate_votes(
+  deps: Deps,
+  aggregate_votes_params: AggregateVotesParams,
+) -> StdResult<AggregateVotesResponse> {
+  let request = QueryRequest::Custom(StructUmeeQuery::aggregate_votes(aggregate_votes_params));
+
+  query_chain_json(deps, &request)
+}
+
+// query_oracle_parameters receives the get exchange rate base
+// query params and creates an query request to the native modules
+// with query_chain wrapping the response to the actual
+// OracleParametersResponse struct
+fn query_oracle_parameters(
+  deps: Deps,
+  oracle_parameters_params: OracleParametersParams,
+) -> StdResult<OracleParametersResponse> {
+  let request = QueryRequest::Custom(StructUmeeQuery::oracle_parameters(oracle_parameters_params));
+
+  query_chain_json(deps, &request)
+}
+
+fn query_medians(deps: Deps, medians_params: MediansParams) -> StdResult<MediansParamsResponse> {
+  let request = QueryRequest::Custom(StructUmeeQuery::medians_params(medians_params));
+
+  query_chain_json(deps, &request)
+}
+
+fn query_median_deviations(
+  deps: Deps,
+  medians_deviations_params: MedianDeviationsParams,
+) -> StdResult<MedianDeviationsParamsResponse> {
+  let request = QueryRequest::Custom(StructUmeeQuery::median_deviations_params(
+    medians_deviations_params,
+  ));
+
+  query_chain_json(deps, &request)
+}
+
+// -----------------------------------TESTS---------------------------------------
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::querier::MockLeverageQuerier;
+  use cosmwasm_std::testing::{mock_env, mock_info, MockApi, MockQuerier, MockStorage};
+  use cosmwasm_std::{coins, CosmosMsg, OwnedDeps, Querier, QuerierResult, ReplyOn, SystemError};
+  use cosmwasm_std::{DenomMetadata, DenomUnit, Storage, SubMsgResponse, SubMsgResult};
+  use cw_umee_types::proto::{encode_coin, encode_message};
+  use cw_umee_types::{DecCoin, SupplyCollateralParams};
+  use serde::de::IgnoredAny;
+  use std::cell::RefCell;
+  use std::collections::BTreeMap;
+  use std::marker::PhantomData;
+  use std::str::FromStr;
+
+  const ORACLE_PARAMS_JSON: &str = r#"{"params":{"vote_period":5,"vote_threshold":"0.5","reward_band":"0.02","reward_distribution_window":5256000,"accept_list":[{"base_denom":"uumee","symbol_denom":"UMEE","exponent":6}],"slash_fraction":"0.0001","slash_window":100800,"min_valid_per_window":"0.05","stamp_period":10,"prune_period":100,"median_period":50,"historic_accept_list":[]}}"#;
+
+  // UmeeMockQuerier answers the registered umee custom queries and
+  // forwards every other request to the cosmwasm MockQuerier, the umee
+  // custom queries are recorded in requested
+  struct UmeeMockQuerier {
+    base: MockQuerier<StructUmeeQuery>,
+    custom: Vec<(StructUmeeQuery, Binary)>,
+    requested: RefCell<Vec<StructUmeeQuery>>,
+  }
+
+  impl Querier for UmeeMockQuerier {
+    fn raw_query(&self, bin_request: &[u8]) -> QuerierResult {
+      let request: QueryRequest<StructUmeeQuery> = match from_json(bin_request) {
+        Ok(request) => request,
+        Err(err) => {
+          return SystemResult::Err(SystemError::InvalidRequest {
+            error: err.to_string(),
+            request: bin_request.into(),
+          })
+        }
+      };
+      if let QueryRequest::Custom(query) = &request {
+        self.requested.borrow_mut().push(query.clone());
+      }
+      match &request {
+        QueryRequest::Custom(query) => match self.custom.iter().find(|(q, _)| q == query) {
+          Some((_, response)) => SystemResult::Ok(ContractResult::Ok(response.clone())),
+          None => SystemResult::Err(SystemError::UnsupportedRequest {
+            kind: String::from("umee"),
+          }),
+        },
+        _ => self.base.handle_query(&request),
+      }
+    }
+  }
+
+  type UmeeMockDeps = OwnedDeps<MockStorage, MockApi, UmeeMockQuerier>;
+
+  // mock_umee_dependencies mocks a chain with the umee feature enabled
+  fn mock_umee_dependencies() -> UmeeMockDeps {
+    let mut deps = OwnedDeps {
+      storage: MockStorage::default(),
+      api: MockApi::default(),
+      querier: UmeeMockQuerier {
+        base: MockQuerier::new(&[]),
+        custom: vec![],
+        requested: RefCell::new(vec![]),
+      },
+      custom_query_type: PhantomData,
+    };
+    mock_custom_response(
+      &mut deps,
+      StructUmeeQuery::oracle_parameters(OracleParametersParams {}),
+      Binary::from(ORACLE_PARAMS_JSON.as_bytes()),
+    );
+    deps
+  }
+
+  // mock_chain_response registers the json response of an umee custom query
+  fn mock_chain_response<T: Serialize>(deps: &mut UmeeMockDeps, query: StructUmeeQuery, resp: &T) {
+    mock_custom_response(deps, query, to_json_binary(resp).unwrap());
+  }
+
+  // mock_contract_balance sets the bank balances of the contract
+  fn mock_contract_balance(deps: &mut UmeeMockDeps, balance: &[Coin]) {
+    let contract = mock_env().contract.address;
+    deps.querier.base.update_balance(contract, balance.to_vec());
+  }
+
+  // module_reply is the reply of a leverage submessage carrying the data
+  // the module returns on chain: a protobuf Msg*Response with the coins in
+  // its first fields
+  fn module_reply(id: u64, coins: &[Coin]) -> Reply {
+    let mut data = vec![];
+    for (field, coin) in (1..).zip(coins) {
+      encode_message(field, &encode_coin(coin), &mut data);
     }
-    UmeeQueryOracle::AggregatePrevote(aggregate_prevote_params) => {
-      to_json_binary(&query_aggregate_prevote(deps, aggregate_prevote_params)?)
+    Reply {
+      id,
+      result: SubMsgResult::Ok(SubMsgResponse {
+        events: vec![],
+        data: Some(Binary::from(data)),
+      }),
     }
-    UmeeQueryOracle::AggregatePrevotes(aggregate_prevotes_params) => {
-      to_json_binary(&query_aggregate_prevotes(deps, aggregate_prevotes_params)?)
+  }
+
+  fn mock_custom_response(deps: &mut UmeeMockDeps, query: StructUmeeQuery, resp: Binary) {
+    deps.querier.custom.retain(|(q, _)| q != &query);
+    deps.querier.custom.push((query, resp));
+  }
+
+  #[test]
+  fn proper_initialization() {
+    let mut deps = mock_umee_dependencies();
+
+    let msg = InstantiateMsg {
+      require_umee: None,
+      chain_queries: None,
+      check_denoms: None,
+    };
+    let info = mock_info("creator", &coins(1000, "earth"));
+
+    // we can just call .unwrap() to assert this was a success
+    let res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+    assert_eq!(0, res.messages.len());
+
+    // it worked, let's query the state
+    let res = query(deps.as_ref(), mock_env(), QueryMsg::GetOwner {}).unwrap();
+    let value: OwnerResponse = from_json(&res).unwrap();
+    assert_eq!("creator", value.owner);
+    assert!(STATE.load(&deps.storage).unwrap().umee_enabled);
+  }
+
+  #[test]
+  fn initialization_without_umee() {
+    let mut deps = mock_umee_dependencies();
+    deps.querier.custom.clear();
+
+    let info = mock_info("creator", &[]);
+    let msg = InstantiateMsg {
+      require_umee: None,
+      chain_queries: None,
+      check_denoms: None,
+    };
+    match instantiate(deps.as_mut(), mock_env(), info.clone(), msg) {
+      Err(ContractError::UmeeUnavailable { .. }) => {}
+      _ => panic!("Must return umee unavailable error"),
     }
-    UmeeQueryOracle::AggregateVote(aggregate_vote_params) => {
-      to_json_binary(&query_aggregate_vote(deps, aggregate_vote_params)?)
+
+    let msg = InstantiateMsg {
+      require_umee: Some(false),
+      chain_queries: None,
+      check_denoms: None,
+    };
+    instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+    assert!(!STATE.load(&deps.storage).unwrap().umee_enabled);
+  }
+
+  #[test]
+  fn change_owner() {
+    let mut deps = mock_umee_dependencies();
+
+    let first_owner = "creator";
+    let msg = InstantiateMsg {
+      require_umee: None,
+      chain_queries: None,
+      check_denoms: None,
+    };
+    let info = mock_info(first_owner, &coins(2, "token"));
+    let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    let res = query(deps.as_ref(), mock_env(), QueryMsg::GetOwner {}).unwrap();
+    let value: OwnerResponse = from_json(&res).unwrap();
+    assert_eq!(first_owner, value.owner);
+
+    let new_owner = "umee1qyqszqgpqyqszqgpqyqszqgpqyqszqgpq9upfw";
+
+    // only the original creator can change the owner the counter
+    let auth_info = mock_info(new_owner, &coins(2, "token"));
+    let msg = ExecuteMsg::ChangeOwner {
+      new_owner: cosmwasm_std::Addr::unchecked(new_owner),
+    };
+    let res = execute(deps.as_mut(), mock_env(), auth_info, msg);
+    match res {
+      Err(ContractError::Unauthorized {}) => {}
+      _ => panic!("Must return unauthorized error"),
     }
-    UmeeQueryOracle::AggregateVotes(aggregate_votes_params) => {
-      to_json_binary(&query_aggregate_votes(deps, aggregate_votes_params)?)
+
+    let auth_info = mock_info(first_owner, &coins(2, "token"));
+    let msg = ExecuteMsg::ChangeOwner {
+      new_owner: cosmwasm_std::Addr::unchecked(new_owner),
+    };
+    let _res = execute(deps.as_mut(), mock_env(), auth_info, msg).unwrap();
+
+    let res = query(deps.as_ref(), mock_env(), QueryMsg::GetOwner {}).unwrap();
+    let value: OwnerResponse = from_json(&res).unwrap();
+    assert_eq!(new_owner, value.owner);
+  }
+
+  fn mock_account_summary(deps: &mut UmeeMockDeps, address: &str, borrowed: &str, limit: &str) {
+    mock_chain_response(
+      deps,
+      StructUmeeQuery::account_summary(AccountSummaryParams {
+        address: Addr::unchecked(address),
+      }),
+      &AccountSummaryResponse {
+        supplied_value: Decimal256::from_str("2000").unwrap(),
+        collateral_value: Decimal256::from_str("1500").unwrap(),
+        borrowed_value: Decimal256::from_str(borrowed).unwrap(),
+        borrow_limit: Decimal256::from_str(limit).unwrap(),
+        liquidation_threshold: Decimal256::from_str("1200").unwrap(),
+      },
+    );
+  }
+
+  #[test]
+  fn borrow_limit_used() {
+    let mut deps = mock_umee_dependencies();
+    mock_account_summary(&mut deps, "borrower", "730", "1000");
+    mock_account_summary(&mut deps, "supplier", "0", "0");
+
+    let msg = QueryMsg::BorrowLimitUsed {
+      address: Addr::unchecked("borrower"),
+    };
+    let res = query(deps.as_ref(), mock_env(), msg).unwrap();
+    let value: BorrowLimitUsedResponse = from_json(&res).unwrap();
+    assert_eq!(Decimal256::from_str("0.73").unwrap(), value.used);
+    assert_eq!(Decimal256::from_str("1000").unwrap(), value.limit);
+    assert_eq!(Decimal256::from_str("270").unwrap(), value.available);
+    assert_eq!("1000.00", value.limit_display);
+    assert_eq!("270.00", value.available_display);
+
+    let msg = QueryMsg::BorrowLimitUsed {
+      address: Addr::unchecked("supplier"),
+    };
+    let res = query(deps.as_ref(), mock_env(), msg).unwrap();
+    let value: BorrowLimitUsedResponse = from_json(&res).unwrap();
+    assert_eq!(Decimal256::zero(), value.used);
+    assert_eq!(Decimal256::zero(), value.available);
+  }
+
+  fn mock_market_summary(deps: &mut UmeeMockDeps, denom: &str, utoken_exchange_rate: &str) {
+    mock_market_summary_apy(deps, denom, utoken_exchange_rate, "0.05");
+  }
+
+  fn mock_market_summary_apy(
+    deps: &mut UmeeMockDeps,
+    denom: &str,
+    utoken_exchange_rate: &str,
+    supply_apy: &str,
+  ) {
+    let json = format!(
+      r#"{{"symbol_denom":"UMEE","exponent":6,"oracle_price":"0.01","utoken_exchange_rate":"{}","supply_apy":"{}","borrow_apy":"0.1","supplied":"1000","reserved":"10","collateral":"800","borrowed":"500","liquidity":"490","maximum_borrow":"0","maximum_collateral":"0","minimum_liquidity":"100","utoken_supply":"900","available_borrow":"390","available_withdraw":"390","available_collateralize":"0"}}"#,
+      utoken_exchange_rate, supply_apy
+    );
+    mock_custom_response(
+      deps,
+      StructUmeeQuery::market_summary(MarketSummaryParams {
+        denom: denom.to_string(),
+      }),
+      Binary::from(json.as_bytes()),
+    );
+  }
+
+  #[test]
+  fn supply_and_bond() {
+    let mut deps = mock_umee_dependencies();
+    mock_market_summary(&mut deps, "uumee", "1.1");
+
+    let msg = ExecuteMsg::SupplyAndBond(SupplyParams::new(Coin::new(1000, "uumee")));
+    let res = execute(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+    assert_eq!(3, res.messages.len());
+
+    let collateralize = StructUmeeMsg::from(UmeeMsgLeverage::Collateralize(CollateralizeParams {
+      asset: Coin::new(909, "u/uumee"),
+    }));
+    assert_eq!(CosmosMsg::Custom(collateralize), res.messages[1].msg);
+    let bond = BondParams {
+      account: mock_env().contract.address,
+      u_token: Coin::new(909, "u/uumee"),
+    };
+    assert_eq!(CosmosMsg::from(bond), res.messages[2].msg);
+  }
+
+  #[test]
+  fn available_withdraw() {
+    let mut deps = mock_umee_dependencies();
+    mock_registry(&mut deps, &[TOKEN_JSON]);
+    mock_market_summary(&mut deps, "uumee", "1.1");
+
+    let msg = QueryMsg::Umee(Box::new(UmeeQuery::Leverage(
+      UmeeQueryLeverage::AvailableWithdraw(AvailableWithdrawParams {
+        denom: String::from("uumee"),
+      }),
+    )));
+    let res = query(deps.as_ref(), mock_env(), msg).unwrap();
+    let value: AvailableWithdrawResponse = from_json(&res).unwrap();
+    assert_eq!(Coin::new(390, "uumee"), value.amount);
+  }
+
+  #[test]
+  fn supply_reply_data() {
+    let mut deps = mock_umee_dependencies();
+    mock_contract_balance(&mut deps, &coins(1000, "uumee"));
+
+    // the passthrough is still sent without reply
+    let supply = SupplyParams::new(Coin::new(1000, "uumee"));
+    let msg = ExecuteMsg::Umee(UmeeMsg::Leverage(UmeeMsgLeverage::Supply(supply.clone())));
+    let res = execute(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+    assert_eq!(ReplyOn::Never, res.messages[0].reply_on);
+
+    let msg = ExecuteMsg::Supply(supply);
+    let res = execute(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+    assert_eq!(1, res.messages.len());
+    assert_eq!(REPLY_SUPPLY, res.messages[0].id);
+
+    mock_contract_balance(&mut deps, &coins(909, "u/uumee"));
+    let reply_msg = module_reply(REPLY_SUPPLY, &[Coin::new(909, "u/uumee")]);
+    let res = reply(deps.as_mut(), mock_env(), reply_msg.clone()).unwrap();
+    let value: SupplyMsgResponse = from_json(res.data.unwrap()).unwrap();
+    assert_eq!(Coin::new(909, "u/uumee"), value.received);
+
+    // a reply that can't be measured leaves the data out instead of failing
+    let res = reply(deps.as_mut(), mock_env(), reply_msg).unwrap();
+    assert_eq!(None, res.data);
+    assert!(res.attributes.iter().any(|attr| attr.key == "report_error"));
+  }
+
+  #[test]
+  fn liquidate_reply_data() {
+    let mut deps = mock_umee_dependencies();
+    let borrower = Addr::unchecked("umee1y6xz2ggfc0pcsmyjlekh0j9pxh6hk87ymc9due");
+    let mock_borrower = |deps: &mut UmeeMockDeps, borrowed: u128, collateral: u128| {
+      mock_chain_response(
+        deps,
+        StructUmeeQuery::account_balances(AccountBalancesParams::new(borrower.clone())),
+        &AccountBalancesResponse {
+          supplied: vec![],
+          collateral: coins(collateral, "u/uumee"),
+          borrowed: coins(borrowed, "uatom"),
+        },
+      );
+    };
+    mock_borrower(&mut deps, 100, 200);
+    mock_contract_balance(&mut deps, &coins(100, "uatom"));
+
+    let msg = ExecuteMsg::Umee(UmeeMsg::Leverage(UmeeMsgLeverage::Liquidate(
+      LiquidateParams::new(
+        borrower.clone(),
+        Coin::new(100, "uatom"),
+        Coin::new(1, "uumee"),
+      ),
+    )));
+    let res = execute(deps.as_mut(), mock_env(), mock_info("keeper", &[]), msg).unwrap();
+    assert_eq!(1, res.messages.len());
+    assert_eq!(REPLY_LIQUIDATE, res.messages[0].id);
+
+    // the repayment is capped by the module below the requested 100uatom
+    mock_borrower(&mut deps, 40, 125);
+    mock_contract_balance(&mut deps, &[Coin::new(40, "uatom"), Coin::new(80, "uumee")]);
+    let settled = [
+      Coin::new(60, "uatom"),
+      Coin::new(75, "u/uumee"),
+      Coin::new(80, "uumee"),
+    ];
+    let res = reply(
+      deps.as_mut(),
+      mock_env(),
+      module_reply(REPLY_LIQUIDATE, &settled),
+    )
+    .unwrap();
+    assert_eq!("reply_liquidate", res.attributes[0].value);
+    let value: LiquidateMsgResponse = from_json(res.data.unwrap()).unwrap();
+    assert_eq!(
+      LiquidateMsgResponse {
+        repaid: settled[0].clone(),
+        collateral: settled[1].clone(),
+        reward: settled[2].clone(),
+      },
+      value
+    );
+  }
+
+  #[test]
+  fn denom_metadata() {
+    let mut deps = mock_umee_dependencies();
+    deps.querier.base.set_denom_metadata(&[DenomMetadata {
+      base: String::from("uumee"),
+      display: String::from("umee"),
+      symbol: String::from("UMEE"),
+      denom_units: vec![
+        DenomUnit {
+          denom: String::from("uumee"),
+          exponent: 0,
+          aliases: vec![],
+        },
+        DenomUnit {
+          denom: String::from("umee"),
+          exponent: 6,
+          aliases: vec![],
+        },
+      ],
+      ..DenomMetadata::default()
+    }]);
+
+    let msg = QueryMsg::DenomMetadata {
+      denom: String::from("uumee"),
+    };
+    let res = query(deps.as_ref(), mock_env(), msg).unwrap();
+    let value: DenomMetadataResponse = from_json(&res).unwrap();
+    assert_eq!("umee", value.display);
+    assert_eq!(6, value.exponent);
+    assert_eq!("UMEE", value.symbol);
+  }
+
+  #[test]
+  fn rebalance() {
+    let mut deps = mock_umee_dependencies();
+
+    let msg = ExecuteMsg::Rebalance(RebalanceParams {
+      decollateralize: vec![Coin::new(10, "u/uumee")],
+      collateralize: vec![Coin::new(5, "u/uatom"), Coin::new(7, "u/uosmo")],
+    });
+    let res = execute(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+    let expected: Vec<CosmosMsg<StructUmeeMsg>> = vec![
+      StructUmeeMsg::from(UmeeMsgLeverage::Decollateralize(
+        DecollateralizeParams::new(Coin::new(10, "u/uumee")),
+      ))
+      .into(),
+      StructUmeeMsg::from(UmeeMsgLeverage::Collateralize(CollateralizeParams::new(
+        Coin::new(5, "u/uatom"),
+      )))
+      .into(),
+      StructUmeeMsg::from(UmeeMsgLeverage::Collateralize(CollateralizeParams::new(
+        Coin::new(7, "u/uosmo"),
+      )))
+      .into(),
+    ];
+    let msgs: Vec<CosmosMsg<StructUmeeMsg>> = res.messages.into_iter().map(|m| m.msg).collect();
+    assert_eq!(expected, msgs);
+
+    let msg = ExecuteMsg::Rebalance(RebalanceParams {
+      decollateralize: vec![Coin::new(10, "u/uumee")],
+      collateralize: vec![Coin::new(5, "u/uumee")],
+    });
+    match execute(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg) {
+      Err(ContractError::CustomError { .. }) => {}
+      _ => panic!("Must return custom error"),
     }
-    UmeeQueryOracle::OracleParameters(oracle_parameters_params) => {
-      to_json_binary(&query_oracle_parameters(deps, oracle_parameters_params)?)
+  }
+
+  #[test]
+  fn instantiate_twice() {
+    let mut deps = mock_umee_dependencies();
+
+    let msg = InstantiateMsg {
+      require_umee: None,
+      chain_queries: None,
+      check_denoms: None,
+    };
+    instantiate(
+      deps.as_mut(),
+      mock_env(),
+      mock_info("creator", &[]),
+      msg.clone(),
+    )
+    .unwrap();
+    match instantiate(deps.as_mut(), mock_env(), mock_info("intruder", &[]), msg) {
+      Err(ContractError::AlreadyInstantiated {}) => {}
+      _ => panic!("Must return already instantiated error"),
     }
-    UmeeQueryOracle::Medians(median_params) => to_json_binary(&query_medians(deps, median_params)?),
-    UmeeQueryOracle::MedianDeviations(median_deviations_params) => {
-      to_json_binary(&query_median_deviations(deps, median_deviations_params)?)
+
+    let res = query(deps.as_ref(), mock_env(), QueryMsg::GetOwner {}).unwrap();
+    let value: OwnerResponse = from_json(&res).unwrap();
+    assert_eq!("creator", value.owner);
+  }
+
+  #[test]
+  fn liquidatable_borrowers() {
+    let mut deps = mock_umee_dependencies();
+    mock_chain_response(
+      &mut deps,
+      StructUmeeQuery::liquidation_targets(LiquidationTargetsParams {}),
+      &LiquidationTargetsResponse {
+        targets: vec![
+          String::from("umee1c"),
+          String::from("umee1a"),
+          String::from("umee1b"),
+        ],
+      },
+    );
+    for (address, borrowed) in [
+      ("umee1a", Coin::new(10, "uumee")),
+      ("umee1b", Coin::new(20, "uatom")),
+      ("umee1c", Coin::new(30, "uumee")),
+    ] {
+      mock_chain_response(
+        &mut deps,
+        StructUmeeQuery::account_balances(AccountBalancesParams::new(Addr::unchecked(address))),
+        &AccountBalancesResponse {
+          supplied: vec![],
+          collateral: vec![],
+          borrowed: vec![borrowed],
+        },
+      );
     }
+
+    let query_page = |start_after: Option<&str>| -> LiquidatableBorrowersResponse {
+      let msg = QueryMsg::Umee(Box::new(UmeeQuery::Leverage(
+        UmeeQueryLeverage::LiquidatableBorrowers(LiquidatableBorrowersParams {
+          denom: String::from("uumee"),
+          limit: Some(1),
+          start_after: start_after.map(Addr::unchecked),
+        }),
+      )));
+      from_json(query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap()
+    };
+    let page = query_page(None);
+    assert_eq!(
+      vec![(Addr::unchecked("umee1a"), Coin::new(10, "uumee"))],
+      page.borrowers
+    );
+    assert_eq!(Some(Addr::unchecked("umee1a")), page.start_after);
+    // a page inspects at most limit targets, even without a borrower
+    let page = query_page(Some("umee1a"));
+    assert!(page.borrowers.is_empty());
+    assert_eq!(Some(Addr::unchecked("umee1b")), page.start_after);
+    let page = query_page(Some("umee1b"));
+    assert_eq!(
+      vec![(Addr::unchecked("umee1c"), Coin::new(30, "uumee"))],
+      page.borrowers
+    );
+    assert_eq!(None, page.start_after);
+  }
+
+  const TOKEN_JSON: &str = r#"{"base_denom":"uumee","reserve_factor":"0.1","collateral_weight":"0.35","liquidation_threshold":"0.5","base_borrow_rate":"0.02","kink_borrow_rate":"0.15","max_borrow_rate":"1.5","kink_utilization":"0.7","liquidation_incentive":"0.1","symbol_denom":"UMEE","exponent":6,"enable_msg_supply":true,"enable_msg_borrow":true,"blacklist":false,"max_collateral_share":"1","max_supply_utilization":"0.9","min_collateral_liquidity":"0.3","max_supply":"100000000000","historic_medians":24}"#;
+
+  fn mock_registry(deps: &mut UmeeMockDeps, tokens: &[&str]) {
+    let json = format!(r#"{{"registry":[{}]}}"#, tokens.join(","));
+    mock_custom_response(
+      deps,
+      StructUmeeQuery::registered_tokens(RegisteredTokensParams {}),
+      Binary::from(json.as_bytes()),
+    );
+  }
+
+  #[test]
+  fn registered_tokens_size_guard() {
+    let mut deps = mock_umee_dependencies();
+    mock_registry(&mut deps, &[TOKEN_JSON]);
+    let msg = QueryMsg::RegisteredTokens(RegisteredTokensParams {});
+    let res = query(deps.as_ref(), mock_env(), msg.clone()).unwrap();
+    let value: RegisteredTokensResponse = from_json(res).unwrap();
+    assert_eq!(1, value.registry.len());
+
+    let tokens = vec![TOKEN_JSON; MAX_REGISTERED_TOKENS_RESPONSE_BYTES / TOKEN_JSON.len() + 1];
+    mock_registry(&mut deps, &tokens);
+    let err = query(deps.as_ref(), mock_env(), msg).unwrap_err();
+    assert!(err.to_string().contains("pagination"));
+  }
+
+  #[test]
+  fn exchange_rates_batch() {
+    let mut deps = mock_umee_dependencies();
+    mock_custom_response(
+      &mut deps,
+      StructUmeeQuery::exchange_rates(ExchangeRatesParams::new("UMEE")),
+      Binary::from(br#"{"exchange_rates":[{"denom":"UMEE","amount":"0.01"}]}"#.as_slice()),
+    );
+
+    let msg = QueryMsg::Umee(Box::new(UmeeQuery::Oracle(
+      UmeeQueryOracle::ExchangeRatesBatch(ExchangeRatesBatchParams {
+        denoms: vec![String::from("UMEE"), String::from("ATOM")],
+      }),
+    )));
+    let res = query(deps.as_ref(), mock_env(), msg).unwrap();
+    let value: ExchangeRatesBatchResponse = from_json(&res).unwrap();
+    assert_eq!(1, value.rates.len());
+    assert_eq!("UMEE", value.rates[0].denom);
+    assert_eq!(vec![String::from("ATOM")], value.missing);
+  }
+
+  #[test]
+  fn special_borrow_limit() {
+    let mut deps = mock_umee_dependencies();
+    mock_account_summary(&mut deps, "borrower", "730", "1150");
+
+    let msg = QueryMsg::Umee(Box::new(UmeeQuery::Leverage(
+      UmeeQueryLeverage::SpecialBorrowLimit(SpecialBorrowLimitParams::new(Addr::unchecked(
+        "borrower",
+      ))),
+    )));
+    let res = query(deps.as_ref(), mock_env(), msg).unwrap();
+    let value: SpecialBorrowLimitResponse = from_json(&res).unwrap();
+    assert_eq!(Decimal256::from_str("1150").unwrap(), value.borrow_limit);
+  }
+
+  #[test]
+  fn fresh_account_balances() {
+    let mut deps = mock_umee_dependencies();
+    mock_custom_response(
+      &mut deps,
+      StructUmeeQuery::account_balances(AccountBalancesParams {
+        address: Addr::unchecked("fresh"),
+      }),
+      Binary::from(b"{}".as_slice()),
+    );
+    mock_custom_response(
+      &mut deps,
+      StructUmeeQuery::account_summary(AccountSummaryParams {
+        address: Addr::unchecked("fresh"),
+      }),
+      Binary::default(),
+    );
+
+    let msg = QueryMsg::Umee(Box::new(UmeeQuery::Leverage(
+      UmeeQueryLeverage::AccountBalances(AccountBalancesParams {
+        address: Addr::unchecked("fresh"),
+      }),
+    )));
+    let res = query(deps.as_ref(), mock_env(), msg).unwrap();
+    let value: AccountBalancesResponse = from_json(&res).unwrap();
+    assert_eq!(AccountBalancesResponse::default(), value);
+
+    let msg = QueryMsg::BorrowLimitUsed {
+      address: Addr::unchecked("fresh"),
+    };
+    let res = query(deps.as_ref(), mock_env(), msg).unwrap();
+    let value: BorrowLimitUsedResponse = from_json(&res).unwrap();
+    assert_eq!(Decimal256::zero(), value.used);
+    assert_eq!(Decimal256::zero(), value.limit);
+  }
+
+  #[test]
+  fn interest_rate_model() {
+    let mut deps = mock_umee_dependencies();
+    mock_registry(&mut deps, &[TOKEN_JSON]);
+
+    let msg = QueryMsg::Umee(Box::new(UmeeQuery::Leverage(
+      UmeeQueryLeverage::InterestRateModel(InterestRateModelParams::new("uumee")),
+    )));
+    let res = query(deps.as_ref(), mock_env(), msg).unwrap();
+    let value: InterestRateModelResponse = from_json(&res).unwrap();
+    assert_eq!(Decimal::from_str("0.02").unwrap(), value.base_rate);
+    assert_eq!(Decimal::from_str("0.7").unwrap(), value.kink_utilization);
+    assert_eq!(Decimal::from_str("0.15").unwrap(), value.kink_rate);
+    assert_eq!(Decimal::from_str("1.5").unwrap(), value.max_rate);
+
+    let msg = QueryMsg::Umee(Box::new(UmeeQuery::Leverage(
+      UmeeQueryLeverage::InterestRateModel(InterestRateModelParams::new("uatom")),
+    )));
+    query(deps.as_ref(), mock_env(), msg).unwrap_err();
+  }
+
+  // mock_contract_position sets the leverage and bank balances of the contract
+  fn mock_contract_position(
+    deps: &mut UmeeMockDeps,
+    borrowed: u128,
+    collateral: u128,
+    bank: &[Coin],
+  ) {
+    let contract = mock_env().contract.address;
+    deps
+      .querier
+      .base
+      .update_balance(contract.clone(), bank.to_vec());
+    mock_chain_response(
+      deps,
+      StructUmeeQuery::account_balances(AccountBalancesParams::new(contract)),
+      &AccountBalancesResponse {
+        supplied: vec![],
+        collateral: coins(collateral, "u/uumee"),
+        borrowed: coins(borrowed, "uumee"),
+      },
+    );
+  }
+
+  fn unwind_reply(deps: &mut UmeeMockDeps, id: u64) -> Response<StructUmeeMsg> {
+    let reply_msg = Reply {
+      id,
+      result: SubMsgResult::Ok(SubMsgResponse {
+        events: vec![],
+        data: None,
+      }),
+    };
+    reply(deps.as_mut(), mock_env(), reply_msg).unwrap()
   }
-}
 
-// query_registered_tokens receives the get all registered tokens
-// query params and creates an query request to the native modules
-// with query_chain wrapping the response to the actual
-// RegisteredTokensResponse struct
-fn query_registered_tokens(
-  deps: Deps,
-  registered_tokens_params: RegisteredTokensParams,
-) -> StdResult<RegisteredTokensResponse> {
-  let request = QueryRequest::Custom(StructUmeeQuery::registered_tokens(registered_tokens_params));
+  #[test]
+  fn unwind() {
+    let mut deps = mock_umee_dependencies();
+    let msg = ExecuteMsg::Unwind(UnwindParams {
+      account: Addr::unchecked("someone"),
+      repay_denom: String::from("uumee"),
+    });
+    execute(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap_err();
+
+    mock_contract_position(&mut deps, 500, 900, &coins(300, "uumee"));
+    let msg = ExecuteMsg::Unwind(UnwindParams {
+      account: mock_env().contract.address,
+      repay_denom: String::from("uumee"),
+    });
+    let res = execute(
+      deps.as_mut(),
+      mock_env(),
+      mock_info("creator", &[]),
+      msg.clone(),
+    )
+    .unwrap();
+    let repay = StructUmeeMsg::from(UmeeMsgLeverage::Repay(RepayParams::new(Coin::new(
+      300, "uumee",
+    ))));
+    assert_eq!(REPLY_UNWIND_REPAY, res.messages[0].id);
+    assert_eq!(CosmosMsg::Custom(repay), res.messages[0].msg);
+    execute(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap_err();
+
+    // 200uumee of debt is left, the borrow limit only frees 400u/uumee
+    mock_contract_position(&mut deps, 200, 900, &[]);
+    mock_chain_response(
+      &mut deps,
+      StructUmeeQuery::max_withdraw_params(MaxWithdrawParams::new(
+        mock_env().contract.address,
+        "uumee",
+      )),
+      &MaxWithdrawResponse {
+        u_tokens: Coin::new(400, "u/uumee"),
+        tokens: Coin::new(400, "uumee"),
+      },
+    );
+    let res = unwind_reply(&mut deps, REPLY_UNWIND_REPAY);
+    let decollateralize = StructUmeeMsg::from(UmeeMsgLeverage::Decollateralize(
+      DecollateralizeParams::new(Coin::new(400, "u/uumee")),
+    ));
+    assert_eq!(REPLY_UNWIND_DECOLLATERALIZE, res.messages[0].id);
+    assert_eq!(CosmosMsg::Custom(decollateralize), res.messages[0].msg);
+
+    mock_contract_position(&mut deps, 200, 500, &coins(400, "u/uumee"));
+    let res = unwind_reply(&mut deps, REPLY_UNWIND_DECOLLATERALIZE);
+    let withdraw = StructUmeeMsg::from(UmeeMsgLeverage::Withdraw(WithdrawParams::new(Coin::new(
+      400, "u/uumee",
+    ))));
+    assert_eq!(REPLY_UNWIND_WITHDRAW, res.messages[0].id);
+    assert_eq!(CosmosMsg::Custom(withdraw), res.messages[0].msg);
+
+    let res = unwind_reply(&mut deps, REPLY_UNWIND_WITHDRAW);
+    assert!(res.messages.is_empty());
+    assert_eq!("done", res.events[0].attributes[0].value);
+    assert_eq!(None, UNWIND.may_load(&deps.storage).unwrap());
+
+    // without debt the whole collateral is decollateralized
+    mock_contract_position(&mut deps, 0, 900, &[]);
+    UNWIND
+      .save(deps.as_mut().storage, &String::from("uumee"))
+      .unwrap();
+    let res = unwind_reply(&mut deps, REPLY_UNWIND_REPAY);
+    let decollateralize = StructUmeeMsg::from(UmeeMsgLeverage::Decollateralize(
+      DecollateralizeParams::new(Coin::new(900, "u/uumee")),
+    ));
+    assert_eq!(CosmosMsg::Custom(decollateralize), res.messages[0].msg);
+  }
 
-  let registered_tokens_response: RegisteredTokensResponse;
-  match query_chain(deps, &request) {
-    Err(err) => {
-      return Err(err);
-    }
-    Ok(binary) => {
-      match from_json::<RegisteredTokensResponse>(&binary) {
-        Err(err) => {
-          return Err(err);
-        }
-        Ok(response) => registered_tokens_response = response,
-      };
-    }
+  #[test]
+  fn invalid_denom_prefix() {
+    let mut deps = mock_umee_dependencies();
+    let info = mock_info("creator", &[]);
+
+    let msg = ExecuteMsg::Supply(SupplyParams::new(Coin::new(1000, "u/uumee")));
+    let err = execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap_err();
+    assert!(matches!(err, ContractError::InvalidDenom { .. }));
+
+    let msg = ExecuteMsg::Umee(UmeeMsg::Leverage(UmeeMsgLeverage::Collateralize(
+      CollateralizeParams::new(Coin::new(1000, "uumee")),
+    )));
+    let err = execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap_err();
+    assert!(matches!(err, ContractError::InvalidDenom { .. }));
+
+    let msg = ExecuteMsg::Rebalance(RebalanceParams {
+      decollateralize: coins(10, "u/uatom"),
+      collateralize: coins(10, "uumee"),
+    });
+    let err = execute(deps.as_mut(), mock_env(), info, msg).unwrap_err();
+    assert!(matches!(err, ContractError::InvalidDenom { .. }));
   }
 
-  Ok(registered_tokens_response)
-}
+  #[test]
+  fn simulate_liquidation() {
+    let mut deps = mock_umee_dependencies();
+    let atom = TOKEN_JSON.replace("uumee", "uatom");
+    mock_registry(&mut deps, &[TOKEN_JSON, &atom]);
+    mock_market_summary(&mut deps, "uumee", "1.0");
+    mock_market_summary(&mut deps, "uatom", "1.1");
+    mock_chain_response(
+      &mut deps,
+      StructUmeeQuery::account_balances(AccountBalancesParams::new(Addr::unchecked("borrower"))),
+      &AccountBalancesResponse {
+        supplied: vec![],
+        collateral: coins(500, "u/uatom"),
+        borrowed: coins(1000, "uumee"),
+      },
+    );
+
+    let simulate = |deps: &UmeeMockDeps, repay: u128, reward_denom: &str| {
+      let msg = QueryMsg::Umee(Box::new(UmeeQuery::Leverage(
+        UmeeQueryLeverage::SimulateLiquidation(SimulateLiquidationParams {
+          borrower: Addr::unchecked("borrower"),
+          repay: Coin::new(repay, "uumee"),
+          reward_denom: String::from(reward_denom),
+        }),
+      )));
+      let res = query(deps.as_ref(), mock_env(), msg).unwrap();
+      from_json::<SimulateLiquidationResponse>(&res).unwrap()
+    };
 
-// query_leverage_parameters creates an query request to the native modules
-// with query_chain wrapping the response to the actual
-// LeverageParametersResponse struct
-fn query_leverage_parameters(
-  deps: Deps,
-  leverage_parameters_params: LeverageParametersParams,
-) -> StdResult<LeverageParametersResponse> {
-  let request = QueryRequest::Custom(StructUmeeQuery::leverage_parameters(
-    leverage_parameters_params,
-  ));
+    // repaying 110 tokens earns 121 uatom with the 10% incentive,
+    // which are 110 uTokens at the 1.1 exchange rate
+    let value = simulate(&deps, 110, "u/uatom");
+    assert_eq!(Coin::new(110, "uumee"), value.repay);
+    assert_eq!(Coin::new(110, "u/uatom"), value.reward);
+    let value = simulate(&deps, 110, "uatom");
+    assert_eq!(Coin::new(121, "uatom"), value.reward);
+
+    // the 500 uTokens of collateral only cover 500 repaid tokens
+    let value = simulate(&deps, 5000, "u/uatom");
+    assert_eq!(Coin::new(500, "uumee"), value.repay);
+    assert_eq!(Coin::new(500, "u/uatom"), value.reward);
+  }
 
-  let leverage_parameters_response: LeverageParametersResponse;
-  match query_chain(deps, &request) {
-    Err(err) => {
-      return Err(err);
-    }
-    Ok(binary) => {
-      match from_json::<LeverageParametersResponse>(&binary) {
-        Err(err) => {
-          return Err(err);
-        }
-        Ok(response) => leverage_parameters_response = response,
+  #[test]
+  fn is_liquidatable() {
+    let mut deps = mock_umee_dependencies();
+    // the liquidation threshold of the mocked summaries is 1200
+    mock_account_summary(&mut deps, "healthy", "750", "1000");
+    mock_account_summary(&mut deps, "underwater", "1500", "1000");
+    mock_account_summary(&mut deps, "supplier", "0", "1000");
+
+    let is_liquidatable = |deps: &UmeeMockDeps, address: &str| {
+      let msg = QueryMsg::IsLiquidatable {
+        address: Addr::unchecked(address),
       };
-    }
-  }
+      let res = query(deps.as_ref(), mock_env(), msg).unwrap();
+      from_json::<IsLiquidatableResponse>(&res).unwrap()
+    };
 
-  Ok(leverage_parameters_response)
-}
+    let value = is_liquidatable(&deps, "healthy");
+    assert!(!value.liquidatable);
+    assert_eq!(
+      Some(Decimal256::from_str("1.6").unwrap()),
+      value.health_factor
+    );
+    let value = is_liquidatable(&deps, "underwater");
+    assert!(value.liquidatable);
+    assert_eq!(
+      Some(Decimal256::from_str("0.8").unwrap()),
+      value.health_factor
+    );
+    let value = is_liquidatable(&deps, "supplier");
+    assert!(!value.liquidatable);
+    assert_eq!(None, value.health_factor);
+
+    // an account without any position answers with an empty payload
+    mock_custom_response(
+      &mut deps,
+      StructUmeeQuery::account_summary(AccountSummaryParams::new(Addr::unchecked("fresh"))),
+      Binary::default(),
+    );
+    let value = is_liquidatable(&deps, "fresh");
+    assert!(!value.liquidatable);
+    assert_eq!(None, value.health_factor);
+    let msg = QueryMsg::BorrowLimitUsed {
+      address: Addr::unchecked("fresh"),
+    };
+    let res = query(deps.as_ref(), mock_env(), msg).unwrap();
+    let value: BorrowLimitUsedResponse = from_json(&res).unwrap();
+    assert_eq!(Decimal256::zero(), value.used);
+  }
 
-// query_account_balances creates an query request to the native modules
-// with query_chain wrapping the response to the actual
-// AccountBalancesResponse struct.
-fn query_account_balances(
-  deps: Deps,
-  account_balances_params: AccountBalancesParams,
-) -> StdResult<AccountBalancesResponse> {
-  let request = QueryRequest::Custom(StructUmeeQuery::account_balances(account_balances_params));
+  #[test]
+  fn max_withdraw_reply() {
+    let mut deps = mock_umee_dependencies();
+    mock_contract_position(&mut deps, 0, 900, &coins(100, "u/uumee"));
+
+    let msg = ExecuteMsg::Umee(UmeeMsg::Leverage(UmeeMsgLeverage::MaxWithdraw(
+      MsgMaxWithdrawParams::new("uumee"),
+    )));
+    let res = execute(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+    assert_eq!(REPLY_MAX_WITHDRAW, res.messages[0].id);
+
+    // the 100u/uumee of the bank balance are burned along with 500
+    // decollateralized ones
+    mock_contract_position(&mut deps, 0, 400, &coins(660, "uumee"));
+    let reply_msg = module_reply(
+      REPLY_MAX_WITHDRAW,
+      &[Coin::new(600, "u/uumee"), Coin::new(660, "uumee")],
+    );
+    let res = reply(deps.as_mut(), mock_env(), reply_msg.clone()).unwrap();
+    let value: MaxWithdrawReplyResponse = from_json(res.data.unwrap()).unwrap();
+    assert_eq!(Coin::new(600, "u/uumee"), value.withdrawn);
+    assert_eq!(Coin::new(660, "uumee"), value.received);
+    assert_eq!(Coin::new(500, "u/uumee"), value.collateral_released);
+    assert_eq!(
+      None,
+      MAX_WITHDRAW_COLLATERAL.may_load(&deps.storage).unwrap()
+    );
+
+    // the max withdraw never fails on its report
+    let res = reply(deps.as_mut(), mock_env(), reply_msg).unwrap();
+    assert_eq!(None, res.data);
+    assert!(res.attributes.iter().any(|attr| attr.key == "report_error"));
+  }
 
-  let account_balances_response: AccountBalancesResponse;
-  match query_chain(deps, &request) {
-    Err(err) => {
-      return Err(err);
-    }
-    Ok(binary) => {
-      match from_json::<AccountBalancesResponse>(&binary) {
-        Err(err) => {
-          return Err(err);
-        }
-        Ok(response) => account_balances_response = response,
-      };
+  #[test]
+  fn chain_query_allowlist() {
+    let mut deps = mock_umee_dependencies();
+    let info = mock_info("creator", &[]);
+    let msg = InstantiateMsg {
+      require_umee: None,
+      chain_queries: Some(vec![ChainQueryKind::Stargate]),
+      check_denoms: None,
+    };
+    if !cfg!(feature = "stargate-queries") {
+      instantiate(deps.as_mut(), mock_env(), info.clone(), msg).unwrap_err();
     }
+
+    let msg = InstantiateMsg {
+      require_umee: None,
+      chain_queries: Some(vec![ChainQueryKind::Custom]),
+      check_denoms: None,
+    };
+    instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    let custom = QueryMsg::Chain(Box::new(QueryRequest::Custom(
+      StructUmeeQuery::oracle_parameters(OracleParametersParams {}),
+    )));
+    query(deps.as_ref(), mock_env(), custom).unwrap();
+
+    let bank = QueryMsg::Chain(Box::new(QueryRequest::Bank(BankQuery::AllBalances {
+      address: String::from("creator"),
+    })));
+    let err = query(deps.as_ref(), mock_env(), bank).unwrap_err();
+    assert!(err.to_string().contains("not allowed"));
   }
 
-  Ok(account_balances_response)
-}
+  #[test]
+  fn supply_then_collateralize() {
+    let mut deps = mock_umee_dependencies();
+    let supply_then_collateralize = |deps: &mut UmeeMockDeps| {
+      let msg = ExecuteMsg::SupplyThenCollateralize(SupplyParams::new(Coin::new(1000, "uumee")));
+      let res = execute(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+      assert_eq!(REPLY_SUPPLY_THEN_COLLATERALIZE, res.messages[0].id);
+    };
+    let reply_msg = module_reply(
+      REPLY_SUPPLY_THEN_COLLATERALIZE,
+      &[Coin::new(909, "u/uumee")],
+    );
+
+    supply_then_collateralize(&mut deps);
+    mock_contract_balance(&mut deps, &coins(909, "u/uumee"));
+    let res = reply(deps.as_mut(), mock_env(), reply_msg.clone()).unwrap();
+    let collateralize = StructUmeeMsg::from(UmeeMsgLeverage::Collateralize(
+      CollateralizeParams::new(Coin::new(909, "u/uumee")),
+    ));
+    assert_eq!(CosmosMsg::Custom(collateralize), res.messages[0].msg);
+
+    // nothing to collateralize without any uToken minted
+    supply_then_collateralize(&mut deps);
+    let err = reply(deps.as_mut(), mock_env(), reply_msg).unwrap_err();
+    assert!(err.to_string().contains("minted no u/uumee"), "{}", err);
+  }
 
-// query_account_summary creates an query request to the native modules
-// with query_chain wrapping the response to the actual
-// AccountsummaryResponse struct.
-fn query_account_summary(
-  deps: Deps,
-  account_summary_params: AccountSummaryParams,
-) -> StdResult<AccountSummaryParams> {
-  let request = QueryRequest::Custom(StructUmeeQuery::account_summary(account_summary_params));
+  #[test]
+  fn exchange_rates_aliases() {
+    let msg: QueryMsg = from_json(br#"{"exchange_rates":{"denom":"UMEE"}}"#).unwrap();
+    let native: QueryMsg = from_json(br#"{"get_exchange_rate_base":{"denom":"UMEE"}}"#).unwrap();
+    assert_eq!(msg, native);
+    assert_eq!(
+      QueryMsg::ExchangeRates(ExchangeRatesParams::new("UMEE")),
+      msg
+    );
+  }
 
-  let account_summary_response: AccountSummaryParams;
-  match query_chain(deps, &request) {
-    Err(err) => {
-      return Err(err);
-    }
-    Ok(binary) => {
-      match from_json::<AccountSummaryParams>(&binary) {
-        Err(err) => {
-          return Err(err);
-        }
-        Ok(response) => account_summary_response = response,
-      };
-    }
+  #[test]
+  fn total_value_locked() {
+    let mut deps = mock_umee_dependencies();
+    let atom = TOKEN_JSON.replace("uumee", "uatom");
+    mock_registry(&mut deps, &[TOKEN_JSON, &atom]);
+    // each market supplies 1000, borrows 500 and collateralizes 800 uTokens
+    // priced at 0.01 USD for 10^6 base tokens
+    mock_market_summary(&mut deps, "uumee", "1.0");
+    mock_market_summary(&mut deps, "uatom", "1.5");
+
+    let res = query(deps.as_ref(), mock_env(), QueryMsg::TotalValueLocked {}).unwrap();
+    let value: TvlResponse = from_json(&res).unwrap();
+    assert_eq!(
+      Decimal256::from_str("0.00002").unwrap(),
+      value.total_supplied_value
+    );
+    assert_eq!(
+      Decimal256::from_str("0.00001").unwrap(),
+      value.total_borrowed_value
+    );
+    assert_eq!(
+      Decimal256::from_str("0.00002").unwrap(),
+      value.total_collateral_value
+    );
+    assert_eq!("0.00", value.total_supplied_display);
   }
 
-  Ok(account_summary_response)
-}
+  #[test]
+  fn collateral_breakdown() {
+    let mut deps = mock_umee_dependencies();
+    mock_chain_response(
+      &mut deps,
+      StructUmeeQuery::account_balances(AccountBalancesParams::new(Addr::unchecked("supplier"))),
+      &AccountBalancesResponse {
+        supplied: coins(1000, "uumee"),
+        collateral: coins(900, "u/uumee"),
+        borrowed: vec![],
+      },
+    );
+    mock_custom_response(
+      &mut deps,
+      StructUmeeQuery::account_bonds(AccountBondsParams {
+        address: String::from("supplier"),
+      }),
+      Binary::from(br#"{"bonded":[{"denom":"u/uumee","amount":"600"}]}"#.as_slice()),
+    );
+
+    let msg = QueryMsg::CollateralBreakdown {
+      address: Addr::unchecked("supplier"),
+    };
+    let res = query(deps.as_ref(), mock_env(), msg).unwrap();
+    let value: CollateralBreakdownResponse = from_json(&res).unwrap();
+    assert_eq!(coins(900, "u/uumee"), value.collateral);
+    assert_eq!(coins(600, "u/uumee"), value.bonded);
+  }
 
-// query_liquidation_targets creates an query request to the native modules
-// with query_chain wrapping the response to the actual
-// LiquidationTargetsResponse struct.
-fn query_liquidation_targets(
-  deps: Deps,
-  liquidation_targets_params: LiquidationTargetsParams,
-) -> StdResult<LiquidationTargetsResponse> {
-  let request = QueryRequest::Custom(StructUmeeQuery::liquidation_targets(
-    liquidation_targets_params,
-  ));
+  #[test]
+  fn truncated_response_length() {
+    let mut deps = mock_umee_dependencies();
+    mock_registry(&mut deps, &[TOKEN_JSON]);
+    mock_custom_response(
+      &mut deps,
+      StructUmeeQuery::market_summary(MarketSummaryParams::new("uumee")),
+      Binary::from(br#"{"symbol_denom":"UM"#.as_slice()),
+    );
+
+    let msg = QueryMsg::Umee(Box::new(UmeeQuery::Leverage(
+      UmeeQueryLeverage::AvailableWithdraw(AvailableWithdrawParams::new("uumee")),
+    )));
+    let err = query(deps.as_ref(), mock_env(), msg).unwrap_err();
+    assert!(err.to_string().contains("(19 bytes response)"), "{}", err);
+  }
 
-  let liquidation_targets_response: LiquidationTargetsResponse;
-  match query_chain(deps, &request) {
-    Err(err) => {
-      return Err(err);
-    }
-    Ok(binary) => {
-      match from_json::<LiquidationTargetsResponse>(&binary) {
-        Err(err) => {
-          return Err(err);
-        }
-        Ok(response) => liquidation_targets_response = response,
-      };
+  #[test]
+  fn setup_feeder() {
+    const FEEDER: &str = "umee1qgpqyqszqgpqyqszqgpqyqszqgpqyqsz3p6yzc";
+    let mut deps = mock_umee_dependencies();
+    let msg = InstantiateMsg {
+      require_umee: None,
+      chain_queries: None,
+      check_denoms: None,
+    };
+    instantiate(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+
+    let msg = ExecuteMsg::SetupFeeder(SetupFeederParams {
+      operator: mock_env().contract.address,
+      feeder: Addr::unchecked(FEEDER),
+    });
+    match execute(
+      deps.as_mut(),
+      mock_env(),
+      mock_info("anyone", &[]),
+      msg.clone(),
+    ) {
+      Err(ContractError::Unauthorized {}) => {}
+      _ => panic!("Must return unauthorized error"),
     }
+
+    let res = execute(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+    let consent = DelegateFeedConsentParams {
+      operator: mock_env().contract.address,
+      delegate: Addr::unchecked(FEEDER),
+    };
+    assert_eq!(CosmosMsg::from(consent), res.messages[0].msg);
+
+    // the former set_feeder name decodes to the same message
+    let legacy: ExecuteMsg = from_json(format!(
+      r#"{{"set_feeder":{{"operator":"{}","feeder":"{}"}}}}"#,
+      mock_env().contract.address,
+      FEEDER
+    ))
+    .unwrap();
+    let msg = ExecuteMsg::SetupFeeder(SetupFeederParams {
+      operator: mock_env().contract.address,
+      feeder: Addr::unchecked(FEEDER),
+    });
+    assert_eq!(msg, legacy);
   }
 
-  Ok(liquidation_targets_response)
-}
+  #[test]
+  fn caps_usage() {
+    let mut deps = mock_umee_dependencies();
+    let token = TOKEN_JSON.replace(r#""max_supply":"100000000000""#, r#""max_supply":"4000""#);
+    mock_registry(&mut deps, &[&token]);
+    mock_market_summary(&mut deps, "uumee", "1.0");
+
+    let msg = QueryMsg::Umee(Box::new(UmeeQuery::Leverage(UmeeQueryLeverage::CapsUsage(
+      CapsUsageParams::new("uumee"),
+    ))));
+    let res = query(deps.as_ref(), mock_env(), msg).unwrap();
+    let value: CapsUsageResponse = from_json(&res).unwrap();
+    // 1000 supplied out of 4000 and 500 borrowed out of 1000 * 0.9
+    assert_eq!(Uint128::new(4000), value.supply_cap);
+    assert_eq!(Decimal::from_str("0.25").unwrap(), value.supply_used);
+    assert_eq!(Uint128::new(900), value.borrow_cap);
+    assert_eq!(
+      Decimal::from_str("0.555555555555555555").unwrap(),
+      value.borrow_used
+    );
+  }
 
-fn query_bad_debts(deps: Deps, bad_debts_params: BadDebtsParams) -> StdResult<BadDebtsResponse> {
-  let request = QueryRequest::Custom(StructUmeeQuery::bad_debts_parameters(bad_debts_params));
+  #[test]
+  fn accept_list() {
+    let deps = mock_umee_dependencies();
+
+    let msg = QueryMsg::Umee(Box::new(UmeeQuery::Oracle(UmeeQueryOracle::AcceptList(
+      AcceptListParams {},
+    ))));
+    let res = query(deps.as_ref(), mock_env(), msg).unwrap();
+    let value: AcceptListResponse = from_json(&res).unwrap();
+    assert_eq!(1, value.denoms.len());
+    let umee = value.find("umee").unwrap();
+    assert_eq!("uumee", umee.base_denom);
+    assert_eq!(6, umee.exponent);
+    assert_eq!(Some(umee), value.find("uumee"));
+    assert_eq!(None, value.find("uatom"));
+  }
 
-  let bad_debts_response: BadDebtsResponse;
-  match query_chain(deps, &request) {
-    Err(err) => {
-      return Err(err);
-    }
-    Ok(binary) => {
-      match from_json::<BadDebtsResponse>(&binary) {
-        Err(err) => {
-          return Err(err);
-        }
-        Ok(response) => bad_debts_response = response,
-      };
+  #[test]
+  fn market_summary_apy_sanity_check() {
+    let mut deps = mock_umee_dependencies();
+    mock_registry(&mut deps, &[TOKEN_JSON]);
+    let query_market = |deps: &UmeeMockDeps| {
+      query(
+        deps.as_ref(),
+        mock_env(),
+        QueryMsg::Umee(Box::new(UmeeQuery::Leverage(
+          UmeeQueryLeverage::MarketSummary(MarketSummaryParams {
+            denom: String::from("uumee"),
+          }),
+        ))),
+      )
+    };
+    mock_market_summary(&mut deps, "uumee", "1");
+    query_market(&deps).unwrap();
+
+    for (apys, expected) in [
+      (r#""supply_apy":"100","borrow_apy":"100""#, None),
+      (
+        r#""supply_apy":"0.05","borrow_apy":"100.1""#,
+        Some("uumee borrow apy 100.1 is above the 100 ceiling"),
+      ),
+      (
+        r#""supply_apy":"-0.05","borrow_apy":"0.1""#,
+        Some("bytes response"),
+      ),
+    ] {
+      let json = format!(
+        r#"{{"symbol_denom":"UMEE","exponent":6,"oracle_price":"0.01","utoken_exchange_rate":"1",{},"supplied":"1000","reserved":"10","collateral":"800","borrowed":"500","liquidity":"490","maximum_borrow":"0","maximum_collateral":"0","minimum_liquidity":"100","utoken_supply":"900","available_borrow":"390","available_withdraw":"390","available_collateralize":"0"}}"#,
+        apys
+      );
+      mock_custom_response(
+        &mut deps,
+        StructUmeeQuery::market_summary(MarketSummaryParams {
+          denom: String::from("uumee"),
+        }),
+        Binary::from(json.as_bytes()),
+      );
+      match (query_market(&deps), expected) {
+        (Ok(_), None) => {}
+        (Err(err), Some(msg)) => assert!(err.to_string().contains(msg), "{}", err),
+        (res, _) => panic!("unexpected {:?} for {}", res, apys),
+      }
     }
   }
 
-  Ok(bad_debts_response)
-}
+  #[test]
+  fn batch_account_summary() {
+    let mut deps = mock_umee_dependencies();
+    mock_account_summary(&mut deps, "borrower", "730", "1000");
+    mock_custom_response(
+      &mut deps,
+      StructUmeeQuery::account_summary(AccountSummaryParams::new(Addr::unchecked("fresh"))),
+      Binary::default(),
+    );
+
+    let batch = |addresses: Vec<Addr>| {
+      QueryMsg::Umee(Box::new(UmeeQuery::Leverage(
+        UmeeQueryLeverage::BatchAccountSummary(BatchAccountSummaryParams { addresses }),
+      )))
+    };
+    let msg = batch(vec![Addr::unchecked("borrower"), Addr::unchecked("fresh")]);
+    let res = query(deps.as_ref(), mock_env(), msg).unwrap();
+    let value: BatchAccountSummaryResponse = from_json(&res).unwrap();
+    assert_eq!(1, value.summaries.len());
+    assert_eq!(Addr::unchecked("borrower"), value.summaries[0].0);
+    assert_eq!(
+      Decimal256::from_str("730").unwrap(),
+      value.summaries[0].1.borrowed_value
+    );
+    assert_eq!(vec![Addr::unchecked("fresh")], value.skipped);
+
+    let msg = batch(vec![Addr::unchecked("borrower"); MAX_BATCH_ACCOUNTS + 1]);
+    let err = query(deps.as_ref(), mock_env(), msg).unwrap_err();
+    assert!(err.to_string().contains("addresses limit"));
+  }
 
-// query_max_withdraw
-fn query_max_withdraw(
-  deps: Deps,
-  max_withdraw_params: MaxWithdrawParams,
-) -> StdResult<MaxWithdrawResponse> {
-  let request = QueryRequest::Custom(StructUmeeQuery::max_withdraw_params(max_withdraw_params));
+  #[test]
+  fn unregistered_denom() {
+    let mut deps = mock_umee_dependencies();
+    mock_registry(&mut deps, &[TOKEN_JSON]);
+    mock_market_summary(&mut deps, "uatom", "1");
+
+    let msg = QueryMsg::Umee(Box::new(UmeeQuery::Leverage(
+      UmeeQueryLeverage::MarketSummary(MarketSummaryParams::new("uatom")),
+    )));
+    let err = query(deps.as_ref(), mock_env(), msg.clone()).unwrap_err();
+    assert!(
+      err.to_string().contains("denom uatom not registered"),
+      "{}",
+      err
+    );
+
+    let instantiate_msg = InstantiateMsg {
+      require_umee: None,
+      chain_queries: None,
+      check_denoms: Some(false),
+    };
+    instantiate(
+      deps.as_mut(),
+      mock_env(),
+      mock_info("creator", &[]),
+      instantiate_msg,
+    )
+    .unwrap();
+    query(deps.as_ref(), mock_env(), msg).unwrap();
+  }
 
-  let max_withdraw_response: MaxWithdrawResponse;
-  match query_chain(deps, &request) {
-    Err(err) => {
-      return Err(err);
-    }
-    Ok(binary) => {
-      match from_json::<MaxWithdrawResponse>(&binary) {
-        Err(err) => {
-          return Err(err);
-        }
-        Ok(response) => max_withdraw_response = response,
-      };
+  // assigned_query returns the name of the only native query set in query
+  fn assigned_query(query: &StructUmeeQuery) -> String {
+    let fields: BTreeMap<String, Option<IgnoredAny>> =
+      from_json(to_json_binary(query).unwrap()).unwrap();
+    let assigned: Vec<String> = fields
+      .into_iter()
+      .filter_map(|(name, value)| value.map(|_| name))
+      .collect();
+    assert_eq!(1, assigned.len(), "{:?}", assigned);
+    assigned[0].clone()
+  }
+
+  #[test]
+  fn dispatch_table() {
+    let mut deps = mock_umee_dependencies();
+    let instantiate_msg = InstantiateMsg {
+      require_umee: None,
+      chain_queries: None,
+      check_denoms: Some(false),
+    };
+    instantiate(
+      deps.as_mut(),
+      mock_env(),
+      mock_info("creator", &[]),
+      instantiate_msg,
+    )
+    .unwrap();
+    deps.querier.custom.clear();
+
+    // every umee query with the native query it reaches first, the composed
+    // queries list the first native query they are built from
+    let table = [
+      (
+        r#"{"leverage":{"leverage_parameters":{}}}"#,
+        "leverage_parameters",
+      ),
+      (
+        r#"{"leverage":{"registered_tokens":{}}}"#,
+        "registered_tokens",
+      ),
+      (
+        r#"{"leverage":{"market_summary":{"denom":"uumee"}}}"#,
+        "market_summary",
+      ),
+      (
+        r#"{"leverage":{"account_balances":{"address":"addr"}}}"#,
+        "account_balances",
+      ),
+      (
+        r#"{"leverage":{"account_summary":{"address":"addr"}}}"#,
+        "account_summary",
+      ),
+      (
+        r#"{"leverage":{"liquidation_targets":{}}}"#,
+        "liquidation_targets",
+      ),
+      (r#"{"leverage":{"bad_debts":{}}}"#, "bad_debts_params"),
+      (
+        r#"{"leverage":{"max_withdraw":{"address":"addr","denom":"uumee"}}}"#,
+        "max_withdraw_params",
+      ),
+      (
+        r#"{"leverage":{"max_borrow":{"address":"addr","denom":"uumee"}}}"#,
+        "max_borrow_params",
+      ),
+      (
+        r#"{"leverage":{"available_withdraw":{"denom":"uumee"}}}"#,
+        "market_summary",
+      ),
+      (
+        r#"{"leverage":{"liquidatable_borrowers":{"denom":"uumee"}}}"#,
+        "liquidation_targets",
+      ),
+      (
+        r#"{"leverage":{"special_borrow_limit":{"address":"addr"}}}"#,
+        "account_summary",
+      ),
+      (
+        r#"{"leverage":{"interest_rate_model":{"denom":"uumee"}}}"#,
+        "registered_tokens",
+      ),
+      (
+        r#"{"leverage":{"simulate_liquidation":{"borrower":"addr","repay":{"denom":"uumee","amount":"1"},"reward_denom":"uatom"}}}"#,
+        "account_balances",
+      ),
+      (
+        r#"{"leverage":{"caps_usage":{"denom":"uumee"}}}"#,
+        "registered_tokens",
+      ),
+      (r#"{"leverage":{"all_reserves":{}}}"#, "registered_tokens"),
+      (r#"{"leverage":{"bad_debt_total":{}}}"#, "bad_debts_params"),
+      (
+        r#"{"leverage":{"top_supply_markets":{"limit":3}}}"#,
+        "registered_tokens",
+      ),
+      (
+        r#"{"leverage":{"denom_status":{"denom":"uumee"}}}"#,
+        "registered_tokens",
+      ),
+      (
+        r#"{"leverage":{"market_enabled":{"denom":"uumee"}}}"#,
+        "registered_tokens",
+      ),
+      (
+        r#"{"leverage":{"borrow_factors":{"denom":"uumee"}}}"#,
+        "registered_tokens",
+      ),
+      (
+        r#"{"leverage":{"market_row":{"denom":"uumee"}}}"#,
+        "market_summary",
+      ),
+      (
+        r#"{"leverage":{"reserve_info":{"denom":"uumee"}}}"#,
+        "registered_tokens",
+      ),
+      (
+        r#"{"leverage":{"time_to_liquidation":{"address":"addr","blocks_per_year":1000}}}"#,
+        "account_summary",
+      ),
+      (
+        r#"{"leverage":{"collateral_value_at_price":{"address":"addr","price_overrides":[]}}}"#,
+        "account_balances",
+      ),
+      (
+        r#"{"leverage":{"liquidation_price":{"address":"addr","collateral_denom":"uumee"}}}"#,
+        "registered_tokens",
+      ),
+      (
+        r#"{"leverage":{"simulate_supply":{"denom":"uumee","amount":"1"}}}"#,
+        "market_summary",
+      ),
+      (
+        r#"{"leverage":{"max_collateralize":{"address":"addr","denom":"uumee"}}}"#,
+        "account_balances",
+      ),
+      (
+        r#"{"leverage":{"batch_account_summary":{"addresses":["addr"]}}}"#,
+        "account_summary",
+      ),
+      (
+        r#"{"oracle":{"exchange_rates":{"denom":"UMEE"}}}"#,
+        "exchange_rates",
+      ),
+      (
+        r#"{"oracle":{"active_exchange_rates":{}}}"#,
+        "active_exchange_rates",
+      ),
+      (
+        r#"{"oracle":{"feeder_delegation":{"validator_addr":"v"}}}"#,
+        "feeder_delegation",
+      ),
+      (
+        r#"{"oracle":{"miss_counter":{"validator_addr":"v"}}}"#,
+        "miss_counter",
+      ),
+      (r#"{"oracle":{"slash_window":{}}}"#, "slash_window"),
+      (
+        r#"{"oracle":{"aggregate_prevote":{"validator_addr":"v"}}}"#,
+        "aggregate_prevote",
+      ),
+      (
+        r#"{"oracle":{"aggregate_prevotes":{}}}"#,
+        "aggregate_prevotes",
+      ),
+      (
+        r#"{"oracle":{"aggregate_vote":{"validator_addr":"v"}}}"#,
+        "aggregate_vote",
+      ),
+      (r#"{"oracle":{"aggregate_votes":{}}}"#, "aggregate_votes"),
+      (r#"{"oracle":{"oracle_parameters":{}}}"#, "oracle_params"),
+      (
+        r#"{"oracle":{"medians":{"denom":"UMEE"}}}"#,
+        "medians_params",
+      ),
+      (
+        r#"{"oracle":{"median_deviations":{"denom":"UMEE"}}}"#,
+        "median_deviations_params",
+      ),
+      (
+        r#"{"oracle":{"exchange_rates_batch":{"denoms":["UMEE"]}}}"#,
+        "exchange_rates",
+      ),
+      (r#"{"oracle":{"accept_list":{}}}"#, "oracle_params"),
+      (
+        r#"{"incentive":{"incentive_parameters":{}}}"#,
+        "incentive_parameters",
+      ),
+      (
+        r#"{"incentive":{"total_bonded":{"denom":"u/uumee"}}}"#,
+        "total_bonded",
+      ),
+      (
+        r#"{"incentive":{"total_unbonding":{"denom":"u/uumee"}}}"#,
+        "total_unbonding",
+      ),
+      (
+        r#"{"incentive":{"account_bonds":{"address":"a"}}}"#,
+        "account_bonds",
+      ),
+      (
+        r#"{"incentive":{"pending_rewards":{"address":"a"}}}"#,
+        "pending_rewards",
+      ),
+      (
+        r#"{"incentive":{"completed_incentive_programs":{}}}"#,
+        "completed_incentive_programs",
+      ),
+      (
+        r#"{"incentive":{"ongoing_incentive_programs":{}}}"#,
+        "ongoing_incentive_programs",
+      ),
+      (
+        r#"{"incentive":{"upcoming_incentive_programs":{}}}"#,
+        "upcoming_incentive_programs",
+      ),
+      (
+        r#"{"incentive":{"incentive_program":{"id":1}}}"#,
+        "incentive_program",
+      ),
+      (
+        r#"{"incentive":{"current_rates":{"u_token":"u/uumee"}}}"#,
+        "current_rates",
+      ),
+      (
+        r#"{"incentive":{"actual_rates":{"u_token":"u/uumee"}}}"#,
+        "actual_rates",
+      ),
+      (
+        r#"{"incentive":{"last_reward_time":{}}}"#,
+        "last_reward_time",
+      ),
+      (
+        r#"{"metoken":{"metoken_parameters":{}}}"#,
+        "metoken_parameters",
+      ),
+      (
+        r#"{"metoken":{"metoken_indexes":{"metoken_denom":"me/USD"}}}"#,
+        "metoken_indexes",
+      ),
+      (
+        r#"{"metoken":{"metoken_swapfee":{"metoken_denom":"me/USD","asset":"uumee"}}}"#,
+        "metoken_swapfee",
+      ),
+      (
+        r#"{"metoken":{"metoken_redeemfee":{"metoken":"me/USD","asset_denom":"uumee"}}}"#,
+        "metoken_redeemfee",
+      ),
+      (
+        r#"{"metoken":{"metoken_indexbalances":{"metoken_denom":"me/USD"}}}"#,
+        "metoken_indexbalances",
+      ),
+      (
+        r#"{"metoken":{"metoken_index_prices":{"metoken_denom":"me/USD"}}}"#,
+        "metoken_indexprice",
+      ),
+    ];
+    for (json, expected) in table {
+      deps.querier.requested.borrow_mut().clear();
+      let umee_query: UmeeQuery = from_json(json.as_bytes()).unwrap();
+      // no response is mocked, only the first native query matters
+      let _ = query(
+        deps.as_ref(),
+        mock_env(),
+        QueryMsg::Umee(Box::new(umee_query)),
+      );
+      let requested = deps.querier.requested.borrow();
+      assert!(!requested.is_empty(), "{} sent no umee query", json);
+      assert_eq!(expected, assigned_query(&requested[0]), "{}", json);
     }
   }
 
-  Ok(max_withdraw_response)
-}
+  #[test]
+  fn all_reserves() {
+    let mut deps = mock_umee_dependencies();
+    let atom = TOKEN_JSON.replace("uumee", "uatom");
+    mock_registry(&mut deps, &[TOKEN_JSON, &atom]);
+    mock_market_summary(&mut deps, "uumee", "1.0");
+    mock_market_summary(&mut deps, "uatom", "1.5");
+
+    let msg = QueryMsg::Umee(Box::new(UmeeQuery::Leverage(
+      UmeeQueryLeverage::AllReserves(AllReservesParams {}),
+    )));
+    let res = query(deps.as_ref(), mock_env(), msg).unwrap();
+    let value: AllReservesResponse = from_json(&res).unwrap();
+    assert_eq!(
+      vec![Coin::new(10, "uumee"), Coin::new(10, "uatom")],
+      value.reserves
+    );
+  }
 
-// query_max_borrow
-fn query_max_borrow(
-  deps: Deps,
-  max_borrow_params: MaxBorrowParams,
-) -> StdResult<MaxBorrowResponse> {
-  let request = QueryRequest::Custom(StructUmeeQuery::max_borrow_params(max_borrow_params));
+  #[test]
+  fn withdraw_min_received() {
+    let mut deps = mock_umee_dependencies();
+    let withdraw = |min_received: Option<u128>| {
+      ExecuteMsg::Withdraw(GuardedWithdrawParams {
+        asset: Coin::new(100, "u/uumee"),
+        min_received: min_received.map(Uint128::new),
+      })
+    };
+    let withdraw_reply = module_reply(REPLY_WITHDRAW, &[Coin::new(105, "uumee")]);
+
+    let res = execute(
+      deps.as_mut(),
+      mock_env(),
+      mock_info("vault", &[]),
+      withdraw(Some(105)),
+    )
+    .unwrap();
+    // the guard is not part of the message sent to the leverage module
+    assert_eq!(
+      CosmosMsg::Custom(StructUmeeMsg::from(UmeeMsgLeverage::Withdraw(
+        WithdrawParams::new(Coin::new(100, "u/uumee"))
+      ))),
+      res.messages[0].msg
+    );
+    mock_contract_balance(&mut deps, &coins(104, "uumee"));
+    let err = reply(deps.as_mut(), mock_env(), withdraw_reply.clone()).unwrap_err();
+    assert!(
+      err.to_string().contains("below the minimum of 105"),
+      "{}",
+      err
+    );
+
+    execute(
+      deps.as_mut(),
+      mock_env(),
+      mock_info("vault", &[]),
+      withdraw(Some(105)),
+    )
+    .unwrap();
+    mock_contract_balance(&mut deps, &coins(209, "uumee"));
+    let res = reply(deps.as_mut(), mock_env(), withdraw_reply.clone()).unwrap();
+    let value: WithdrawMsgResponse = from_json(res.data.unwrap()).unwrap();
+    assert_eq!(Coin::new(105, "uumee"), value.received);
+    // the guard is cleared by its reply
+    reply(deps.as_mut(), mock_env(), withdraw_reply.clone()).unwrap();
+
+    // without a guard the withdraw only reports what it received
+    execute(
+      deps.as_mut(),
+      mock_env(),
+      mock_info("vault", &[]),
+      withdraw(None),
+    )
+    .unwrap();
+    let res = reply(deps.as_mut(), mock_env(), withdraw_reply).unwrap();
+    let value: WithdrawMsgResponse = from_json(res.data.unwrap()).unwrap();
+    assert_eq!(Coin::new(0, "uumee"), value.received);
+  }
 
-  let max_borrow_response: MaxBorrowResponse;
-  match query_chain(deps, &request) {
-    Err(err) => {
-      return Err(err);
+  #[test]
+  fn cached_tvl() {
+    let mut deps = mock_umee_dependencies();
+    mock_registry(&mut deps, &[TOKEN_JSON]);
+    mock_market_summary(&mut deps, "uumee", "1.0");
+    let cached_tvl = QueryMsg::CachedTvl { max_age_blocks: 10 };
+
+    let err = query(deps.as_ref(), mock_env(), cached_tvl.clone()).unwrap_err();
+    assert!(err.to_string().contains("never refreshed"));
+
+    let mut env = mock_env();
+    let msg = ExecuteMsg::RefreshTvlCache {};
+    execute(deps.as_mut(), env.clone(), mock_info("anyone", &[]), msg).unwrap();
+
+    env.block.height += 10;
+    let res = query(deps.as_ref(), env.clone(), cached_tvl.clone()).unwrap();
+    let value: CachedTvlResponse = from_json(&res).unwrap();
+    assert_eq!(mock_env().block.height, value.height);
+    assert_eq!(
+      Decimal256::from_str("0.00001").unwrap(),
+      value.tvl.total_supplied_value
+    );
+    assert_eq!("0.00", value.tvl.total_supplied_display);
+
+    env.block.height += 1;
+    let err = query(deps.as_ref(), env, cached_tvl).unwrap_err();
+    assert!(err.to_string().contains("11 blocks old"), "{}", err);
+  }
+
+  #[test]
+  fn supply_schedule() {
+    let mut deps = mock_umee_dependencies();
+    let schedule = vec![
+      SupplyParams::new(Coin::new(30, "uumee")),
+      SupplyParams::new(Coin::new(10, "uatom")),
+      SupplyParams::new(Coin::new(20, "uumee")),
+    ];
+    let msg = ExecuteMsg::SupplySchedule(schedule.clone());
+    let res = execute(deps.as_mut(), mock_env(), mock_info("scheduler", &[]), msg).unwrap();
+    assert_eq!("3", res.attributes[1].value);
+    for (supply_params, sub_msg) in schedule.into_iter().zip(res.messages) {
+      assert_eq!(
+        CosmosMsg::Custom(StructUmeeMsg::from(UmeeMsgLeverage::Supply(supply_params))),
+        sub_msg.msg
+      );
     }
-    Ok(binary) => {
-      match from_json::<MaxBorrowResponse>(&binary) {
-        Err(err) => {
-          return Err(err);
-        }
-        Ok(response) => max_borrow_response = response,
-      };
+
+    for (schedule, expected) in [
+      (vec![], "without any entry"),
+      (
+        vec![
+          SupplyParams::new(Coin::new(30, "uumee")),
+          SupplyParams::new(Coin::new(0, "uumee")),
+        ],
+        "entry 1: Zero amount of uumee",
+      ),
+      (
+        vec![SupplyParams::new(Coin::new(30, "u/uumee"))],
+        "entry 0: Invalid denom u/uumee",
+      ),
+    ] {
+      let msg = ExecuteMsg::SupplySchedule(schedule);
+      let err = execute(deps.as_mut(), mock_env(), mock_info("scheduler", &[]), msg).unwrap_err();
+      assert!(err.to_string().contains(expected), "{}", err);
     }
   }
 
-  Ok(max_borrow_response)
-}
-
-// query_market_summary creates an query request to the native modules
-// with query_chain wrapping the response to the actual
-// MarketSummaryResponse struct.
-fn query_market_summary(
-  deps: Deps,
-  market_summary_params: MarketSummaryParams,
-) -> StdResult<MarketSummaryResponse> {
-  let request = QueryRequest::Custom(StructUmeeQuery::market_summary(market_summary_params));
+  #[test]
+  fn batch_leverage_order() {
+    let mut deps = mock_umee_dependencies();
+    let batch = vec![
+      UmeeMsgLeverage::Supply(SupplyParams::new(Coin::new(100, "uumee"))),
+      UmeeMsgLeverage::Collateralize(CollateralizeParams::new(Coin::new(100, "u/uumee"))),
+      UmeeMsgLeverage::Borrow(BorrowParams::new(Coin::new(20, "uatom"))),
+      UmeeMsgLeverage::Repay(RepayParams::new(Coin::new(5, "uosmo"))),
+    ];
+    let msg = ExecuteMsg::BatchLeverage(batch.clone());
+    let res = execute(deps.as_mut(), mock_env(), mock_info("strategy", &[]), msg).unwrap();
+    assert_eq!("4", res.attributes[1].value);
+    assert_eq!(batch.len(), res.messages.len());
+    for (leverage_msg, sub_msg) in batch.into_iter().zip(res.messages) {
+      assert_eq!(ReplyOn::Never, sub_msg.reply_on);
+      assert_eq!(
+        CosmosMsg::Custom(StructUmeeMsg::from(leverage_msg)),
+        sub_msg.msg
+      );
+    }
 
-  let market_summary_response: MarketSummaryResponse;
-  match query_chain(deps, &request) {
-    Err(err) => {
-      return Err(err);
+    for (batch, expected) in [
+      (vec![], "without any message"),
+      (
+        vec![
+          UmeeMsgLeverage::Supply(SupplyParams::new(Coin::new(100, "uumee"))),
+          UmeeMsgLeverage::Withdraw(WithdrawParams::new(Coin::new(50, "u/uumee"))),
+        ],
+        "can't mix supply and withdraw of uumee",
+      ),
+      (
+        vec![
+          UmeeMsgLeverage::Decollateralize(DecollateralizeParams::new(Coin::new(1, "u/uatom"))),
+          UmeeMsgLeverage::SupplyCollateral(SupplyCollateralParams::new(Coin::new(1, "uatom"))),
+        ],
+        "can't mix decollateralize and supply_collateral of uatom",
+      ),
+      (
+        vec![
+          UmeeMsgLeverage::Borrow(BorrowParams::new(Coin::new(20, "uatom"))),
+          UmeeMsgLeverage::Repay(RepayParams::new(Coin::new(20, "uatom"))),
+        ],
+        "can't mix borrow and repay of uatom",
+      ),
+      (
+        vec![
+          UmeeMsgLeverage::Borrow(BorrowParams::new(Coin::new(20, "uatom"))),
+          UmeeMsgLeverage::Borrow(BorrowParams::new(Coin::new(30, "uatom"))),
+        ],
+        "batch leverage debt position lists the denom uatom more than once",
+      ),
+      (
+        vec![UmeeMsgLeverage::Borrow(BorrowParams::new(Coin::new(
+          20, "u/uatom",
+        )))],
+        "entry 0: Invalid denom u/uatom",
+      ),
+    ] {
+      let msg = ExecuteMsg::BatchLeverage(batch);
+      let err = execute(deps.as_mut(), mock_env(), mock_info("strategy", &[]), msg).unwrap_err();
+      assert!(err.to_string().contains(expected), "{}", err);
     }
-    Ok(binary) => {
-      match from_json::<MarketSummaryResponse>(&binary) {
-        Err(err) => {
-          return Err(err);
-        }
-        Ok(response) => market_summary_response = response,
-      };
+  }
+
+  #[test]
+  fn cross_chain_addresses() {
+    let mut deps = mock_umee_dependencies();
+    let msg = InstantiateMsg {
+      require_umee: None,
+      chain_queries: None,
+      check_denoms: None,
+    };
+    instantiate(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+
+    let cosmos_addr = Addr::unchecked("cosmos1qyqszqgpqyqszqgpqyqszqgpqyqszqgpjnp7du");
+    let msgs = vec![
+      ExecuteMsg::ChangeOwner {
+        new_owner: cosmos_addr.clone(),
+      },
+      ExecuteMsg::SetupFeeder(SetupFeederParams {
+        operator: mock_env().contract.address,
+        feeder: cosmos_addr.clone(),
+      }),
+      ExecuteMsg::Umee(UmeeMsg::Leverage(UmeeMsgLeverage::Liquidate(
+        LiquidateParams::new(
+          cosmos_addr.clone(),
+          Coin::new(10, "uumee"),
+          Coin::new(10, "uatom"),
+        ),
+      ))),
+    ];
+    for msg in msgs {
+      match execute(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg) {
+        Err(ContractError::InvalidAddress { address, .. }) => assert_eq!(cosmos_addr, address),
+        res => panic!("unexpected {:?}", res),
+      }
     }
   }
 
-  Ok(market_summary_response)
-}
+  #[test]
+  fn adjust_collateral() {
+    let mut deps = mock_umee_dependencies();
+    let env = mock_env();
+    // 300 u/uumee collateralized and 200 held by the contract
+    mock_contract_position(&mut deps, 0, 300, &coins(200, "u/uumee"));
+    let adjust = |borrower: &Addr, target: u128| {
+      ExecuteMsg::AdjustCollateral(AdjustCollateralParams {
+        borrower: borrower.clone(),
+        denom: String::from("uumee"),
+        target: Uint128::new(target),
+      })
+    };
+    let contract = env.contract.address.clone();
+    let info = mock_info("anyone", &[]);
+
+    let res = execute(
+      deps.as_mut(),
+      env.clone(),
+      info.clone(),
+      adjust(&contract, 450),
+    )
+    .unwrap();
+    assert_eq!(
+      CosmosMsg::Custom(StructUmeeMsg::from(UmeeMsgLeverage::Collateralize(
+        CollateralizeParams::new(Coin::new(150, "u/uumee"))
+      ))),
+      res.messages[0].msg
+    );
+
+    let res = execute(
+      deps.as_mut(),
+      env.clone(),
+      info.clone(),
+      adjust(&contract, 100),
+    )
+    .unwrap();
+    assert_eq!(
+      CosmosMsg::Custom(StructUmeeMsg::from(UmeeMsgLeverage::Decollateralize(
+        DecollateralizeParams::new(Coin::new(200, "u/uumee"))
+      ))),
+      res.messages[0].msg
+    );
+
+    let res = execute(
+      deps.as_mut(),
+      env.clone(),
+      info.clone(),
+      adjust(&contract, 300),
+    )
+    .unwrap();
+    assert!(res.messages.is_empty());
+
+    let err = execute(
+      deps.as_mut(),
+      env.clone(),
+      info.clone(),
+      adjust(&contract, 501),
+    )
+    .unwrap_err();
+    assert!(
+      err.to_string().contains("more than the 200u/uumee held"),
+      "{}",
+      err
+    );
+
+    let other = Addr::unchecked("other");
+    execute(deps.as_mut(), env, info, adjust(&other, 300)).unwrap_err();
+  }
 
-// query_exchange_rates receives the get exchange rate base
-// query params and creates an query request to the native modules
-// with query_chain wrapping the response to the actual
-// ExchangeRatesResponse struct
-fn query_exchange_rates(
-  deps: Deps,
-  exchange_rates_params: ExchangeRatesParams,
-) -> StdResult<ExchangeRatesResponse> {
-  let request = QueryRequest::Custom(StructUmeeQuery::exchange_rates(exchange_rates_params));
+  #[test]
+  fn market_math_overflow() {
+    let mut deps = mock_umee_dependencies();
+    mock_registry(&mut deps, &[TOKEN_JSON]);
+    let mock_market = |deps: &mut UmeeMockDeps, exponent: u32, supplied: &str, borrowed: &str| {
+      let json = format!(
+        r#"{{"symbol_denom":"UMEE","exponent":{},"oracle_price":"0.01","utoken_exchange_rate":"1","supply_apy":"0.05","borrow_apy":"0.1","supplied":"{}","reserved":"10","collateral":"800","borrowed":"{}","liquidity":"490","maximum_borrow":"0","maximum_collateral":"0","minimum_liquidity":"100","utoken_supply":"900","available_borrow":"390","available_withdraw":"390","available_collateralize":"0"}}"#,
+        exponent, supplied, borrowed
+      );
+      mock_custom_response(
+        deps,
+        StructUmeeQuery::market_summary(MarketSummaryParams::new("uumee")),
+        Binary::from(json.as_bytes()),
+      );
+    };
 
-  let exchange_rates_resp: ExchangeRatesResponse;
-  match query_chain(deps, &request) {
-    Err(err) => {
-      return Err(err);
-    }
-    Ok(binary) => {
-      match from_json::<ExchangeRatesResponse>(&binary) {
-        Err(err) => {
-          return Err(err);
-        }
-        Ok(response) => exchange_rates_resp = response,
-      };
-    }
+    // 10^39 doesn't fit in the u128 of a display token factor
+    mock_market(&mut deps, 39, "1000", "500");
+    let err = query(deps.as_ref(), mock_env(), QueryMsg::TotalValueLocked {}).unwrap_err();
+    assert!(err.to_string().contains("exponent 39 overflows"), "{}", err);
+
+    // a dust supply makes a borrow cap the borrowed value can't be divided by
+    mock_market(
+      &mut deps,
+      6,
+      "0.000000000000000002",
+      "1000000000000000000000000000000000000000000000000000",
+    );
+    let msg = QueryMsg::Umee(Box::new(UmeeQuery::Leverage(UmeeQueryLeverage::CapsUsage(
+      CapsUsageParams::new("uumee"),
+    ))));
+    let err = query(deps.as_ref(), mock_env(), msg).unwrap_err();
+    assert!(err.to_string().contains("usage: "), "{}", err);
   }
 
-  Ok(exchange_rates_resp)
-}
+  #[test]
+  fn raw_query_passthrough() {
+    let params = MarketSummaryParams {
+      denom: "uumee".to_string(),
+    };
+    // fields unknown to the response types are returned as well
+    let chain = Binary::from(br#"{"symbol_denom":"UMEE","future_field":[1,2]}"#.to_vec());
+    let deps_with = |chain_queries: Option<Vec<ChainQueryKind>>| {
+      let mut deps = mock_umee_dependencies();
+      let msg = InstantiateMsg {
+        require_umee: None,
+        chain_queries,
+        check_denoms: None,
+      };
+      instantiate(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+      mock_custom_response(
+        &mut deps,
+        StructUmeeQuery::market_summary(params.clone()),
+        chain.clone(),
+      );
+      deps
+    };
+    let raw = QueryMsg::Raw {
+      query: UmeeQuery::Leverage(UmeeQueryLeverage::MarketSummary(params.clone())),
+    };
 
-// query_active_exchange_rates receives the get exchange rate base
-// query params and creates an query request to the native modules
-// with query_chain wrapping the response to the actual
-// ActiveExchangeRatesResponse struct
-fn query_active_exchange_rates(
-  deps: Deps,
-  active_exchange_rates_params: ActiveExchangeRatesParams,
-) -> StdResult<ActiveExchangeRatesResponse> {
-  let request = QueryRequest::Custom(StructUmeeQuery::active_exchange_rates(
-    active_exchange_rates_params,
-  ));
+    // raw passes through the custom queries only if they are allowed
+    let deps = deps_with(Some(vec![ChainQueryKind::Bank]));
+    let err = query(deps.as_ref(), mock_env(), raw.clone()).unwrap_err();
+    assert!(err.to_string().contains("not allowed"), "{}", err);
 
-  let active_exchange_rates_resp: ActiveExchangeRatesResponse;
-  match query_chain(deps, &request) {
-    Err(err) => {
-      return Err(err);
-    }
-    Ok(binary) => {
-      match from_json::<ActiveExchangeRatesResponse>(&binary) {
-        Err(err) => {
-          return Err(err);
-        }
-        Ok(response) => active_exchange_rates_resp = response,
-      };
-    }
-  }
+    let deps = deps_with(None);
+    assert_eq!(query(deps.as_ref(), mock_env(), raw).unwrap(), chain);
 
-  Ok(active_exchange_rates_resp)
-}
+    let composed = QueryMsg::Raw {
+      query: UmeeQuery::Leverage(UmeeQueryLeverage::AllReserves(AllReservesParams {})),
+    };
+    let err = query(deps.as_ref(), mock_env(), composed).unwrap_err();
+    assert!(err.to_string().contains("native umee queries"));
+  }
 
-// query_feeder_delegation receives the get exchange rate base
-// query params and creates an query request to the native modules
-// with query_chain wrapping the response to the actual
-// FeederDelegationResponse struct
-fn query_feeder_delegation(
-  deps: Deps,
-  feeder_delegation_params: FeederDelegationParams,
-) -> StdResult<FeederDelegationResponse> {
-  let request = QueryRequest::Custom(StructUmeeQuery::feeder_delegation(feeder_delegation_params));
+  #[test]
+  fn max_collateralize() {
+    let mut deps = mock_umee_dependencies();
+    let contract = mock_env().contract.address;
+    mock_contract_position(&mut deps, 0, 300, &coins(200, "u/uumee"));
+    let max_collateralize = |denom: &str| {
+      QueryMsg::Umee(Box::new(UmeeQuery::Leverage(
+        UmeeQueryLeverage::MaxCollateralize(MaxCollateralizeParams {
+          address: contract.clone(),
+          denom: String::from(denom),
+        }),
+      )))
+    };
 
-  let feeder_delegation_resp: FeederDelegationResponse;
-  match query_chain(deps, &request) {
-    Err(err) => {
-      return Err(err);
-    }
-    Ok(binary) => {
-      match from_json::<FeederDelegationResponse>(&binary) {
-        Err(err) => {
-          return Err(err);
-        }
-        Ok(response) => feeder_delegation_resp = response,
-      };
+    for denom in ["uumee", "u/uumee"] {
+      let res = query(deps.as_ref(), mock_env(), max_collateralize(denom)).unwrap();
+      let value: MaxCollateralizeResponse = from_json(&res).unwrap();
+      assert_eq!(value.available, Coin::new(200, "u/uumee"));
+      assert_eq!(value.collateralized, Coin::new(300, "u/uumee"));
     }
   }
 
-  Ok(feeder_delegation_resp)
-}
+  #[test]
+  fn trace_native_queries() {
+    let summary = StructUmeeQuery::market_summary(MarketSummaryParams {
+      denom: String::from("uumee"),
+    });
+    assert_eq!(
+      query_trace(&summary).unwrap(),
+      "umee query market_summary denom=uumee address="
+    );
+
+    let balances =
+      StructUmeeQuery::account_balances(AccountBalancesParams::new(Addr::unchecked("umee1addr")));
+    assert_eq!(
+      query_trace(&balances).unwrap(),
+      "umee query account_balances denom= address=umee1addr"
+    );
+  }
+
+  #[test]
+  fn rates() {
+    let mut deps = mock_umee_dependencies();
+    mock_registry(&mut deps, &[TOKEN_JSON]);
+    mock_market_summary(&mut deps, "uumee", "1.02");
+    let rates = QueryMsg::Rates {
+      denom: String::from("uumee"),
+      round_to: None,
+    };
+    let exchange_rates = StructUmeeQuery::exchange_rates(ExchangeRatesParams::new("UMEE"));
+
+    mock_custom_response(
+      &mut deps,
+      exchange_rates.clone(),
+      Binary::from(br#"{"exchange_rates":[]}"#.as_slice()),
+    );
+    let err = query(deps.as_ref(), mock_env(), rates.clone()).unwrap_err();
+    assert!(
+      err.to_string().contains("no oracle price for UMEE"),
+      "{}",
+      err
+    );
+
+    mock_custom_response(
+      &mut deps,
+      exchange_rates,
+      Binary::from(br#"{"exchange_rates":[{"denom":"UMEE","amount":"0.0125"}]}"#.as_slice()),
+    );
+    let res = query(deps.as_ref(), mock_env(), rates).unwrap();
+    let value: RatesResponse = from_json(&res).unwrap();
+    assert_eq!(value.oracle_price, Decimal256::from_str("0.0125").unwrap());
+    assert_eq!(
+      value.utoken_exchange_rate,
+      Decimal256::from_str("1.02").unwrap()
+    );
+
+    let rounded = QueryMsg::Rates {
+      denom: String::from("uumee"),
+      round_to: Some(1),
+    };
+    let res = query(deps.as_ref(), mock_env(), rounded).unwrap();
+    let value: RatesResponse = from_json(&res).unwrap();
+    assert_eq!(value.oracle_price, Decimal256::zero());
+    assert_eq!(value.utoken_exchange_rate, Decimal256::one());
+
+    // a summary without the uToken exchange rate falls back to the
+    // supplied 1000uumee over the uToken supply, one before any supply
+    mock_market_summary(&mut deps, "uumee", "0");
+    let unrounded = QueryMsg::Rates {
+      denom: String::from("uumee"),
+      round_to: None,
+    };
+    let res = query(deps.as_ref(), mock_env(), unrounded.clone()).unwrap();
+    let value: RatesResponse = from_json(&res).unwrap();
+    assert_eq!(value.utoken_exchange_rate, Decimal256::one());
+    deps
+      .querier
+      .base
+      .update_balance("supplier", coins(800_000_000, "u/uumee"));
+    let res = query(deps.as_ref(), mock_env(), unrounded).unwrap();
+    let value: RatesResponse = from_json(&res).unwrap();
+    assert_eq!(
+      value.utoken_exchange_rate,
+      Decimal256::from_ratio(1000u128, 800_000_000u128)
+    );
+  }
+
+  #[test]
+  fn base_denom_exchange_rates() {
+    let mut deps = mock_umee_dependencies();
+    mock_registry(&mut deps, &[TOKEN_JSON]);
+    mock_custom_response(
+      &mut deps,
+      StructUmeeQuery::exchange_rates(ExchangeRatesParams::new("UMEE")),
+      Binary::from(br#"{"exchange_rates":[{"denom":"UMEE","amount":"0.0125"}]}"#.as_slice()),
+    );
+    let exchange_rates = |denom: &str| {
+      let msg = QueryMsg::ExchangeRates(ExchangeRatesParams {
+        denom_kind: DenomKind::Base,
+        ..ExchangeRatesParams::new(denom)
+      });
+      query(deps.as_ref(), mock_env(), msg)
+        .map(|res| from_json::<ExchangeRatesResponse>(&res).unwrap())
+    };
+
+    let rates = exchange_rates("uumee").unwrap();
+    assert_eq!("UMEE", rates.exchange_rates[0].denom);
+    assert!(exchange_rates("uatom")
+      .unwrap_err()
+      .to_string()
+      .contains("uatom is not a registered token"));
+    // the symbol kind is the default and keeps the former json
+    let json = to_json_binary(&ExchangeRatesParams::new("UMEE")).unwrap();
+    assert_eq!(br#"{"denom":"UMEE"}"#.as_slice(), json.as_slice());
+  }
+
+  #[test]
+  fn rounded_exchange_rates() {
+    let mut deps = mock_umee_dependencies();
+    mock_custom_response(
+      &mut deps,
+      StructUmeeQuery::exchange_rates(ExchangeRatesParams::new("UMEE")),
+      Binary::from(br#"{"exchange_rates":[{"denom":"UMEE","amount":"0.0125"}]}"#.as_slice()),
+    );
+    let exchange_rates = |round_to: Option<u32>| {
+      let msg = QueryMsg::ExchangeRates(ExchangeRatesParams {
+        round_to,
+        ..ExchangeRatesParams::new("UMEE")
+      });
+      let res = query(deps.as_ref(), mock_env(), msg).unwrap();
+      from_json::<ExchangeRatesResponse>(&res)
+        .unwrap()
+        .exchange_rates[0]
+        .amount
+    };
+    assert_eq!(
+      Decimal256::from_str("0.0125").unwrap(),
+      exchange_rates(None)
+    );
+    // 0.0125 is a tie rounded to the even 0.012
+    assert_eq!(
+      Decimal256::from_str("0.012").unwrap(),
+      exchange_rates(Some(3))
+    );
+    assert_eq!(
+      Decimal256::from_str("0.01").unwrap(),
+      exchange_rates(Some(2))
+    );
+
+    mock_custom_response(
+      &mut deps,
+      StructUmeeQuery::actual_rates(ActualRatesParams {
+        u_token: String::from("u/uumee"),
+        round_to: None,
+      }),
+      Binary::from(br#"{"APY":"0.05249"}"#.as_slice()),
+    );
+    let msg = QueryMsg::Umee(Box::new(UmeeQuery::Incentive(
+      UmeeQueryIncentive::ActualRates(ActualRatesParams {
+        u_token: String::from("u/uumee"),
+        round_to: Some(3),
+      }),
+    )));
+    let res = query(deps.as_ref(), mock_env(), msg).unwrap();
+    let value: ActualRatesResponse = from_json(&res).unwrap();
+    assert_eq!(Decimal::from_str("0.052").unwrap(), value.apy);
+  }
 
-// query_miss_counter receives the get exchange rate base
-// query params and creates an query request to the native modules
-// with query_chain wrapping the response to the actual
-// MissCounterResponse struct
-fn query_miss_counter(
-  deps: Deps,
-  miss_counter_params: MissCounterParams,
-) -> StdResult<MissCounterResponse> {
-  let request = QueryRequest::Custom(StructUmeeQuery::miss_counter(miss_counter_params));
+  #[test]
+  fn self_position() {
+    let mut deps = mock_umee_dependencies();
+    let contract = mock_env().contract.address;
+    mock_contract_position(&mut deps, 40, 300, &[]);
+    mock_account_summary(&mut deps, contract.as_str(), "40", "150");
+
+    let res = query(deps.as_ref(), mock_env(), QueryMsg::SelfPosition {}).unwrap();
+    let value: SelfPositionResponse = from_json(&res).unwrap();
+    assert_eq!(coins(300, "u/uumee"), value.balances.collateral);
+    assert_eq!(coins(40, "uumee"), value.balances.borrowed);
+    assert_eq!(
+      Decimal256::from_str("40").unwrap(),
+      value.summary.borrowed_value
+    );
+    assert_eq!(
+      Decimal256::from_str("150").unwrap(),
+      value.summary.borrow_limit
+    );
+  }
 
-  let miss_counter_resp: MissCounterResponse;
-  match query_chain(deps, &request) {
-    Err(err) => {
-      return Err(err);
-    }
-    Ok(binary) => {
-      match from_json::<MissCounterResponse>(&binary) {
-        Err(err) => {
-          return Err(err);
-        }
-        Ok(response) => miss_counter_resp = response,
-      };
+  #[test]
+  fn zero_amounts() {
+    let mut deps = mock_umee_dependencies();
+    let zero = |denom: &str| Coin::new(0, denom);
+    let borrower = Addr::unchecked("umee1borrower");
+    let leverage = |msg: UmeeMsgLeverage| ExecuteMsg::Umee(UmeeMsg::Leverage(msg));
+
+    for (msg, denom) in [
+      (
+        leverage(UmeeMsgLeverage::Supply(SupplyParams::new(zero("uumee")))),
+        "uumee",
+      ),
+      (
+        leverage(UmeeMsgLeverage::Withdraw(WithdrawParams::new(zero(
+          "u/uumee",
+        )))),
+        "u/uumee",
+      ),
+      (
+        leverage(UmeeMsgLeverage::Collateralize(CollateralizeParams::new(
+          zero("u/uumee"),
+        ))),
+        "u/uumee",
+      ),
+      (
+        leverage(UmeeMsgLeverage::Decollateralize(
+          DecollateralizeParams::new(zero("u/uumee")),
+        )),
+        "u/uumee",
+      ),
+      (
+        leverage(UmeeMsgLeverage::Borrow(BorrowParams::new(zero("uumee")))),
+        "uumee",
+      ),
+      (
+        leverage(UmeeMsgLeverage::Repay(RepayParams::new(zero("uumee")))),
+        "uumee",
+      ),
+      (
+        leverage(UmeeMsgLeverage::Liquidate(LiquidateParams::new(
+          borrower.clone(),
+          zero("uumee"),
+          Coin::new(10, "uatom"),
+        ))),
+        "uumee",
+      ),
+      (
+        leverage(UmeeMsgLeverage::Liquidate(LiquidateParams::new(
+          borrower.clone(),
+          Coin::new(10, "uumee"),
+          zero("uatom"),
+        ))),
+        "uatom",
+      ),
+      (
+        leverage(UmeeMsgLeverage::SupplyCollateral(
+          SupplyCollateralParams::new(zero("uumee")),
+        )),
+        "uumee",
+      ),
+      (
+        ExecuteMsg::Supply(SupplyParams::new(zero("uumee"))),
+        "uumee",
+      ),
+      (
+        ExecuteMsg::SupplyAndBond(SupplyParams::new(zero("uumee"))),
+        "uumee",
+      ),
+      (
+        ExecuteMsg::SupplyThenCollateralize(SupplyParams::new(zero("uumee"))),
+        "uumee",
+      ),
+    ] {
+      let err = execute(deps.as_mut(), mock_env(), mock_info("anyone", &[]), msg).unwrap_err();
+      assert!(
+        matches!(&err, ContractError::ZeroAmount { denom: got } if got == denom),
+        "{}",
+        err
+      );
     }
+
+    let msg = ExecuteMsg::BatchLeverage(vec![UmeeMsgLeverage::Borrow(BorrowParams::new(zero(
+      "uumee",
+    )))]);
+    let err = execute(deps.as_mut(), mock_env(), mock_info("anyone", &[]), msg).unwrap_err();
+    assert!(
+      err.to_string().contains("entry 0: Zero amount of uumee"),
+      "{}",
+      err
+    );
   }
 
-  Ok(miss_counter_resp)
-}
+  #[test]
+  fn supply_check_caps() {
+    let mut deps = mock_umee_dependencies();
+    let token = TOKEN_JSON.replace(r#""max_supply":"100000000000""#, r#""max_supply":"1500""#);
+    mock_registry(&mut deps, &[&token]);
+    mock_market_summary(&mut deps, "uumee", "1.0");
+    let supply = |amount: u128| SupplyParams::new(Coin::new(amount, "uumee")).with_check_caps();
+
+    let msg = ExecuteMsg::Supply(supply(600));
+    let err = execute(deps.as_mut(), mock_env(), mock_info("anyone", &[]), msg).unwrap_err();
+    assert!(err.to_string().contains("supply cap exceeded"), "{}", err);
+    assert!(err.to_string().contains("500uumee of headroom"), "{}", err);
+    let msg = ExecuteMsg::SupplyAndBond(supply(600));
+    execute(deps.as_mut(), mock_env(), mock_info("anyone", &[]), msg).unwrap_err();
+    let msg = ExecuteMsg::Supply(SupplyParams::new(Coin::new(600, "uumee")));
+    execute(deps.as_mut(), mock_env(), mock_info("anyone", &[]), msg).unwrap();
+
+    let msg = ExecuteMsg::Supply(supply(500));
+    let res = execute(deps.as_mut(), mock_env(), mock_info("anyone", &[]), msg).unwrap();
+    // the pre-check option never reaches the leverage module
+    let sent = to_json_binary(&res.messages[0].msg).unwrap();
+    assert!(!String::from_utf8_lossy(sent.as_slice()).contains("check_caps"));
+
+    // a schedule checks the supplies scheduled up to the checking entry
+    let msg = ExecuteMsg::SupplySchedule(vec![supply(300), supply(300)]);
+    let err = execute(deps.as_mut(), mock_env(), mock_info("anyone", &[]), msg).unwrap_err();
+    assert!(err.to_string().contains("entry 1: "), "{}", err);
+    assert!(err.to_string().contains("supply cap exceeded"), "{}", err);
+    let unchecked = SupplyParams::new(Coin::new(300, "uumee"));
+    let msg = ExecuteMsg::SupplySchedule(vec![unchecked, supply(200)]);
+    execute(deps.as_mut(), mock_env(), mock_info("anyone", &[]), msg).unwrap();
+    let msg = ExecuteMsg::BatchLeverage(vec![UmeeMsgLeverage::Supply(supply(600))]);
+    let err = execute(deps.as_mut(), mock_env(), mock_info("anyone", &[]), msg).unwrap_err();
+    assert!(err.to_string().contains("entry 0: "), "{}", err);
+    assert!(err.to_string().contains("supply cap exceeded"), "{}", err);
+  }
 
-// query_slash_window receives the slash window
-// query params and creates an query request to the native modules
-// with query_chain wrapping the response to the actual
-// SlashWindowResponse struct
-fn query_slash_window(
-  deps: Deps,
-  slash_window_params: SlashWindowParams,
-) -> StdResult<SlashWindowResponse> {
-  let request = QueryRequest::Custom(StructUmeeQuery::slash_window(slash_window_params));
+  #[test]
+  fn borrow_max_available() {
+    let mut deps = mock_umee_dependencies();
+    mock_market_summary(&mut deps, "uumee", "1.0");
+    let mut borrow = |params: BorrowParams| {
+      let msg = ExecuteMsg::Umee(UmeeMsg::Leverage(UmeeMsgLeverage::Borrow(params)));
+      let res = execute(deps.as_mut(), mock_env(), mock_info("anyone", &[]), msg).unwrap();
+      assert_eq!(ReplyOn::Never, res.messages[0].reply_on);
+      let sent = to_json_binary(&res.messages[0].msg).unwrap();
+      let clamped = res
+        .attributes
+        .into_iter()
+        .find(|attr| attr.key == "borrow_clamped");
+      (
+        String::from_utf8_lossy(sent.as_slice()).into_owned(),
+        clamped.map(|attr| attr.value),
+      )
+    };
 
-  let slash_window_resp: SlashWindowResponse;
-  match query_chain(deps, &request) {
-    Err(err) => {
-      return Err(err);
-    }
-    Ok(binary) => {
-      match from_json::<SlashWindowResponse>(&binary) {
-        Err(err) => {
-          return Err(err);
-        }
-        Ok(response) => slash_window_resp = response,
-      };
-    }
+    // the market has 390uumee available to borrow
+    let (sent, clamped) = borrow(BorrowParams::new(Coin::new(1000, "uumee")).with_max_available());
+    assert!(sent.contains(r#""amount":"390""#), "{}", sent);
+    assert!(!sent.contains("max_available"), "{}", sent);
+    assert_eq!(Some("1000uumee".to_string()), clamped);
+
+    let (sent, clamped) = borrow(BorrowParams::new(Coin::new(300, "uumee")).with_max_available());
+    assert!(sent.contains(r#""amount":"300""#), "{}", sent);
+    assert_eq!(None, clamped);
+
+    let (sent, _) = borrow(BorrowParams::new(Coin::new(1000, "uumee")));
+    assert!(sent.contains(r#""amount":"1000""#), "{}", sent);
+
+    // a batched borrow is clamped the same way
+    let msg = ExecuteMsg::BatchLeverage(vec![UmeeMsgLeverage::Borrow(
+      BorrowParams::new(Coin::new(1000, "uumee")).with_max_available(),
+    )]);
+    let res = execute(deps.as_mut(), mock_env(), mock_info("anyone", &[]), msg).unwrap();
+    let sent = to_json_binary(&res.messages[0].msg).unwrap();
+    assert!(String::from_utf8_lossy(sent.as_slice()).contains(r#""amount":"390""#));
+    let clamped = res
+      .attributes
+      .iter()
+      .find(|attr| attr.key == "borrow_clamped");
+    assert_eq!("1000uumee", clamped.unwrap().value);
   }
 
-  Ok(slash_window_resp)
-}
+  #[test]
+  fn repay_from_balance() {
+    let mut deps = mock_umee_dependencies();
+    let env = mock_env();
+    let contract = env.contract.address.clone();
+    let repay_from_balance = |borrower: &Addr| ExecuteMsg::RepayFromBalance {
+      borrower: borrower.clone(),
+      denom: "uumee".to_string(),
+    };
 
-// query_aggregate_prevote receives the get exchange rate base
-// query params and creates an query request to the native modules
-// with query_chain wrapping the response to the actual
-// AggregatePrevoteResponse struct
-fn query_aggregate_prevote(
-  deps: Deps,
-  aggregate_prevote_params: AggregatePrevoteParams,
-) -> StdResult<AggregatePrevoteResponse> {
-  let request = QueryRequest::Custom(StructUmeeQuery::aggregate_prevote(aggregate_prevote_params));
+    let msg = repay_from_balance(&contract);
+    let err = execute(deps.as_mut(), env.clone(), mock_info("anyone", &[]), msg).unwrap_err();
+    assert!(err.to_string().contains("no uumee held"), "{}", err);
+
+    mock_contract_position(&mut deps, 100, 0, &coins(70, "uumee"));
+    let msg = repay_from_balance(&Addr::unchecked("borrower"));
+    let err = execute(deps.as_mut(), env.clone(), mock_info("anyone", &[]), msg).unwrap_err();
+    assert!(err.to_string().contains("use a liquidation"), "{}", err);
+    let msg = repay_from_balance(&contract);
+    let res = execute(deps.as_mut(), env, mock_info("anyone", &[]), msg).unwrap();
+    assert_eq!(
+      CosmosMsg::Custom(StructUmeeMsg::from(UmeeMsgLeverage::Repay(
+        RepayParams::new(Coin::new(70, "uumee"))
+      ))),
+      res.messages[0].msg
+    );
+    assert_eq!("70uumee", res.attributes.last().unwrap().value);
+  }
 
-  let aggregate_prevote_resp: AggregatePrevoteResponse;
-  match query_chain(deps, &request) {
-    Err(err) => {
-      return Err(err);
-    }
-    Ok(binary) => {
-      match from_json::<AggregatePrevoteResponse>(&binary) {
-        Err(err) => {
-          return Err(err);
-        }
-        Ok(response) => aggregate_prevote_resp = response,
-      };
-    }
+  #[test]
+  fn with_memo() {
+    let mut deps = mock_umee_dependencies();
+    let with_memo = |memo: &str, inner: ExecuteMsg| ExecuteMsg::WithMemo {
+      memo: memo.to_string(),
+      inner: Box::new(inner),
+    };
+    let supply = ExecuteMsg::Supply(SupplyParams::new(Coin::new(100, "uumee")));
+
+    let msg = with_memo("keeper-42", supply.clone());
+    let res = execute(deps.as_mut(), mock_env(), mock_info("anyone", &[]), msg).unwrap();
+    let plain = execute(
+      deps.as_mut(),
+      mock_env(),
+      mock_info("anyone", &[]),
+      supply.clone(),
+    );
+    assert_eq!(plain.unwrap().messages, res.messages);
+    let memo = res.attributes.last().unwrap();
+    assert_eq!(
+      ("client_memo", "keeper-42"),
+      (memo.key.as_str(), memo.value.as_str())
+    );
+
+    let msg = with_memo("outer", with_memo("inner", supply.clone()));
+    execute(deps.as_mut(), mock_env(), mock_info("anyone", &[]), msg).unwrap_err();
+    let msg = with_memo(&"m".repeat(MAX_MEMO_LENGTH + 1), supply);
+    execute(deps.as_mut(), mock_env(), mock_info("anyone", &[]), msg).unwrap_err();
   }
 
-  Ok(aggregate_prevote_resp)
-}
+  #[test]
+  fn collateralize_all() {
+    let mut deps = mock_umee_dependencies();
+    let env = mock_env();
+    let contract = env.contract.address.clone();
+    let collateralize_all = |borrower: &Addr| ExecuteMsg::CollateralizeAll {
+      borrower: borrower.clone(),
+      denom: "uumee".to_string(),
+    };
 
-// query_aggregate_prevotes receives the get exchange rate base
-// query params and creates an query request to the native modules
-// with query_chain wrapping the response to the actual
-// AggregatePrevotesResponse struct
-fn query_aggregate_prevotes(
-  deps: Deps,
-  aggregate_prevotes_params: AggregatePrevotesParams,
-) -> StdResult<AggregatePrevotesResponse> {
-  let request = QueryRequest::Custom(StructUmeeQuery::aggregate_prevotes(
-    aggregate_prevotes_params,
-  ));
+    let msg = collateralize_all(&Addr::unchecked("borrower"));
+    let err = execute(deps.as_mut(), env.clone(), mock_info("anyone", &[]), msg).unwrap_err();
+    assert!(err.to_string().contains("contract position"), "{}", err);
+    let msg = collateralize_all(&contract);
+    let err = execute(deps.as_mut(), env.clone(), mock_info("anyone", &[]), msg).unwrap_err();
+    assert!(err.to_string().contains("no u/uumee held"), "{}", err);
+
+    mock_contract_position(&mut deps, 0, 0, &coins(250, "u/uumee"));
+    let msg = collateralize_all(&contract);
+    let res = execute(deps.as_mut(), env, mock_info("anyone", &[]), msg).unwrap();
+    assert_eq!(
+      CosmosMsg::Custom(StructUmeeMsg::from(UmeeMsgLeverage::Collateralize(
+        CollateralizeParams::new(Coin::new(250, "u/uumee"))
+      ))),
+      res.messages[0].msg
+    );
+    assert_eq!("250u/uumee", res.attributes[2].value);
+  }
 
-  let aggregate_prevotes_resp: AggregatePrevotesResponse;
-  match query_chain(deps, &request) {
-    Err(err) => {
-      return Err(err);
-    }
-    Ok(binary) => {
-      match from_json::<AggregatePrevotesResponse>(&binary) {
-        Err(err) => {
-          return Err(err);
-        }
-        Ok(response) => aggregate_prevotes_resp = response,
-      };
-    }
+  #[test]
+  fn full_position() {
+    let mut deps = mock_umee_dependencies();
+    let contract = mock_env().contract.address;
+    mock_contract_position(&mut deps, 40, 300, &[]);
+    mock_account_summary(&mut deps, contract.as_str(), "40", "150");
+
+    let msg = QueryMsg::FullPosition {
+      address: contract.clone(),
+    };
+    let res = query(deps.as_ref(), mock_env(), msg).unwrap();
+    let value: FullPositionResponse = from_json(&res).unwrap();
+    assert_eq!(coins(300, "u/uumee"), value.collateral);
+    assert_eq!(coins(40, "uumee"), value.borrowed);
+    assert_eq!(Decimal256::from_str("2000").unwrap(), value.supplied_value);
+    assert_eq!(
+      Decimal256::from_str("1500").unwrap(),
+      value.collateral_value
+    );
+    assert_eq!(Decimal256::from_str("40").unwrap(), value.borrowed_value);
+    assert_eq!("2000.00", value.supplied_display);
+    assert_eq!("40.00", value.borrowed_display);
+    assert_eq!(
+      Some(Decimal256::from_str("30").unwrap()),
+      value.health_factor
+    );
+
+    mock_account_summary(&mut deps, contract.as_str(), "0", "150");
+    let msg = QueryMsg::FullPosition { address: contract };
+    let res = query(deps.as_ref(), mock_env(), msg).unwrap();
+    assert_eq!(
+      None,
+      from_json::<FullPositionResponse>(&res)
+        .unwrap()
+        .health_factor
+    );
   }
 
-  Ok(aggregate_prevotes_resp)
-}
+  #[test]
+  fn position_pnl() {
+    let mut deps = mock_umee_dependencies();
+    let mut env = mock_env();
+    let pnl = QueryMsg::PositionPnl {
+      address: Addr::unchecked("borrower"),
+    };
+    let err = query(deps.as_ref(), env.clone(), pnl.clone()).unwrap_err();
+    assert!(err.to_string().contains("no position snapshot"), "{}", err);
 
-// query_aggregate_vote receives the get exchange rate base
-// query params and creates an query request to the native modules
-// with query_chain wrapping the response to the actual
-// AggregateVoteResponse struct
-fn query_aggregate_vote(
-  deps: Deps,
-  aggregate_vote_params: AggregateVoteParams,
-) -> StdResult<AggregateVoteResponse> {
-  let request = QueryRequest::Custom(StructUmeeQuery::aggregate_vote(aggregate_vote_params));
+    let msg = InstantiateMsg {
+      require_umee: None,
+      chain_queries: None,
+      check_denoms: None,
+    };
+    instantiate(deps.as_mut(), env.clone(), mock_info("creator", &[]), msg).unwrap();
+    mock_account_summary(&mut deps, "borrower", "500", "1000");
+    let snapshot = ExecuteMsg::SnapshotPosition {
+      address: Addr::unchecked("borrower"),
+    };
+    // a third party can't move the baseline of the borrower
+    let err = execute(
+      deps.as_mut(),
+      env.clone(),
+      mock_info("anyone", &[]),
+      snapshot.clone(),
+    )
+    .unwrap_err();
+    assert!(matches!(err, ContractError::Unauthorized {}));
+    let err = query(deps.as_ref(), env.clone(), pnl.clone()).unwrap_err();
+    assert!(err.to_string().contains("no position snapshot"), "{}", err);
+
+    execute(
+      deps.as_mut(),
+      env.clone(),
+      mock_info("creator", &[]),
+      snapshot.clone(),
+    )
+    .unwrap();
+    execute(
+      deps.as_mut(),
+      env.clone(),
+      mock_info("borrower", &[]),
+      snapshot,
+    )
+    .unwrap();
+
+    env.block.height += 5;
+    mock_account_summary(&mut deps, "borrower", "700", "1000");
+    let res = query(deps.as_ref(), env, pnl).unwrap();
+    let value: PositionPnlResponse = from_json(&res).unwrap();
+    assert_eq!(mock_env().block.height, value.snapshot_height);
+    assert_eq!(Decimal256::from_str("1500").unwrap(), value.snapshot_value);
+    assert_eq!(Decimal256::from_str("1300").unwrap(), value.current_value);
+    assert_eq!(Decimal256::zero(), value.profit);
+    assert_eq!(Decimal256::from_str("200").unwrap(), value.loss);
+    assert_eq!("0.00", value.profit_display);
+    assert_eq!("200.00", value.loss_display);
+  }
 
-  let aggregate_vote_resp: AggregateVoteResponse;
-  match query_chain(deps, &request) {
-    Err(err) => {
-      return Err(err);
-    }
-    Ok(binary) => {
-      match from_json::<AggregateVoteResponse>(&binary) {
-        Err(err) => {
-          return Err(err);
-        }
-        Ok(response) => aggregate_vote_resp = response,
+  #[test]
+  fn action_attributes() {
+    let mut deps = mock_umee_dependencies();
+    let mut run = |msg: ExecuteMsg| {
+      let res = execute(deps.as_mut(), mock_env(), mock_info("anyone", &[]), msg).unwrap();
+      let values = |key: &str| -> Vec<String> {
+        res
+          .attributes
+          .iter()
+          .filter(|attr| attr.key == key)
+          .map(|attr| attr.value.clone())
+          .collect()
       };
-    }
-  }
+      (values("action"), values("assigned_msg"))
+    };
 
-  Ok(aggregate_vote_resp)
-}
+    // the responses sending a single umee msg name it as the assigned msg
+    let (action, assigned) = run(ExecuteMsg::Supply(SupplyParams::new(Coin::new(
+      10, "uumee",
+    ))));
+    assert_eq!(
+      (vec!["supply".to_string()], vec!["supply".to_string()]),
+      (action, assigned)
+    );
+    // the ones sending several msgs only name the action
+    let (action, assigned) = run(ExecuteMsg::Rebalance(RebalanceParams {
+      decollateralize: coins(10, "u/uumee"),
+      collateralize: coins(10, "u/uatom"),
+    }));
+    assert_eq!(vec!["rebalance".to_string()], action);
+    assert!(assigned.is_empty());
+  }
 
-// query_aggregate_votes receives the get exchange rate base
-// query params and creates an query request to the native modules
-// with query_chain wrapping the response to the actual
-// AggregateVotesResponse struct
-fn query_aggregate_votes(
-  deps: Deps,
-  aggregate_votes_params: AggregateVotesParams,
-) -> StdResult<AggregateVotesResponse> {
-  let request = QueryRequest::Custom(StructUmeeQuery::aggregate_votes(aggregate_votes_params));
+  #[test]
+  fn assigned_ids() {
+    let deps = mock_umee_dependencies();
+    let res = query(deps.as_ref(), mock_env(), QueryMsg::AssignedIds {}).unwrap();
+    let value: AssignedIdsResponse = from_json(&res).unwrap();
+    assert_eq!(ASSIGNED_QUERIES.len(), value.queries.len());
+    assert!(value.queries.contains(&"account_summary".to_string()));
+    assert_eq!(MsgTypes::ALL.len(), value.messages.len());
+    assert!(value.messages.contains(&(
+      MsgTypes::AssignedMsgSupplyCollateralize,
+      "supply_collateral".to_string()
+    )));
+  }
 
-  let aggregate_votes_resp: AggregateVotesResponse;
-  match query_chain(deps, &request) {
-    Err(err) => {
-      return Err(err);
-    }
-    Ok(binary) => {
-      match from_json::<AggregateVotesResponse>(&binary) {
-        Err(err) => {
-          return Err(err);
-        }
-        Ok(response) => aggregate_votes_resp = response,
-      };
-    }
+  #[test]
+  fn withdraw_all() {
+    let mut deps = mock_umee_dependencies();
+    let env = mock_env();
+    let contract = env.contract.address.clone();
+    let info = mock_info("anyone", &[]);
+    let withdraw_all = |supplier: &Addr| ExecuteMsg::WithdrawAll {
+      supplier: supplier.clone(),
+    };
+    mock_contract_position(&mut deps, 0, 0, &[]);
+
+    let err = execute(
+      deps.as_mut(),
+      env.clone(),
+      info.clone(),
+      withdraw_all(&Addr::unchecked("supplier")),
+    )
+    .unwrap_err();
+    assert!(err.to_string().contains("not on supplier"), "{}", err);
+
+    let err = execute(
+      deps.as_mut(),
+      env.clone(),
+      info.clone(),
+      withdraw_all(&contract),
+    )
+    .unwrap_err();
+    assert!(
+      err.to_string().contains("without any supplied denom"),
+      "{}",
+      err
+    );
+
+    mock_chain_response(
+      &mut deps,
+      StructUmeeQuery::account_balances(AccountBalancesParams::new(contract.clone())),
+      &AccountBalancesResponse {
+        supplied: vec![Coin::new(100, "uatom"), Coin::new(200, "uumee")],
+        collateral: vec![],
+        borrowed: vec![],
+      },
+    );
+    mock_market_summary(&mut deps, "uumee", "1");
+    mock_custom_response(
+      &mut deps,
+      StructUmeeQuery::market_summary(MarketSummaryParams {
+        denom: String::from("uatom"),
+      }),
+      Binary::from(
+        br#"{"symbol_denom":"ATOM","exponent":6,"oracle_price":"10","utoken_exchange_rate":"1","supply_apy":"0.05","borrow_apy":"0.1","supplied":"1000","reserved":"0","collateral":"0","borrowed":"1000","liquidity":"0","maximum_borrow":"0","maximum_collateral":"0","minimum_liquidity":"0","utoken_supply":"1000","available_borrow":"0","available_withdraw":"0","available_collateralize":"0"}"#
+          .as_slice(),
+      ),
+    );
+
+    let res = execute(deps.as_mut(), env, info, withdraw_all(&contract)).unwrap();
+    assert_eq!(1, res.messages.len());
+    let max_withdraw = StructUmeeMsg::from(UmeeMsgLeverage::MaxWithdraw(
+      MsgMaxWithdrawParams::new("uumee"),
+    ));
+    assert_eq!(CosmosMsg::Custom(max_withdraw), res.messages[0].msg);
+    assert_eq!("uatom", res.attributes[2].value);
   }
 
-  Ok(aggregate_votes_resp)
-}
+  #[test]
+  fn migrate_legacy_state() {
+    let mut deps = mock_umee_dependencies();
+    // the first deployments stored only the owner under the state key
+    deps.storage.set(b"state", br#"{"owner":"creator"}"#);
+
+    let res = migrate(deps.as_mut(), mock_env(), MigrateMsg {}).unwrap();
+    assert_eq!(res.attributes[0].key, "action");
+    assert_eq!(res.attributes[0].value, "migrate");
+    assert_eq!("1", res.attributes[1].value);
+    assert_eq!(
+      STATE.load(&deps.storage).unwrap(),
+      StateV2 {
+        owner: Addr::unchecked("creator"),
+        pending_owner: None,
+        umee_enabled: false,
+        chain_queries: default_chain_queries(),
+        check_denoms: true,
+      }
+    );
+    assert_eq!(
+      CURRENT_STATE_VERSION,
+      STATE_VERSION.load(&deps.storage).unwrap()
+    );
+    assert!(LEGACY_STATE.may_load(&deps.storage).unwrap().is_none());
+
+    // migrating the current layout keeps the state
+    let res = migrate(deps.as_mut(), mock_env(), MigrateMsg {}).unwrap();
+    assert_eq!("2", res.attributes[1].value);
+    assert_eq!(
+      Addr::unchecked("creator"),
+      STATE.load(&deps.storage).unwrap().owner
+    );
+
+    STATE_VERSION.save(&mut deps.storage, &3).unwrap();
+    let err = migrate(deps.as_mut(), mock_env(), MigrateMsg {}).unwrap_err();
+    assert!(err.to_string().contains("newer"), "{}", err);
+  }
 
-// query_oracle_parameters receives the get exchange rate base
-// query params and creates an query request to the native modules
-// with query_chain wrapping the response to the actual
-// OracleParametersResponse struct
-fn query_oracle_parameters(
-  deps: Deps,
-  oracle_parameters_params: OracleParametersParams,
-) -> StdResult<OracleParametersResponse> {
-  let request = QueryRequest::Custom(StructUmeeQuery::oracle_parameters(oracle_parameters_params));
+  #[test]
+  fn simulate_supply() {
+    let mut deps = mock_umee_dependencies();
+    mock_registry(&mut deps, &[TOKEN_JSON]);
+    mock_market_summary(&mut deps, "uumee", "1.1");
+    let simulate = QueryMsg::Umee(Box::new(UmeeQuery::Leverage(
+      UmeeQueryLeverage::SimulateSupply(SimulateSupplyParams {
+        denom: String::from("uumee"),
+        amount: Uint128::new(1000),
+      }),
+    )));
+
+    let res = query(deps.as_ref(), mock_env(), simulate).unwrap();
+    let value: SimulateSupplyResponse = from_json(&res).unwrap();
+    // 1000 / 1.1 rounded down
+    assert_eq!(value.utokens, Coin::new(909, "u/uumee"));
+  }
 
-  let oracle_parameters_resp: OracleParametersResponse;
-  match query_chain(deps, &request) {
-    Err(err) => {
-      return Err(err);
+  #[test]
+  fn bad_debt_total() {
+    let mut deps = mock_umee_dependencies();
+    mock_custom_response(
+      &mut deps,
+      StructUmeeQuery::bad_debts_parameters(BadDebtsParams {}),
+      Binary::from(
+        br#"{"targets":[{"address":"alice","denom":"uumee"},{"address":"bob","denom":"uumee"}]}"#
+          .as_slice(),
+      ),
+    );
+    for (address, borrowed) in [("alice", 500_000), ("bob", 1_500_000)] {
+      mock_chain_response(
+        &mut deps,
+        StructUmeeQuery::account_balances(AccountBalancesParams::new(Addr::unchecked(address))),
+        &AccountBalancesResponse {
+          supplied: vec![],
+          collateral: vec![],
+          borrowed: vec![Coin::new(borrowed, "uumee"), Coin::new(7, "uatom")],
+        },
+      );
     }
-    Ok(binary) => {
-      match from_json::<OracleParametersResponse>(&binary) {
-        Err(err) => {
-          return Err(err);
-        }
-        Ok(response) => oracle_parameters_resp = response,
-      };
+    mock_market_summary(&mut deps, "uumee", "1");
+
+    let total = QueryMsg::Umee(Box::new(UmeeQuery::Leverage(
+      UmeeQueryLeverage::BadDebtTotal(BadDebtTotalParams {}),
+    )));
+    let res = query(deps.as_ref(), mock_env(), total).unwrap();
+    let value: BadDebtTotalResponse = from_json(&res).unwrap();
+    assert_eq!(value.by_denom, coins(2_000_000, "uumee"));
+    // 2 UMEE at 0.01 USD
+    assert_eq!(value.total_value, Decimal256::from_str("0.02").unwrap());
+  }
+
+  #[test]
+  fn top_supply_markets() {
+    let mut deps = mock_umee_dependencies();
+    let markets = [("uumee", "0.05"), ("uatom", "0.12"), ("uosmo", "0.05")];
+    let tokens: Vec<String> = markets
+      .iter()
+      .map(|(denom, _)| TOKEN_JSON.replace("uumee", denom))
+      .collect();
+    mock_registry(
+      &mut deps,
+      &tokens.iter().map(String::as_str).collect::<Vec<_>>(),
+    );
+    for (denom, supply_apy) in markets {
+      mock_market_summary_apy(&mut deps, denom, "1", supply_apy);
     }
+    let top = |limit: u32| {
+      let msg = QueryMsg::Umee(Box::new(UmeeQuery::Leverage(
+        UmeeQueryLeverage::TopSupplyMarkets(TopSupplyMarketsParams { limit }),
+      )));
+      let res = query(deps.as_ref(), mock_env(), msg).unwrap();
+      from_json::<TopSupplyMarketsResponse>(&res).unwrap().markets
+    };
+
+    let apy = |value: &str| Decimal256::from_str(value).unwrap();
+    assert_eq!(
+      top(2),
+      vec![
+        (String::from("uatom"), apy("0.12")),
+        (String::from("uosmo"), apy("0.05")),
+      ]
+    );
+    assert_eq!(3, top(100).len());
   }
 
-  Ok(oracle_parameters_resp)
-}
+  #[test]
+  fn duplicate_denoms_in_batches() {
+    let mut deps = mock_umee_dependencies();
+    let info = mock_info("anyone", &[]);
+
+    let rebalance = ExecuteMsg::Rebalance(RebalanceParams {
+      decollateralize: vec![],
+      collateralize: vec![Coin::new(5, "u/uatom"), Coin::new(7, "u/uatom")],
+    });
+    let err = execute(deps.as_mut(), mock_env(), info, rebalance).unwrap_err();
+    assert!(
+      err.to_string().contains("u/uatom more than once"),
+      "{}",
+      err
+    );
+
+    let batch = QueryMsg::Umee(Box::new(UmeeQuery::Oracle(
+      UmeeQueryOracle::ExchangeRatesBatch(ExchangeRatesBatchParams {
+        denoms: vec![String::from("UMEE"), String::from("UMEE")],
+      }),
+    )));
+    let err = query(deps.as_ref(), mock_env(), batch).unwrap_err();
+    assert!(err.to_string().contains("UMEE more than once"), "{}", err);
+  }
 
-fn query_medians(deps: Deps, medians_params: MediansParams) -> StdResult<MediansParamsResponse> {
-  let request = QueryRequest::Custom(StructUmeeQuery::medians_params(medians_params));
+  #[test]
+  fn utoken_supply() {
+    let mut deps = mock_umee_dependencies();
+    mock_registry(&mut deps, &[TOKEN_JSON]);
+    deps.querier.base.update_balance(
+      "alice",
+      vec![Coin::new(300, "u/uumee"), Coin::new(5, "uumee")],
+    );
+    deps
+      .querier
+      .base
+      .update_balance("bob", coins(200, "u/uumee"));
+
+    let msg = QueryMsg::Umee(Box::new(UmeeQuery::Leverage(
+      UmeeQueryLeverage::UTokenSupply(UTokenSupplyParams {
+        denom: String::from("uumee"),
+      }),
+    )));
+    let res = query(deps.as_ref(), mock_env(), msg).unwrap();
+    let value: UTokenSupplyResponse = from_json(&res).unwrap();
+    assert_eq!(value.utoken_supply, Coin::new(500, "u/uumee"));
+  }
 
-  let medians_response: MediansParamsResponse;
-  match query_chain(deps, &request) {
-    Err(err) => {
-      return Err(err);
-    }
-    Ok(binary) => {
-      match from_json::<MediansParamsResponse>(&binary) {
-        Err(err) => {
-          return Err(err);
-        }
-        Ok(response) => medians_response = response,
-      };
-    }
+  #[test]
+  fn denom_status() {
+    let mut deps = mock_umee_dependencies();
+    let no_collateral = TOKEN_JSON
+      .replace("uumee", "uatom")
+      .replace(
+        r#""collateral_weight":"0.35""#,
+        r#""collateral_weight":"0""#,
+      )
+      .replace(
+        r#""enable_msg_borrow":true"#,
+        r#""enable_msg_borrow":false"#,
+      );
+    let blacklisted = TOKEN_JSON
+      .replace("uumee", "ujuno")
+      .replace(r#""blacklist":false"#, r#""blacklist":true"#);
+    mock_registry(&mut deps, &[TOKEN_JSON, &no_collateral, &blacklisted]);
+    let denom_status = |denom: &str| {
+      let msg = QueryMsg::Umee(Box::new(UmeeQuery::Leverage(
+        UmeeQueryLeverage::DenomStatus(DenomStatusParams {
+          denom: denom.to_string(),
+        }),
+      )));
+      from_json::<DenomStatusResponse>(&query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap()
+    };
+
+    let all_enabled = DenomStatusResponse {
+      registered: true,
+      collateral_enabled: true,
+      borrow_enabled: true,
+      blacklisted: false,
+    };
+    assert_eq!(all_enabled, denom_status("uumee"));
+    assert_eq!(
+      DenomStatusResponse {
+        collateral_enabled: false,
+        borrow_enabled: false,
+        ..all_enabled.clone()
+      },
+      denom_status("uatom")
+    );
+    assert_eq!(
+      DenomStatusResponse {
+        collateral_enabled: false,
+        blacklisted: true,
+        ..all_enabled
+      },
+      denom_status("ujuno")
+    );
+    assert_eq!(DenomStatusResponse::default(), denom_status("uosmo"));
   }
 
-  Ok(medians_response)
-}
+  #[test]
+  fn market_enabled() {
+    let mut deps = mock_umee_dependencies();
+    let collateral_only = TOKEN_JSON.replace("uumee", "uatom").replace(
+      r#""enable_msg_borrow":true"#,
+      r#""enable_msg_borrow":false"#,
+    );
+    let blacklisted = TOKEN_JSON
+      .replace("uumee", "ujuno")
+      .replace(r#""blacklist":false"#, r#""blacklist":true"#);
+    mock_registry(&mut deps, &[TOKEN_JSON, &collateral_only, &blacklisted]);
+    let market_enabled = |denom: &str| {
+      let msg = QueryMsg::Umee(Box::new(UmeeQuery::Leverage(
+        UmeeQueryLeverage::MarketEnabled(MarketEnabledParams {
+          denom: denom.to_string(),
+        }),
+      )));
+      query(deps.as_ref(), mock_env(), msg)
+        .map(|res| from_json::<MarketEnabledResponse>(&res).unwrap())
+    };
 
-fn query_median_deviations(
-  deps: Deps,
-  medians_deviations_params: MedianDeviationsParams,
-) -> StdResult<MedianDeviationsParamsResponse> {
-  let request = QueryRequest::Custom(StructUmeeQuery::median_deviations_params(
-    medians_deviations_params,
-  ));
+    let enabled = |supply_enabled, borrow_enabled, collateral_enabled| MarketEnabledResponse {
+      supply_enabled,
+      borrow_enabled,
+      collateral_enabled,
+    };
+    assert_eq!(enabled(true, true, true), market_enabled("uumee").unwrap());
+    assert_eq!(enabled(true, false, true), market_enabled("uatom").unwrap());
+    assert_eq!(
+      enabled(false, false, false),
+      market_enabled("ujuno").unwrap()
+    );
+    let err = market_enabled("uosmo").unwrap_err();
+    assert!(err.to_string().contains("uosmo is not a registered token"));
+  }
 
-  let median_deviations_response: MedianDeviationsParamsResponse;
-  match query_chain(deps, &request) {
-    Err(err) => {
-      return Err(err);
-    }
-    Ok(binary) => {
-      match from_json::<MedianDeviationsParamsResponse>(&binary) {
-        Err(err) => {
-          return Err(err);
-        }
-        Ok(response) => median_deviations_response = response,
-      };
+  #[test]
+  fn invalid_umee_queries() {
+    let deps = mock_umee_dependencies();
+    for (json, expected) in [
+      (
+        r#"{"leverage":{"account_summary":{"address":"Umee1Addr"}}}"#,
+        "account_summary: invalid address Umee1Addr",
+      ),
+      (
+        r#"{"leverage":{"market_summary":{"denom":""}}}"#,
+        "market_summary: empty denom",
+      ),
+      (
+        r#"{"leverage":{"simulate_liquidation":{"borrower":"addr","repay":{"denom":"uumee","amount":"0"},"reward_denom":"uatom"}}}"#,
+        "simulate_liquidation: zero amount",
+      ),
+      (
+        r#"{"leverage":{"market_summary":{"denom":"ATOM"}}}"#,
+        "market_summary: ATOM looks like an oracle symbol denom",
+      ),
+      (
+        r#"{"oracle":{"exchange_rates":{"denom":"uatom"}}}"#,
+        "exchange_rates: uatom looks like a leverage base denom",
+      ),
+      (
+        r#"{"oracle":{"medians":{"denom":"ibc/27394FB092D2ECCD56123C74F36E4C1F926001CE"}}}"#,
+        "medians: ibc/27394FB092D2ECCD56123C74F36E4C1F926001CE looks like a leverage base",
+      ),
+    ] {
+      let msg = QueryMsg::Umee(Box::new(from_json(json).unwrap()));
+      let err = query(deps.as_ref(), mock_env(), msg).unwrap_err();
+      assert!(err.to_string().contains(expected), "{}", err);
     }
   }
 
-  Ok(median_deviations_response)
-}
-
-// -----------------------------------TESTS---------------------------------------
+  #[test]
+  fn borrow_factors() {
+    let mut deps = mock_umee_dependencies();
+    let atom = TOKEN_JSON.replace("uumee", "uatom").replace(
+      r#""collateral_weight":"0.35""#,
+      r#""collateral_weight":"0.8""#,
+    );
+    mock_registry(&mut deps, &[TOKEN_JSON, &atom]);
+    let borrow_factors = |denom: &str| {
+      let msg = QueryMsg::Umee(Box::new(UmeeQuery::Leverage(
+        UmeeQueryLeverage::BorrowFactors(BorrowFactorsParams {
+          denom: denom.to_string(),
+        }),
+      )));
+      query(deps.as_ref(), mock_env(), msg)
+        .map(|res| from_json::<BorrowFactorsResponse>(&res).unwrap())
+    };
 
-#[cfg(test)]
-mod tests {
-  use super::*;
-  use cosmwasm_std::testing::{mock_dependencies_with_balance, mock_env, mock_info};
-  use cosmwasm_std::{coins, from_binary};
+    let umee = borrow_factors("uumee").unwrap();
+    assert_eq!(Decimal::from_str("0.35").unwrap(), umee.collateral_weight);
+    assert_eq!(Decimal::from_str("2").unwrap(), umee.borrow_factor);
+    let atom = borrow_factors("uatom").unwrap();
+    assert_eq!(Decimal::from_str("0.8").unwrap(), atom.collateral_weight);
+    assert_eq!(Decimal::from_str("1.25").unwrap(), atom.borrow_factor);
+    borrow_factors("uosmo").unwrap_err();
+  }
 
   #[test]
-  fn proper_initialization() {
-    let mut deps = mock_dependencies_with_balance(&coins(2, "token"));
+  fn market_row() {
+    let mut deps = mock_umee_dependencies();
+    mock_registry(&mut deps, &[TOKEN_JSON]);
+    mock_market_summary(&mut deps, "uumee", "1.0");
+    let market_row = |denom: &str| {
+      let msg = QueryMsg::Umee(Box::new(UmeeQuery::Leverage(UmeeQueryLeverage::MarketRow(
+        MarketRowParams {
+          denom: denom.to_string(),
+        },
+      ))));
+      query(deps.as_ref(), mock_env(), msg).map(|res| from_json::<MarketRowResponse>(&res).unwrap())
+    };
 
-    let msg = InstantiateMsg {};
-    let info = mock_info("creator", &coins(1000, "earth"));
+    let row = market_row("uumee").unwrap();
+    assert_eq!("UMEE", row.symbol_denom);
+    assert_eq!(Decimal256::from_str("0.05").unwrap(), row.supply_apy);
+    assert_eq!(Decimal256::from_str("0.1").unwrap(), row.borrow_apy);
+    assert_eq!(Decimal256::from_str("1000").unwrap(), row.total_supplied);
+    assert_eq!(Decimal256::from_str("500").unwrap(), row.total_borrowed);
+    assert_eq!(Decimal256::from_str("0.5").unwrap(), row.utilization);
+    assert_eq!(Decimal256::from_str("0.01").unwrap(), row.oracle_price);
+    market_row("uatom").unwrap_err();
+  }
 
-    // we can just call .unwrap() to assert this was a success
-    let res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
-    assert_eq!(0, res.messages.len());
+  #[test]
+  fn reserve_info() {
+    let mut deps = mock_umee_dependencies();
+    mock_registry(&mut deps, &[TOKEN_JSON]);
+    mock_market_summary(&mut deps, "uumee", "1.0");
+    let reserve_info = |denom: &str| {
+      let msg = QueryMsg::Umee(Box::new(UmeeQuery::Leverage(
+        UmeeQueryLeverage::ReserveInfo(ReserveInfoParams {
+          denom: denom.to_string(),
+        }),
+      )));
+      query(deps.as_ref(), mock_env(), msg)
+        .map(|res| from_json::<ReserveInfoResponse>(&res).unwrap())
+    };
 
-    // it worked, let's query the state
-    let res = query(deps.as_ref(), mock_env(), QueryMsg::GetOwner {}).unwrap();
-    let value: OwnerResponse = from_binary(&res).unwrap();
-    assert_eq!("creator", value.owner);
+    let info = reserve_info("uumee").unwrap();
+    assert_eq!(Decimal::from_str("0.1").unwrap(), info.reserve_factor);
+    assert_eq!(Coin::new(10, "uumee"), info.reserves);
+    // 10uumee at 0.01 per UMEE
+    assert_eq!(
+      Decimal256::from_str("0.0000001").unwrap(),
+      info.reserves_value
+    );
+    reserve_info("uatom").unwrap_err();
   }
 
   #[test]
-  fn change_owner() {
-    let mut deps = mock_dependencies_with_balance(&coins(2, "token"));
+  fn collateral_value_at_price() {
+    let mut deps = mock_umee_dependencies();
+    mock_market_summary(&mut deps, "uumee", "1.5");
+    mock_chain_response(
+      &mut deps,
+      StructUmeeQuery::account_balances(AccountBalancesParams::new(Addr::unchecked("borrower"))),
+      &AccountBalancesResponse {
+        supplied: vec![],
+        collateral: coins(2_000_000, "u/uumee"),
+        borrowed: vec![],
+      },
+    );
+    let value_at = |price_overrides: Vec<DecCoin>| {
+      let msg = QueryMsg::Umee(Box::new(UmeeQuery::Leverage(
+        UmeeQueryLeverage::CollateralValueAtPrice(CollateralValueAtPriceParams {
+          address: Addr::unchecked("borrower"),
+          price_overrides,
+        }),
+      )));
+      let res = query(deps.as_ref(), mock_env(), msg).unwrap();
+      from_json::<CollateralValueAtPriceResponse>(&res)
+        .unwrap()
+        .collateral_value
+    };
+    let price = |denom: &str, amount: &str| DecCoin {
+      denom: denom.to_string(),
+      amount: Decimal256::from_str(amount).unwrap(),
+    };
 
-    let first_owner = "creator";
-    let msg = InstantiateMsg {};
-    let info = mock_info(first_owner, &coins(2, "token"));
-    let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+    // 2 uTokens redeem 3 UMEE, at the 0.01 oracle price
+    assert_eq!(Decimal256::from_str("0.03").unwrap(), value_at(vec![]));
+    assert_eq!(
+      Decimal256::from_str("0.021").unwrap(),
+      value_at(vec![price("umee", "0.007")])
+    );
+    assert_eq!(
+      Decimal256::from_str("6").unwrap(),
+      value_at(vec![price("uatom", "9"), price("uumee", "2")])
+    );
+  }
 
-    let res = query(deps.as_ref(), mock_env(), QueryMsg::GetOwner {}).unwrap();
-    let value: OwnerResponse = from_binary(&res).unwrap();
-    assert_eq!(first_owner, value.owner);
+  #[test]
+  fn liquidation_price() {
+    let mut deps = mock_umee_dependencies();
+    mock_registry(&mut deps, &[TOKEN_JSON]);
+    mock_market_summary(&mut deps, "uumee", "1.0");
+    let mock_borrower = |deps: &mut UmeeMockDeps, borrowed_value: &str, borrowed: u128| {
+      mock_account_summary(deps, "borrower", borrowed_value, "1000");
+      mock_chain_response(
+        deps,
+        StructUmeeQuery::account_balances(AccountBalancesParams::new(Addr::unchecked("borrower"))),
+        &AccountBalancesResponse {
+          supplied: vec![],
+          collateral: coins(1_000_000_000, "u/uumee"),
+          borrowed: coins(borrowed, "uumee"),
+        },
+      );
+    };
+    let liquidation_price = |deps: &UmeeMockDeps, denom: &str| {
+      let msg = QueryMsg::Umee(Box::new(UmeeQuery::Leverage(
+        UmeeQueryLeverage::LiquidationPrice(LiquidationPriceParams {
+          address: Addr::unchecked("borrower"),
+          collateral_denom: denom.to_string(),
+        }),
+      )));
+      query(deps.as_ref(), mock_env(), msg)
+        .map(|res| from_json::<LiquidationPriceResponse>(&res).unwrap().price)
+    };
 
-    let new_owner = "new_owner";
+    // 1000 UMEE at 0.01 add 5 to the 1200 threshold, the 2.5 of debt above
+    // the rest of the threshold is reached at a 0.005 price
+    mock_borrower(&mut deps, "1197.5", 0);
+    assert_eq!(
+      Decimal256::from_str("0.005").unwrap(),
+      liquidation_price(&deps, "uumee").unwrap()
+    );
+    mock_borrower(&mut deps, "100", 0);
+    assert_eq!(
+      Decimal256::zero(),
+      liquidation_price(&deps, "uumee").unwrap()
+    );
+    mock_borrower(&mut deps, "1197.5", 2_000_000_000);
+    let err = liquidation_price(&deps, "uumee").unwrap_err();
+    assert!(err.to_string().contains("outweighs"), "{}", err);
+    liquidation_price(&deps, "uosmo").unwrap_err();
+  }
 
-    // only the original creator can change the owner the counter
-    let auth_info = mock_info(new_owner, &coins(2, "token"));
-    let msg = ExecuteMsg::ChangeOwner {
-      new_owner: cosmwasm_std::Addr::unchecked(new_owner),
+  #[test]
+  fn composites_from_canned_data() {
+    let borrower = Addr::unchecked("borrower");
+    let mut querier = MockLeverageQuerier {
+      registered_tokens: from_json(format!(r#"{{"registry":[{}]}}"#, TOKEN_JSON)).unwrap(),
+      ..MockLeverageQuerier::default()
     };
-    let res = execute(deps.as_mut(), mock_env(), auth_info, msg);
-    match res {
-      Err(ContractError::Unauthorized {}) => {}
-      _ => panic!("Must return unauthorized error"),
-    }
+    querier.markets.insert(
+      "uumee".to_string(),
+      MarketSummaryResponse {
+        symbol_denom: "UMEE".to_string(),
+        exponent: 6,
+        oracle_price: Decimal256::from_str("2").unwrap(),
+        utoken_exchange_rate: Decimal256::one(),
+        supplied: Decimal256::from_str("3000000").unwrap(),
+        borrowed: Decimal256::from_str("1000000").unwrap(),
+        reserved: Decimal256::from_str("25").unwrap(),
+        ..MarketSummaryResponse::default()
+      },
+    );
+    querier.summaries.insert(
+      borrower.clone(),
+      AccountSummaryResponse {
+        borrowed_value: Decimal256::from_str("120").unwrap(),
+        liquidation_threshold: Decimal256::from_str("100").unwrap(),
+        ..AccountSummaryResponse::default()
+      },
+    );
+
+    let liquidatable = query_is_liquidatable(&querier, borrower.clone()).unwrap();
+    assert!(liquidatable.liquidatable);
+    let reserves = query_all_reserves(&querier, AllReservesParams {}).unwrap();
+    assert_eq!(coins(25, "uumee"), reserves.reserves);
+    let tvl = query_total_value_locked(&querier).unwrap();
+    assert_eq!(Decimal256::from_str("6").unwrap(), tvl.total_supplied_value);
+    assert_eq!(Decimal256::from_str("2").unwrap(), tvl.total_borrowed_value);
+    // the balances of the borrower were never canned
+    let err = query_full_position(&querier, borrower).unwrap_err();
+    assert!(
+      err.to_string().contains("no canned account balances"),
+      "{}",
+      err
+    );
+  }
 
-    let auth_info = mock_info(first_owner, &coins(2, "token"));
-    let msg = ExecuteMsg::ChangeOwner {
-      new_owner: cosmwasm_std::Addr::unchecked(new_owner),
+  #[test]
+  fn time_to_liquidation() {
+    let borrower = Addr::unchecked("borrower");
+    let mut querier = MockLeverageQuerier::default();
+    querier.markets.insert(
+      "uumee".to_string(),
+      MarketSummaryResponse {
+        symbol_denom: "UMEE".to_string(),
+        exponent: 6,
+        oracle_price: Decimal256::from_str("2").unwrap(),
+        borrow_apy: Decimal256::from_str("0.1").unwrap(),
+        ..MarketSummaryResponse::default()
+      },
+    );
+    querier.balances.insert(
+      borrower.clone(),
+      AccountBalancesResponse {
+        borrowed: coins(50_000000, "uumee"),
+        ..AccountBalancesResponse::default()
+      },
+    );
+    let mut time_to_liquidation = |borrowed_value: &str, liquidation_threshold: &str| {
+      querier.summaries.insert(
+        borrower.clone(),
+        AccountSummaryResponse {
+          borrowed_value: Decimal256::from_str(borrowed_value).unwrap(),
+          liquidation_threshold: Decimal256::from_str(liquidation_threshold).unwrap(),
+          ..AccountSummaryResponse::default()
+        },
+      );
+      let params = TimeToLiquidationParams {
+        address: borrower.clone(),
+        blocks_per_year: 1000,
+      };
+      query_time_to_liquidation(&querier, params).unwrap().blocks
     };
-    let _res = execute(deps.as_mut(), mock_env(), auth_info, msg).unwrap();
 
-    let res = query(deps.as_ref(), mock_env(), QueryMsg::GetOwner {}).unwrap();
-    let value: OwnerResponse = from_binary(&res).unwrap();
-    assert_eq!(new_owner, value.owner);
+    // 10 of interest a year on 100 borrowed, a year of headroom
+    assert_eq!(Some(1001), time_to_liquidation("100", "110"));
+    assert_eq!(Some(1), time_to_liquidation("110", "110"));
+    assert_eq!(None, time_to_liquidation("111", "110"));
+    assert_eq!(None, time_to_liquidation("0", "110"));
+  }
+
+  #[test]
+  fn denom_aliases() {
+    let mut deps = mock_umee_dependencies();
+    let msg = InstantiateMsg {
+      require_umee: None,
+      chain_queries: None,
+      check_denoms: None,
+    };
+    instantiate(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+    mock_registry(&mut deps, &[TOKEN_JSON]);
+    mock_market_summary(&mut deps, "uumee", "1.0");
+    let set_alias = |alias: &str, denom: &str| ExecuteMsg::SetDenomAlias {
+      alias: alias.to_string(),
+      denom: denom.to_string(),
+    };
+    let resolve = |deps: &OwnedDeps<_, _, _, _>, alias: &str| {
+      let msg = QueryMsg::ResolveAlias {
+        alias: alias.to_string(),
+      };
+      let res = query(deps.as_ref(), mock_env(), msg).unwrap();
+      from_json::<ResolveAliasResponse>(&res).unwrap().denom
+    };
+
+    let msg = set_alias("UMEE", "uumee");
+    let err = execute(deps.as_mut(), mock_env(), mock_info("anyone", &[]), msg).unwrap_err();
+    assert!(matches!(err, ContractError::Unauthorized {}));
+    let msg = set_alias("UMEE2", "UMEE");
+    let err = execute(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap_err();
+    assert!(matches!(err, ContractError::InvalidDenom { .. }));
+    assert_eq!(None, resolve(&deps, "UMEE"));
+
+    let msg = set_alias("UMEE", "uumee");
+    execute(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+    assert_eq!(Some("uumee".to_string()), resolve(&deps, "UMEE"));
+
+    // the alias is resolved before the symbol-like denom is rejected
+    let msg = QueryMsg::Umee(Box::new(UmeeQuery::Leverage(UmeeQueryLeverage::MarketRow(
+      MarketRowParams {
+        denom: "UMEE".to_string(),
+      },
+    ))));
+    let row: MarketRowResponse = from_json(query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+    assert_eq!("uumee", row.denom);
+    let msg = ExecuteMsg::Umee(UmeeMsg::Leverage(UmeeMsgLeverage::Borrow(
+      BorrowParams::new(Coin::new(10, "UMEE")),
+    )));
+    let res = execute(deps.as_mut(), mock_env(), mock_info("anyone", &[]), msg).unwrap();
+    let sent = to_json_binary(&res.messages[0].msg).unwrap();
+    assert!(String::from_utf8_lossy(sent.as_slice()).contains(r#""denom":"uumee""#));
+
+    // a registered denom can't be shadowed by an alias
+    let msg = set_alias("uumee", "uatom");
+    let err = execute(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap_err();
+    assert!(
+      matches!(err, ContractError::InvalidDenom { ref denom, .. } if denom == "uumee"),
+      "{}",
+      err
+    );
+    assert_eq!(None, resolve(&deps, "uumee"));
+
+    // the base kind exchange rates take the alias as a base denom
+    mock_custom_response(
+      &mut deps,
+      StructUmeeQuery::exchange_rates(ExchangeRatesParams::new("UMEE")),
+      Binary::from(br#"{"exchange_rates":[{"denom":"UMEE","amount":"0.0125"}]}"#.as_slice()),
+    );
+    let msg = QueryMsg::ExchangeRates(ExchangeRatesParams {
+      denom_kind: DenomKind::Base,
+      ..ExchangeRatesParams::new("UMEE")
+    });
+    let rates: ExchangeRatesResponse =
+      from_json(query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+    assert_eq!("UMEE", rates.exchange_rates[0].denom);
+
+    // the incentive queries name the uToken of the aliased denom
+    mock_chain_response(
+      &mut deps,
+      StructUmeeQuery::total_bonded(TotalBondedParams {
+        denom: "u/uumee".to_string(),
+      }),
+      &TotalBondedResponse {
+        bonded: coins(50, "u/uumee"),
+      },
+    );
+    for denom in ["u/UMEE", "UMEE"] {
+      let total_bonded = UmeeQuery::Incentive(UmeeQueryIncentive::TotalBonded(TotalBondedParams {
+        denom: denom.to_string(),
+      }));
+      let msg = QueryMsg::Umee(Box::new(total_bonded.clone()));
+      let value: TotalBondedResponse =
+        from_json(query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+      assert_eq!(coins(50, "u/uumee"), value.bonded);
+      let msg = QueryMsg::Raw {
+        query: total_bonded,
+      };
+      query(deps.as_ref(), mock_env(), msg).unwrap();
+    }
   }
 }
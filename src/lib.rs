@@ -1,3 +1,7 @@
 pub mod contract;
 pub mod msg;
+pub mod querier;
 pub mod state;
+
+#[cfg(test)]
+mod multitest;
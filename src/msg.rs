@@ -1,21 +1,163 @@
-use cosmwasm_std::{Addr, QueryRequest};
+use cosmwasm_std::{Addr, Coin, Decimal256, QueryRequest, Uint128};
 use cw_umee_types::{
-  ExchangeRatesParams, LeverageParametersParams, RegisteredTokensParams, StructUmeeQuery,
-  SupplyParams, UmeeMsg, UmeeQuery,
+  AccountBalancesResponse, AccountSummaryResponse, ExchangeRatesParams, LeverageParametersParams,
+  MsgTypes, RegisteredTokensParams, StructUmeeQuery, SupplyParams, UmeeMsg, UmeeMsgLeverage,
+  UmeeQuery,
 };
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
-pub struct InstantiateMsg {}
+pub struct InstantiateMsg {
+  // require_umee fails the instantiation if the chain does not answer
+  // the umee custom queries, defaults to true
+  pub require_umee: Option<bool>,
+  // chain_queries lists the kinds of requests the Chain query passes
+  // through, defaults to custom, bank and staking
+  pub chain_queries: Option<Vec<ChainQueryKind>>,
+  // check_denoms looks the denom up in the registered tokens before the
+  // per denom leverage queries to fail with a clear error, defaults to true
+  pub check_denoms: Option<bool>,
+}
+
+// ChainQueryKind is a kind of request the Chain query can pass through,
+// stargate is only accepted with the stargate-queries feature
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ChainQueryKind {
+  Custom,
+  Bank,
+  Staking,
+  Stargate,
+}
+
+// MigrateMsg carries no parameter, migrate only translates the state
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct MigrateMsg {}
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum ExecuteMsg {
   // updates the state owner
-  ChangeOwner { new_owner: Addr },
+  ChangeOwner {
+    new_owner: Addr,
+  },
   Umee(UmeeMsg),
+  // supplies the asset and sets the uTokens minted as the response data
   Supply(SupplyParams),
+  // withdraws the uTokens and sets the base tokens received as the response
+  // data, failing in the reply if less than min_received are returned
+  Withdraw(GuardedWithdrawParams),
+  // supplies the asset, collateralizes the minted uTokens and bonds
+  // them in the incentive module, in this order
+  SupplyAndBond(SupplyParams),
+  // decollateralizes and then collateralizes the given uTokens in one tx
+  Rebalance(RebalanceParams),
+  // repays the debt of a denom, decollateralizes and withdraws its uTokens,
+  // each step is sent from the reply of the previous one
+  Unwind(UnwindParams),
+  // supplies the coins and collateralizes the exact amount of uTokens
+  // minted, read from the reply of the supply
+  SupplyThenCollateralize(SupplyParams),
+  // delegates the price feeding of the validator operator to the feeder
+  // in one tx, only the owner can call it. The oracle module requires the
+  // operator signature, so the chain only accepts an operator whose account
+  // is the contract itself. The former set_feeder name is still accepted.
+  // The oracle module has no message to cast an initial vote on behalf of
+  // the feeder, the feeder starts voting at the next vote period
+  #[serde(alias = "set_feeder")]
+  SetupFeeder(SetupFeederParams),
+  // computes the total value locked and stores it with the current block
+  // height, to be read by the CachedTvl query
+  RefreshTvlCache {},
+  // supplies each entry with its own message, in the given order
+  SupplySchedule(Vec<SupplyParams>),
+  // collateralizes or decollateralizes the uTokens of a denom until the
+  // collateral reaches the target
+  AdjustCollateral(AdjustCollateralParams),
+  // sends a max withdraw for each denom supplied by the supplier, which
+  // has to be the contract itself as the leverage module withdraws the
+  // supply of the signer. The markets without liquidity are skipped
+  WithdrawAll {
+    supplier: Addr,
+  },
+  // sends the leverage messages in the exact given order within one tx,
+  // so a borrow listed after a collateralize sees the new collateral and
+  // any failing message reverts the whole batch. A batch can't move a
+  // position of a denom in both directions, like supplying and withdrawing
+  BatchLeverage(Vec<UmeeMsgLeverage>),
+  // collateralizes every uToken of the denom held by the borrower, which
+  // has to be the contract itself as the leverage module collateralizes
+  // the uTokens of the signer
+  CollateralizeAll {
+    borrower: Addr,
+    denom: String,
+  },
+  // repays the debt of the denom with the whole bank balance of the
+  // contract in that denom, the borrower has to be the contract itself as
+  // the leverage module repays the debt of the signer
+  RepayFromBalance {
+    borrower: Addr,
+    denom: String,
+  },
+  // executes the inner message and emits the memo as a client_memo
+  // attribute, for a client to find the events of its own actions
+  WithMemo {
+    memo: String,
+    inner: Box<ExecuteMsg>,
+  },
+  // stores the net value of the account summary of the address with the
+  // current block height, to be compared by the PositionPnl query. Only
+  // the address itself or the owner can call it
+  SnapshotPosition {
+    address: Addr,
+  },
+  // maps the alias to the denom, the messages and queries naming the alias
+  // as a denom are handled as naming the denom. Only the owner can call it,
+  // and a registered base denom can't be an alias
+  SetDenomAlias {
+    alias: String,
+    denom: String,
+  },
+}
+
+// GuardedWithdrawParams selects the uTokens to withdraw, min_received is
+// checked by the contract and never sent to the leverage module
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct GuardedWithdrawParams {
+  pub asset: Coin,
+  pub min_received: Option<Uint128>,
+}
+
+// RebalanceParams lists the uTokens to remove from and to add to the collateral
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct RebalanceParams {
+  pub decollateralize: Vec<Coin>,
+  pub collateralize: Vec<Coin>,
+}
+
+// UnwindParams selects the position to unwind, the account has to be the
+// contract itself since the leverage module only accepts its own signature
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct UnwindParams {
+  pub account: Addr,
+  pub repay_denom: String,
+}
+
+// AdjustCollateralParams sets the collateral of the base denom to target
+// uTokens, the borrower has to be the contract itself since the leverage
+// module only accepts its own signature
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct AdjustCollateralParams {
+  pub borrower: Addr,
+  pub denom: String,
+  pub target: Uint128,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct SetupFeederParams {
+  pub operator: Addr,
+  pub feeder: Addr,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
@@ -28,9 +170,75 @@ pub enum QueryMsg {
   // wraps to use the enums
   Umee(Box<UmeeQuery>),
   // it can also call an specific enum directly
+  #[serde(alias = "get_exchange_rate_base")]
   ExchangeRates(ExchangeRatesParams),
   RegisteredTokens(RegisteredTokensParams),
   LeverageParameters(LeverageParametersParams),
+  // BorrowLimitUsed returns the portion of the borrow limit used by an address
+  BorrowLimitUsed {
+    address: Addr,
+  },
+  // DenomMetadata returns the display information of a denom from the bank module
+  DenomMetadata {
+    denom: String,
+  },
+  // IsLiquidatable returns if the borrowed value of an address is above
+  // its liquidation threshold
+  IsLiquidatable {
+    address: Addr,
+  },
+  // TotalValueLocked returns the USD value supplied, borrowed and
+  // collateralized across every registered market
+  TotalValueLocked {},
+  // CollateralBreakdown returns the leverage collateral of an address
+  // along with the part of it bonded in the incentive module
+  CollateralBreakdown {
+    address: Addr,
+  },
+  // CachedTvl returns the total value locked stored by RefreshTvlCache,
+  // it fails if it was stored more than max_age_blocks ago
+  CachedTvl {
+    max_age_blocks: u64,
+  },
+  // Rates returns both exchange rates of a base denom, the oracle USD
+  // price and the leverage uToken exchange rate, rounded to round_to
+  // decimal places if set
+  Rates {
+    denom: String,
+    round_to: Option<u32>,
+  },
+  // Raw sends a native umee query and returns the chain response untouched,
+  // without decoding it into the response types of the crate
+  Raw {
+    query: UmeeQuery,
+  },
+  // SelfPosition returns the leverage balances and summary of the contract
+  // itself, for the contracts supplying or borrowing on their own account
+  SelfPosition {},
+  // PositionPnl returns the change of the account net value since the
+  // snapshot stored by SnapshotPosition, it fails without a snapshot
+  PositionPnl {
+    address: Addr,
+  },
+  // FullPosition returns the balances of an address in every market with
+  // the values and health factor of its account summary
+  FullPosition {
+    address: Addr,
+  },
+  // AssignedIds lists the assigned queries and msgs the contract can send
+  // to the umee native modules
+  AssignedIds {},
+  // ResolveAlias returns the denom mapped to the alias by SetDenomAlias
+  ResolveAlias {
+    alias: String,
+  },
+}
+
+// returns the denom of an alias, None if the alias was never set
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ResolveAliasResponse {
+  pub alias: String,
+  pub denom: Option<String>,
 }
 
 // returns the current contract owner
@@ -38,3 +246,138 @@ pub enum QueryMsg {
 pub struct OwnerResponse {
   pub owner: Addr,
 }
+
+// returns the borrow limit gauge of an address, all values
+// are computed from the same account summary
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct BorrowLimitUsedResponse {
+  // used is the borrowed value divided by the borrow limit
+  pub used: Decimal256,
+  // limit is the borrow limit value in USD
+  pub limit: Decimal256,
+  // available is the value still borrowable in USD
+  pub available: Decimal256,
+  // the USD values rendered with format_usd for display
+  pub limit_display: String,
+  pub available_display: String,
+}
+
+// returns the display information of a denom registered in the bank module
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct DenomMetadataResponse {
+  // display is the denom unit used to display the token
+  pub display: String,
+  // exponent is the exponent of the display denom unit
+  pub exponent: u32,
+  pub symbol: String,
+}
+
+// returns if an address can be liquidated, from its account summary
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct IsLiquidatableResponse {
+  pub liquidatable: bool,
+  // health_factor is the liquidation threshold divided by the borrowed
+  // value, below one the address is liquidatable. None without any debt
+  pub health_factor: Option<Decimal256>,
+}
+
+// data set by the reply of a max withdraw sent by the contract
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct MaxWithdrawReplyResponse {
+  // withdrawn is the amount of uTokens burned by the module
+  pub withdrawn: Coin,
+  // received is the amount of base tokens sent back to the contract
+  pub received: Coin,
+  // collateral_released is the amount of uTokens decollateralized by the
+  // module to withdraw them
+  pub collateral_released: Coin,
+}
+
+// returns the two exchange rates of a base denom, which are not
+// interchangeable: oracle_price values one display unit of the token
+// (e.g. UMEE, not uumee) in USD, while utoken_exchange_rate is the amount
+// of base tokens redeemed by one uToken and grows with the interest, it
+// says nothing about the USD value
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct RatesResponse {
+  // oracle_price is the USD price of the symbol denom from the oracle
+  pub oracle_price: Decimal256,
+  // utoken_exchange_rate is the base tokens per uToken of the leverage module
+  pub utoken_exchange_rate: Decimal256,
+}
+
+// returns the USD values summed over every registered market, along with
+// their display strings. The display strings default to empty for the
+// caches stored before they were added
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct TvlResponse {
+  pub total_supplied_value: Decimal256,
+  pub total_borrowed_value: Decimal256,
+  pub total_collateral_value: Decimal256,
+  #[serde(default)]
+  pub total_supplied_display: String,
+  #[serde(default)]
+  pub total_borrowed_display: String,
+  #[serde(default)]
+  pub total_collateral_display: String,
+}
+
+// returns the leverage position of the contract address
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct SelfPositionResponse {
+  pub balances: AccountBalancesResponse,
+  pub summary: AccountSummaryResponse,
+}
+
+// returns the leverage position of an address, the coins of every market
+// and the USD values of its account summary. health_factor is None
+// without any debt
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct FullPositionResponse {
+  pub supplied: Vec<Coin>,
+  pub collateral: Vec<Coin>,
+  pub borrowed: Vec<Coin>,
+  pub supplied_value: Decimal256,
+  pub collateral_value: Decimal256,
+  pub borrowed_value: Decimal256,
+  pub supplied_display: String,
+  pub collateral_display: String,
+  pub borrowed_display: String,
+  pub health_factor: Option<Decimal256>,
+}
+
+// returns the net value of an account, supplied minus borrowed value, now
+// and at the snapshot height. Only one of profit and loss is non zero
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PositionPnlResponse {
+  pub snapshot_height: u64,
+  pub snapshot_value: Decimal256,
+  pub current_value: Decimal256,
+  pub profit: Decimal256,
+  pub loss: Decimal256,
+  pub profit_display: String,
+  pub loss_display: String,
+}
+
+// returns the fields of StructUmeeQuery and the assigned msgs of
+// StructUmeeMsg with the field carrying their params
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct AssignedIdsResponse {
+  pub queries: Vec<String>,
+  pub messages: Vec<(MsgTypes, String)>,
+}
+
+// returns the total value locked stored at the height block
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct CachedTvlResponse {
+  pub height: u64,
+  pub tvl: TvlResponse,
+}
+
+// returns the uTokens collateralized by an address, the bonded ones earn
+// incentive rewards and are a part of the collateral
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct CollateralBreakdownResponse {
+  pub collateral: Vec<Coin>,
+  pub bonded: Vec<Coin>,
+}
@@ -0,0 +1,379 @@
+// multitest runs the contract in a cw-multi-test app, its custom module
+// models a minimal leverage market answering the umee messages and queries
+// like the native module does: every token is worth one uToken and the
+// borrows are limited to half of the amount supplied of the same denom.
+// Like on chain, the module answers with protobuf response data.
+use std::ops::Deref;
+
+use anyhow::{bail, Result as AnyResult};
+use cosmwasm_std::testing::{MockApi, MockStorage};
+use cosmwasm_std::{
+  coins, from_json, to_json_binary, Addr, Api, BankMsg, Binary, BlockInfo, Coin, CustomQuery, Deps,
+  DepsMut, Empty, Env, GovMsg, IbcMsg, IbcQuery, MessageInfo, Order, Querier, QuerierWrapper,
+  Reply, Response, StdResult, Storage, Uint128,
+};
+use cw_multi_test::{
+  App, AppBuilder, AppResponse, BankKeeper, BankSudo, ContractWrapper, CosmosRouter,
+  DistributionKeeper, Executor, FailingModule, Module, StakeKeeper, WasmKeeper,
+};
+use cw_storage_plus::Map;
+use cw_umee_types::error::ContractError;
+use cw_umee_types::proto::{encode_coin, encode_message};
+use cw_umee_types::{
+  utoken_denom, AccountBalancesParams, AccountBalancesResponse, BorrowParams, StructUmeeMsg,
+  StructUmeeQuery, SupplyMsgResponse, SupplyParams, UmeeMsg, UmeeMsgLeverage, UmeeQuery,
+  UmeeQueryLeverage,
+};
+use schemars::JsonSchema;
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+
+use crate::contract;
+use crate::msg::{ExecuteMsg, InstantiateMsg, QueryMsg};
+
+// LEVERAGE_ACCOUNT holds the base tokens supplied to the market
+const LEVERAGE_ACCOUNT: &str = "leverage";
+
+const SUPPLIED: Map<(&Addr, &str), Uint128> = Map::new("leverage_supplied");
+const BORROWED: Map<(&Addr, &str), Uint128> = Map::new("leverage_borrowed");
+
+type UmeeApp = App<
+  BankKeeper,
+  MockApi,
+  MockStorage,
+  LeverageModule,
+  WasmKeeper<StructUmeeMsg, StructUmeeQuery>,
+  StakeKeeper,
+  DistributionKeeper,
+  FailingModule<IbcMsg, IbcQuery, Empty>,
+  FailingModule<GovMsg, Empty, Empty>,
+>;
+
+// LeverageMsg decodes the messages of StructUmeeMsg handled by the module,
+// its fields are private to the types crate
+#[derive(Deserialize)]
+struct LeverageMsg {
+  supply: Option<SupplyParams>,
+  borrow: Option<BorrowParams>,
+}
+
+// LeverageQuery decodes the queries of StructUmeeQuery handled by the module
+#[derive(Deserialize)]
+struct LeverageQuery {
+  account_balances: Option<AccountBalancesParams>,
+}
+
+struct LeverageModule;
+
+impl LeverageModule {
+  fn balances(storage: &dyn Storage, map: Map<(&Addr, &str), Uint128>, addr: &Addr) -> Vec<Coin> {
+    map
+      .prefix(addr)
+      .range(storage, None, None, Order::Ascending)
+      .map(|item| item.map(|(denom, amount)| Coin { denom, amount }))
+      .collect::<Result<_, _>>()
+      .unwrap()
+  }
+}
+
+impl Module for LeverageModule {
+  type ExecT = StructUmeeMsg;
+  type QueryT = StructUmeeQuery;
+  type SudoT = Empty;
+
+  fn execute<ExecC, QueryC>(
+    &self,
+    api: &dyn Api,
+    storage: &mut dyn Storage,
+    router: &dyn CosmosRouter<ExecC = ExecC, QueryC = QueryC>,
+    block: &BlockInfo,
+    sender: Addr,
+    msg: StructUmeeMsg,
+  ) -> AnyResult<AppResponse>
+  where
+    ExecC: std::fmt::Debug + Clone + PartialEq + JsonSchema + DeserializeOwned + 'static,
+    QueryC: CustomQuery + DeserializeOwned + 'static,
+  {
+    let msg: LeverageMsg = from_json(to_json_binary(&msg)?)?;
+    if let Some(supply) = msg.supply {
+      let asset = supply.asset;
+      router.execute(
+        api,
+        storage,
+        block,
+        sender.clone(),
+        BankMsg::Send {
+          to_address: LEVERAGE_ACCOUNT.to_string(),
+          amount: vec![asset.clone()],
+        }
+        .into(),
+      )?;
+      let received = Coin::new(asset.amount.u128(), utoken_denom(&asset.denom));
+      router.sudo(
+        api,
+        storage,
+        block,
+        BankSudo::Mint {
+          to_address: sender.to_string(),
+          amount: vec![received.clone()],
+        }
+        .into(),
+      )?;
+      let supplied = SUPPLIED
+        .may_load(storage, (&sender, &asset.denom))?
+        .unwrap_or_default()
+        .checked_add(asset.amount)?;
+      SUPPLIED.save(storage, (&sender, &asset.denom), &supplied)?;
+
+      // MsgSupplyResponse holds the uTokens received in its first field
+      let mut data = vec![];
+      encode_message(1, &encode_coin(&received), &mut data);
+      return Ok(AppResponse {
+        events: vec![],
+        data: Some(Binary::from(data)),
+      });
+    }
+
+    if let Some(borrow) = msg.borrow {
+      let asset = borrow.asset;
+      let supplied = SUPPLIED
+        .may_load(storage, (&sender, &asset.denom))?
+        .unwrap_or_default();
+      let borrowed = BORROWED
+        .may_load(storage, (&sender, &asset.denom))?
+        .unwrap_or_default()
+        .checked_add(asset.amount)?;
+      if borrowed > supplied.multiply_ratio(1u128, 2u128) {
+        bail!("borrow of {} above the borrow limit", asset);
+      }
+      router.execute(
+        api,
+        storage,
+        block,
+        Addr::unchecked(LEVERAGE_ACCOUNT),
+        BankMsg::Send {
+          to_address: sender.to_string(),
+          amount: vec![asset.clone()],
+        }
+        .into(),
+      )?;
+      BORROWED.save(storage, (&sender, &asset.denom), &borrowed)?;
+
+      // MsgBorrowResponse has no field
+      return Ok(AppResponse {
+        events: vec![],
+        data: Some(Binary::default()),
+      });
+    }
+
+    bail!("only the supply and borrow leverage messages are modeled")
+  }
+
+  fn sudo<ExecC, QueryC>(
+    &self,
+    _api: &dyn Api,
+    _storage: &mut dyn Storage,
+    _router: &dyn CosmosRouter<ExecC = ExecC, QueryC = QueryC>,
+    _block: &BlockInfo,
+    _msg: Empty,
+  ) -> AnyResult<AppResponse>
+  where
+    ExecC: std::fmt::Debug + Clone + PartialEq + JsonSchema + DeserializeOwned + 'static,
+    QueryC: CustomQuery + DeserializeOwned + 'static,
+  {
+    bail!("the leverage module has no sudo")
+  }
+
+  fn query(
+    &self,
+    _api: &dyn Api,
+    storage: &dyn Storage,
+    _querier: &dyn Querier,
+    _block: &BlockInfo,
+    request: StructUmeeQuery,
+  ) -> AnyResult<Binary> {
+    let query: LeverageQuery = from_json(to_json_binary(&request)?)?;
+    match query.account_balances {
+      Some(params) => {
+        let address = params.address;
+        Ok(to_json_binary(&AccountBalancesResponse {
+          supplied: Self::balances(storage, SUPPLIED, &address),
+          collateral: vec![],
+          borrowed: Self::balances(storage, BORROWED, &address),
+        })?)
+      }
+      None => bail!("leverage query not modeled"),
+    }
+  }
+}
+
+// the contract entry points take Deps without custom query, the app runs
+// them with the StructUmeeQuery querier of the custom module
+fn instantiate(
+  deps: DepsMut<StructUmeeQuery>,
+  env: Env,
+  info: MessageInfo,
+  msg: InstantiateMsg,
+) -> Result<Response<StructUmeeMsg>, ContractError> {
+  let deps = DepsMut {
+    storage: deps.storage,
+    api: deps.api,
+    querier: QuerierWrapper::new(deps.querier.deref()),
+  };
+  let res = contract::instantiate(deps, env, info, msg)?;
+  Ok(Response::new().add_attributes(res.attributes))
+}
+
+fn execute(
+  deps: DepsMut<StructUmeeQuery>,
+  env: Env,
+  info: MessageInfo,
+  msg: ExecuteMsg,
+) -> Result<Response<StructUmeeMsg>, ContractError> {
+  let deps = DepsMut {
+    storage: deps.storage,
+    api: deps.api,
+    querier: QuerierWrapper::new(deps.querier.deref()),
+  };
+  contract::execute(deps, env, info, msg)
+}
+
+fn reply(
+  deps: DepsMut<StructUmeeQuery>,
+  env: Env,
+  msg: Reply,
+) -> Result<Response<StructUmeeMsg>, ContractError> {
+  let deps = DepsMut {
+    storage: deps.storage,
+    api: deps.api,
+    querier: QuerierWrapper::new(deps.querier.deref()),
+  };
+  contract::reply(deps, env, msg)
+}
+
+fn query(deps: Deps<StructUmeeQuery>, env: Env, msg: QueryMsg) -> StdResult<Binary> {
+  let deps = Deps {
+    storage: deps.storage,
+    api: deps.api,
+    querier: QuerierWrapper::new(deps.querier.deref()),
+  };
+  contract::query(deps, env, msg)
+}
+
+// setup instantiates the contract holding the funds
+fn setup(funds: &[Coin]) -> (UmeeApp, Addr) {
+  let owner = Addr::unchecked("owner");
+  let mut app = AppBuilder::new_custom()
+    .with_custom(LeverageModule)
+    .build(|_, _, _| {});
+  let code = ContractWrapper::new(execute, instantiate, query).with_reply(reply);
+  let code_id = app.store_code(Box::new(code));
+  let contract = app
+    .instantiate_contract(
+      code_id,
+      owner.clone(),
+      &InstantiateMsg {
+        require_umee: Some(false),
+        chain_queries: None,
+        check_denoms: None,
+      },
+      &[],
+      "umee-cosmwasm",
+      None,
+    )
+    .unwrap();
+  app
+    .sudo(
+      BankSudo::Mint {
+        to_address: contract.to_string(),
+        amount: funds.to_vec(),
+      }
+      .into(),
+    )
+    .unwrap();
+  (app, contract)
+}
+
+fn leverage(msg: UmeeMsgLeverage) -> ExecuteMsg {
+  ExecuteMsg::Umee(UmeeMsg::Leverage(msg))
+}
+
+fn account_balances(app: &UmeeApp, contract: &Addr) -> AccountBalancesResponse {
+  app
+    .wrap()
+    .query_wasm_smart(
+      contract,
+      &QueryMsg::Umee(Box::new(UmeeQuery::Leverage(
+        UmeeQueryLeverage::AccountBalances(AccountBalancesParams::new(contract.clone())),
+      ))),
+    )
+    .unwrap()
+}
+
+#[test]
+fn supply_then_borrow() {
+  let (mut app, contract) = setup(&coins(1000, "uumee"));
+  let owner = Addr::unchecked("owner");
+
+  let supply = ExecuteMsg::Supply(SupplyParams::new(Coin::new(500, "uumee")));
+  let res = app
+    .execute_contract(owner.clone(), contract.clone(), &supply, &[])
+    .unwrap();
+  let received: SupplyMsgResponse = from_json(res.data.unwrap()).unwrap();
+  assert_eq!(received.received, Coin::new(500, "u/uumee"));
+  // the passthrough is sent without reply, so without data
+  let supply = leverage(UmeeMsgLeverage::Supply(SupplyParams::new(Coin::new(
+    100, "uumee",
+  ))));
+  let res = app
+    .execute_contract(owner.clone(), contract.clone(), &supply, &[])
+    .unwrap();
+  assert_eq!(None, res.data);
+  assert_eq!(
+    app.wrap().query_all_balances(&contract).unwrap(),
+    vec![Coin::new(600, "u/uumee"), Coin::new(400, "uumee")]
+  );
+
+  let borrow = |amount: u128| {
+    leverage(UmeeMsgLeverage::Borrow(BorrowParams::new(Coin::new(
+      amount, "uumee",
+    ))))
+  };
+  app
+    .execute_contract(owner.clone(), contract.clone(), &borrow(200), &[])
+    .unwrap();
+  let err = app
+    .execute_contract(owner, contract.clone(), &borrow(101), &[])
+    .unwrap_err();
+  assert!(
+    err
+      .root_cause()
+      .to_string()
+      .contains("above the borrow limit"),
+    "{}",
+    err
+  );
+
+  let balances = account_balances(&app, &contract);
+  assert_eq!(balances.supplied, coins(600, "uumee"));
+  assert_eq!(balances.borrowed, coins(200, "uumee"));
+  assert_eq!(
+    app.wrap().query_balance(&contract, "uumee").unwrap().amount,
+    Uint128::new(600)
+  );
+}
+
+#[test]
+fn supply_above_the_balance() {
+  let (mut app, contract) = setup(&coins(100, "uumee"));
+
+  let supply = leverage(UmeeMsgLeverage::Supply(SupplyParams::new(Coin::new(
+    101, "uumee",
+  ))));
+  app
+    .execute_contract(Addr::unchecked("owner"), contract.clone(), &supply, &[])
+    .unwrap_err();
+
+  let balances = account_balances(&app, &contract);
+  assert!(balances.supplied.is_empty());
+}
@@ -0,0 +1,83 @@
+// querier abstracts the native leverage queries the composite queries are
+// built from, so their logic can run against canned responses as well as
+// against the chain.
+use cosmwasm_std::{Addr, Deps, StdResult};
+use cw_umee_types::{
+  AccountBalancesParams, AccountBalancesResponse, AccountSummaryParams, AccountSummaryResponse,
+  MarketSummaryParams, MarketSummaryResponse, RegisteredTokensParams, RegisteredTokensResponse,
+};
+
+use crate::contract::{
+  query_account_balances, query_account_summary, query_market_summary, query_registered_tokens,
+};
+
+// LeverageQuerier answers the native leverage queries, one method per query
+pub trait LeverageQuerier {
+  fn registered_tokens(&self) -> StdResult<RegisteredTokensResponse>;
+  fn market_summary(&self, denom: &str) -> StdResult<MarketSummaryResponse>;
+  fn account_balances(&self, address: &Addr) -> StdResult<AccountBalancesResponse>;
+  fn account_summary(&self, address: &Addr) -> StdResult<AccountSummaryResponse>;
+}
+
+// Deps sends the queries to the leverage module through the custom querier
+impl LeverageQuerier for Deps<'_> {
+  fn registered_tokens(&self) -> StdResult<RegisteredTokensResponse> {
+    query_registered_tokens(*self, RegisteredTokensParams {})
+  }
+
+  fn market_summary(&self, denom: &str) -> StdResult<MarketSummaryResponse> {
+    query_market_summary(*self, MarketSummaryParams::new(denom))
+  }
+
+  fn account_balances(&self, address: &Addr) -> StdResult<AccountBalancesResponse> {
+    query_account_balances(*self, AccountBalancesParams::new(address.clone()))
+  }
+
+  fn account_summary(&self, address: &Addr) -> StdResult<AccountSummaryResponse> {
+    query_account_summary(*self, AccountSummaryParams::new(address.clone()))
+  }
+}
+
+#[cfg(test)]
+pub use mock::MockLeverageQuerier;
+
+#[cfg(test)]
+mod mock {
+  use super::*;
+  use cosmwasm_std::StdError;
+  use std::collections::BTreeMap;
+
+  // MockLeverageQuerier answers with the canned responses, a query without
+  // one fails like a chain query would.
+  #[derive(Default)]
+  pub struct MockLeverageQuerier {
+    pub registered_tokens: RegisteredTokensResponse,
+    pub markets: BTreeMap<String, MarketSummaryResponse>,
+    pub balances: BTreeMap<Addr, AccountBalancesResponse>,
+    pub summaries: BTreeMap<Addr, AccountSummaryResponse>,
+  }
+
+  impl LeverageQuerier for MockLeverageQuerier {
+    fn registered_tokens(&self) -> StdResult<RegisteredTokensResponse> {
+      Ok(self.registered_tokens.clone())
+    }
+
+    fn market_summary(&self, denom: &str) -> StdResult<MarketSummaryResponse> {
+      canned(self.markets.get(denom), "market summary", denom)
+    }
+
+    fn account_balances(&self, address: &Addr) -> StdResult<AccountBalancesResponse> {
+      canned(self.balances.get(address), "account balances", address)
+    }
+
+    fn account_summary(&self, address: &Addr) -> StdResult<AccountSummaryResponse> {
+      canned(self.summaries.get(address), "account summary", address)
+    }
+  }
+
+  fn canned<T: Clone>(value: Option<&T>, query: &str, key: impl std::fmt::Display) -> StdResult<T> {
+    value
+      .cloned()
+      .ok_or_else(|| StdError::generic_err(format!("no canned {} for {}", query, key)))
+  }
+}
@@ -1,11 +1,108 @@
-use cosmwasm_std::Addr;
-use cw_storage_plus::Item;
+use crate::msg::{CachedTvlResponse, ChainQueryKind};
+use cosmwasm_std::{Addr, Coin, Decimal256, Uint128};
+use cw_storage_plus::{Item, Map};
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
+// State is the first state layout, stored without any version under the
+// "state" key. It is only read by migrate to translate it to StateV2
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct State {
   pub owner: Addr,
+  // umee_enabled is set at instantiation if the umee custom querier answered
+  #[serde(default)]
+  pub umee_enabled: bool,
+  // chain_queries are the request kinds the Chain query passes through
+  #[serde(default = "default_chain_queries")]
+  pub chain_queries: Vec<ChainQueryKind>,
+  // check_denoms enables the registered denom pre-check of the queries
+  #[serde(default = "default_check_denoms")]
+  pub check_denoms: bool,
 }
 
-pub const STATE: Item<State> = Item::new("state");
+// StateV2 is the current state layout, STATE_VERSION tells which layout
+// a deployment stores. New fields must have a serde default so the stored
+// StateV2 keeps decoding
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct StateV2 {
+  pub owner: Addr,
+  // pending_owner is the owner proposed by a two step ownership transfer
+  #[serde(default)]
+  pub pending_owner: Option<Addr>,
+  // umee_enabled is set at instantiation if the umee custom querier answered
+  #[serde(default)]
+  pub umee_enabled: bool,
+  // chain_queries are the request kinds the Chain query passes through
+  #[serde(default = "default_chain_queries")]
+  pub chain_queries: Vec<ChainQueryKind>,
+  // check_denoms enables the registered denom pre-check of the queries
+  #[serde(default = "default_check_denoms")]
+  pub check_denoms: bool,
+}
+
+impl From<State> for StateV2 {
+  fn from(state: State) -> Self {
+    StateV2 {
+      owner: state.owner,
+      pending_owner: None,
+      umee_enabled: state.umee_enabled,
+      chain_queries: state.chain_queries,
+      check_denoms: state.check_denoms,
+    }
+  }
+}
+
+pub fn default_chain_queries() -> Vec<ChainQueryKind> {
+  vec![
+    ChainQueryKind::Custom,
+    ChainQueryKind::Bank,
+    ChainQueryKind::Staking,
+  ]
+}
+
+pub fn default_check_denoms() -> bool {
+  true
+}
+
+// CURRENT_STATE_VERSION is the layout version of STATE
+pub const CURRENT_STATE_VERSION: u32 = 2;
+
+// STATE_VERSION holds the layout version of the stored state, deployments
+// without it store the first State layout
+pub const STATE_VERSION: Item<u32> = Item::new("state_version");
+
+// LEGACY_STATE is the first State layout, removed by the migration
+pub const LEGACY_STATE: Item<State> = Item::new("state");
+
+pub const STATE: Item<StateV2> = Item::new("state_v2");
+
+// UNWIND holds the denom of the unwind in progress between its replies
+pub const UNWIND: Item<String> = Item::new("unwind");
+
+// MAX_WITHDRAW_COLLATERAL holds the collateral of the contract before a max
+// withdraw, so its reply can tell how much was decollateralized
+pub const MAX_WITHDRAW_COLLATERAL: Item<Coin> = Item::new("max_withdraw_collateral");
+
+// WITHDRAW_MIN_RECEIVED holds the minimum base tokens a withdraw in
+// progress must return, checked by its reply
+pub const WITHDRAW_MIN_RECEIVED: Item<Uint128> = Item::new("withdraw_min_received");
+
+// REPLY_BALANCES holds the contract bank balances saved before a leverage
+// submessage, its reply measures the outcome from their change
+pub const REPLY_BALANCES: Item<Vec<Coin>> = Item::new("reply_balances");
+
+// LIQUIDATION_SNAPSHOT holds the borrower of a liquidation in progress with
+// its debt of the repayment denom and its collateral of the reward uTokens,
+// so the reply can tell how much was repaid and liquidated
+pub const LIQUIDATION_SNAPSHOT: Item<(Addr, Coin, Coin)> = Item::new("liquidation_snapshot");
+
+// TVL_CACHE holds the last total value locked computed by RefreshTvlCache
+pub const TVL_CACHE: Item<CachedTvlResponse> = Item::new("tvl_cache");
+
+// POSITION_SNAPSHOTS holds the block height and the net value of the account
+// summary of an address, stored by SnapshotPosition for the PositionPnl query
+pub const POSITION_SNAPSHOTS: Map<&Addr, (u64, Decimal256)> = Map::new("position_snapshots");
+
+// DENOM_ALIASES maps the aliases set by SetDenomAlias, like a symbol, to
+// the denom they stand for in the messages and queries
+pub const DENOM_ALIASES: Map<String, String> = Map::new("denom_aliases");